@@ -0,0 +1,62 @@
+//! Benchmarks for identifier searches over a 1000-node overlay.
+//!
+//! `search_by_id/local_step` measures the local step of a search hop: `search_by_id` scanning a
+//! node's own lookup table, level by level, for the closest identifier not past the target.
+//! `search_by_id/overlay` measures a whole search, relayed hop by hop across the overlay until it
+//! settles on the target.
+//!
+//! Nodes and networks are not part of the public API yet, so both are driven through the
+//! `bench` feature's overlay harness: `cargo bench --features bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rand::Rng;
+use skipgraph::bench::Overlay;
+use skipgraph::core::Identifier;
+
+const OVERLAY_SIZE: usize = 1000;
+
+fn random_overlay() -> Overlay {
+    let mut rng = rand::rng();
+    let ids = (0..OVERLAY_SIZE)
+        .map(|_| Identifier::from_bytes(&rng.random::<[u8; 32]>()).unwrap())
+        .collect();
+    Overlay::new(ids).expect("failed to build overlay")
+}
+
+fn bench_local_search_step(c: &mut Criterion) {
+    let overlay = random_overlay();
+    let ids = overlay.ids();
+    // the median node, whose lookup table reaches both sides of the overlay
+    let origin = OVERLAY_SIZE / 2;
+    let mut rng = rand::rng();
+
+    c.bench_function("search_by_id/local_step", |b| {
+        b.iter_batched(
+            || ids[rng.random_range(0..ids.len())],
+            |target| black_box(overlay.local_search(origin, target).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_overlay_search(c: &mut Criterion) {
+    let overlay = random_overlay();
+    let ids = overlay.ids();
+    let mut rng = rand::rng();
+
+    c.bench_function("search_by_id/overlay", |b| {
+        b.iter_batched(
+            || {
+                (
+                    rng.random_range(0..ids.len()),
+                    ids[rng.random_range(0..ids.len())],
+                )
+            },
+            |(origin, target)| black_box(overlay.search(origin, target).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, bench_local_search_step, bench_overlay_search);
+criterion_main!(benches);
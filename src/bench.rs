@@ -0,0 +1,83 @@
+//! Entry points for the criterion benchmarks under `benches/`. Nodes and networks are not part of
+//! the public API yet, so the benchmarks reach them through the overlay harness the in-crate tests
+//! use. Compiled only with the `bench` feature.
+
+use crate::core::model::direction::Direction;
+use crate::core::{IdSearchReq, Identifier, LOOKUP_TABLE_LEVELS};
+use crate::network::mock::hub::NetworkHub;
+use crate::network::Network;
+use crate::node::base_node::BaseNode;
+use crate::node::core::{BaseCore, Core};
+use crate::node::overlay::{build_overlay_with, network_search_with_stats};
+use crate::node::NodeView;
+use tracing::subscriber::NoSubscriber;
+use tracing::Span;
+
+/// Overlay is a fully wired skip graph whose nodes talk over a mock network hub.
+pub struct Overlay {
+    nodes: Vec<BaseNode>,
+    cores: Vec<BaseCore>,
+    // kept so that the nodes' networks stay registered for the lifetime of the overlay
+    _hub: NetworkHub,
+}
+
+impl Overlay {
+    /// Builds a fully wired skip graph over the given identifiers. Tracing is silenced for the
+    /// rest of the process, so that the benchmarks do not measure log formatting.
+    pub fn new(ids: Vec<Identifier>) -> anyhow::Result<Self> {
+        // installed before the harness tries to install its logging subscriber, which then backs off.
+        let _ = tracing::subscriber::set_global_default(NoSubscriber::default());
+
+        let hub = NetworkHub::new();
+        let factory_hub = hub.clone();
+        let nodes = build_overlay_with(ids, move |id| {
+            NetworkHub::new_mock_network(factory_hub.clone(), id)
+                .expect("failed to create mock network")
+                .clone_box()
+        })?;
+        // each core shares the lookup table of its node, so searching it is the local step of a
+        // search hop at that node.
+        let cores = nodes
+            .iter()
+            .map(|node| {
+                let identity = node.identity();
+                BaseCore::new(
+                    Span::none(),
+                    identity.id(),
+                    identity.mem_vec(),
+                    identity.address(),
+                    node.lookup_table(),
+                )
+            })
+            .collect();
+        Ok(Overlay {
+            nodes,
+            cores,
+            _hub: hub,
+        })
+    }
+
+    /// Returns the identifiers of the nodes, in ascending order.
+    pub fn ids(&self) -> Vec<Identifier> {
+        self.nodes.iter().map(|node| node.id()).collect()
+    }
+
+    /// Runs the local step of a search for `target` at the node with index `origin`, i.e.,
+    /// `search_by_id` over its own lookup table, and returns the next hop it picks.
+    pub fn local_search(&self, origin: usize, target: Identifier) -> anyhow::Result<Identifier> {
+        let core = &self.cores[origin];
+        let direction = if target > core.id() {
+            Direction::Right
+        } else {
+            Direction::Left
+        };
+        let req = IdSearchReq::try_new(core.id(), target, LOOKUP_TABLE_LEVELS - 1, direction)?;
+        Ok(core.search_by_id(req)?.result)
+    }
+
+    /// Searches from the node with index `origin` for `target` across the overlay, and returns
+    /// the identifier the search settled on together with the number of network hops it took.
+    pub fn search(&self, origin: usize, target: Identifier) -> anyhow::Result<(Identifier, usize)> {
+        network_search_with_stats(&self.nodes[origin], target)
+    }
+}
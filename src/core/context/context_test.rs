@@ -149,4 +149,28 @@ mod tests {
             }
         }
     }
+
+    /// this test ensures that a context created with a deadline cancels itself, and its children,
+    /// once the deadline elapses, and not before
+    #[tokio::test]
+    async fn test_deadline_cancellation() {
+        let ctx = IrrevocableContext::with_deadline(
+            &span_fixture(),
+            "test_deadline",
+            Duration::from_millis(50),
+        );
+        let child = ctx.child("test_deadline_child");
+
+        assert!(!ctx.is_cancelled());
+        assert!(!child.is_cancelled());
+
+        let ctx_clone = ctx.clone();
+        wait_until(move || ctx_clone.is_cancelled(), Duration::from_millis(500))
+            .await
+            .expect("context should be cancelled after its deadline");
+        assert!(
+            child.is_cancelled(),
+            "child should be cancelled with its parent"
+        );
+    }
 }
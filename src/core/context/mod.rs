@@ -13,6 +13,7 @@ mod context_test;
 
 use anyhow::Result;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_util::sync::CancellationToken;
 use tracing::Span;
 
@@ -44,6 +45,28 @@ impl IrrevocableContext {
         }
     }
 
+    /// Create a new root context that cancels itself once `after` has elapsed.
+    /// Children created from it are cancelled along with it, as with an explicit `cancel()`.
+    /// The deadline is driven by a tokio task, so this must be called from within a tokio runtime.
+    pub fn with_deadline(parent_span: &Span, tag: &str, after: Duration) -> Self {
+        let ctx = Self::new(parent_span, tag);
+
+        let deadline_ctx = ctx.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep(after) => {
+                    let _enter = deadline_ctx.inner.span.enter();
+                    tracing::trace!("context deadline of {:?} elapsed", after);
+                    deadline_ctx.cancel();
+                }
+                // already cancelled explicitly, nothing left to do
+                _ = deadline_ctx.cancelled() => {}
+            }
+        });
+
+        ctx
+    }
+
     /// Create a child context that inherits cancellation from the parent
     pub fn child(&self, tag: &str) -> Self {
         let span = tracing::span!(parent: &self.inner.span, tracing::Level::TRACE, "irrevocable_context_child", tag = tag);
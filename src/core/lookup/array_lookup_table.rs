@@ -1,37 +1,360 @@
-use crate::core::lookup::{LookupTable, LookupTableLevel};
+use crate::core::lookup::{LookupDiff, LookupTable, LookupTableLevel};
 use crate::core::model;
-use crate::core::model::direction::Direction;
+use crate::core::model::direction::{Direction, DirectionSet};
 use crate::core::model::identity::Identity;
+use crate::core::Identifier;
 use anyhow::anyhow;
 use parking_lot::RwLock;
+use std::collections::HashSet;
 use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
+#[cfg(feature = "timing")]
+use std::time::Instant;
 
 /// The number of levels in the lookup table is determined by the size of the identifier in bits (that is
 /// `IDENTIFIER_SIZE_BYTES * 8`).
 pub const LOOKUP_TABLE_LEVELS: usize = model::IDENTIFIER_SIZE_BYTES * 8;
 
-/// It is a 2D array of Identity, where the first dimension is the level and the second dimension is the direction.
+/// LookupTableObserver is notified of every entry change in an `ArrayLookupTable`, with the level, the direction,
+/// and the entry before and after the change.
+pub type LookupTableObserver =
+    Arc<dyn Fn(LookupTableLevel, Direction, Option<&Identity>, Option<&Identity>) + Send + Sync>;
+
+/// EntryChange is a single entry change as (level, direction, previous entry, current entry).
+type EntryChange = (
+    LookupTableLevel,
+    Direction,
+    Option<Identity>,
+    Option<Identity>,
+);
+
+/// Notifies the observer, if any, of every change. Mutators call it only after the table lock is released, so that
+/// the observer can read the table without deadlocking.
+fn notify(observer: Option<LookupTableObserver>, changes: impl IntoIterator<Item = EntryChange>) {
+    if let Some(observer) = observer {
+        for (level, direction, previous, current) in changes {
+            observer(level, direction, previous.as_ref(), current.as_ref());
+        }
+    }
+}
+
+/// OpTimer measures a lookup table operation, from its creation until it is dropped, and emits the elapsed time as
+/// a trace event when dropped. It is created before the table lock is taken, so the measurement covers waiting for
+/// the lock as well as holding it.
+#[cfg(feature = "timing")]
+struct OpTimer {
+    op: &'static str,
+    start: Instant,
+}
+
+#[cfg(feature = "timing")]
+impl OpTimer {
+    fn start(op: &'static str) -> Self {
+        OpTimer {
+            op,
+            start: Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "timing")]
+impl Drop for OpTimer {
+    fn drop(&mut self) {
+        tracing::trace!(
+            op = self.op,
+            elapsed_us = self.start.elapsed().as_micros() as u64,
+            "lookup table operation timed"
+        );
+    }
+}
+
+/// Without the `timing` feature, OpTimer is an empty placeholder that compiles away.
+#[cfg(not(feature = "timing"))]
+struct OpTimer;
+
+#[cfg(not(feature = "timing"))]
+impl OpTimer {
+    #[inline(always)]
+    fn start(_op: &'static str) -> Self {
+        OpTimer
+    }
+}
+
+/// It is a 2D array of Identity, where the first dimension is the direction and the second dimension is the level.
+/// The directions held are given by a `DirectionSet`, `{Left, Right}` by default; accessing a direction outside
+/// the set is an error.
 /// Uses Arc for shallow cloning - cloned instances share the same underlying data.
 pub struct ArrayLookupTable {
     inner: Arc<RwLock<InnerArrayLookupTable>>,
 }
 
 struct InnerArrayLookupTable {
-    left: Vec<Option<Identity>>,
-    right: Vec<Option<Identity>>,
+    directions: DirectionSet,
+    // entries[slot][level], where slot is the position of the direction in `directions`.
+    entries: Vec<Vec<Option<Identity>>>,
+    observer: Option<LookupTableObserver>,
+    // when set, updates violating level monotonicity are rejected.
+    strict_monotonicity: bool,
+}
+
+impl InnerArrayLookupTable {
+    /// Returns the entries of the given direction, or an error if it is not in the direction set.
+    fn direction_entries(&self, direction: Direction) -> anyhow::Result<&Vec<Option<Identity>>> {
+        let slot = self.slot(direction)?;
+        Ok(&self.entries[slot])
+    }
+
+    fn direction_entries_mut(
+        &mut self,
+        direction: Direction,
+    ) -> anyhow::Result<&mut Vec<Option<Identity>>> {
+        let slot = self.slot(direction)?;
+        Ok(&mut self.entries[slot])
+    }
+
+    /// Checks that placing `identity` at `level` in `direction` keeps the direction monotonic across levels: right
+    /// neighbors never decrease and left neighbors never increase as the level grows, since a higher level links
+    /// a subset of the nodes linked below it.
+    fn check_monotonicity(
+        &self,
+        identity: &Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        let id = identity.id();
+        for (other_level, entry) in self.direction_entries(direction)?.iter().enumerate() {
+            let Some(other) = entry else {
+                continue;
+            };
+            // the identifier that must be the smaller of the two, for right neighbors.
+            let (lower, upper) = if other_level < level {
+                (other.id(), id)
+            } else if other_level > level {
+                (id, other.id())
+            } else {
+                continue;
+            };
+            let monotonic = match direction {
+                Direction::Right => lower <= upper,
+                Direction::Left => lower >= upper,
+            };
+            if !monotonic {
+                return Err(anyhow!(
+                    "update at level {} in direction {} to {} breaks monotonicity with level {} entry {}",
+                    level,
+                    direction,
+                    id,
+                    other_level,
+                    other.id()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn slot(&self, direction: Direction) -> anyhow::Result<usize> {
+        self.directions
+            .slot(direction)
+            .ok_or_else(|| anyhow!("direction {} is not held by this lookup table", direction))
+    }
 }
 
 impl ArrayLookupTable {
-    /// Create a new empty LookupTable instance.
+    /// Create a new empty LookupTable instance holding the default `{Left, Right}` directions.
     pub fn new() -> ArrayLookupTable {
+        Self::with_direction_set(DirectionSet::default())
+    }
+
+    /// Create a new empty LookupTable instance holding the given set of directions.
+    pub fn with_direction_set(directions: DirectionSet) -> ArrayLookupTable {
         ArrayLookupTable {
             inner: Arc::new(RwLock::new(InnerArrayLookupTable {
-                left: vec![None; LOOKUP_TABLE_LEVELS],
-                right: vec![None; LOOKUP_TABLE_LEVELS],
+                directions,
+                entries: vec![vec![None; LOOKUP_TABLE_LEVELS]; directions.len()],
+                observer: None,
+                strict_monotonicity: false,
             })),
         }
     }
+
+    /// Sets the observer notified after every `update_entry` and `remove_entry`, replacing any previous one.
+    /// The observer is shared by all shallow clones of this table, and is invoked after the table lock is
+    /// released, so it may safely access the table itself.
+    pub fn set_observer(&self, observer: LookupTableObserver) {
+        self.inner.write().observer = Some(observer);
+    }
+
+    /// Enables or disables strict mode, shared by all shallow clones of this table. In strict mode, `update_entry`
+    /// rejects with an error any update after which the right neighbors would decrease, or the left neighbors
+    /// increase, from one level to a higher one. This catches wiring bugs, e.g., in join, at the faulty update.
+    /// Lenient mode, the default, accepts every update.
+    pub fn set_strict_monotonicity(&self, enabled: bool) {
+        self.inner.write().strict_monotonicity = enabled;
+    }
+
+    /// Returns the set of directions held by this lookup table.
+    pub fn direction_set(&self) -> DirectionSet {
+        self.inner.read().directions
+    }
+
+    /// Returns a snapshot of every populated entry as (level, direction, identity) tuples, taken under a single
+    /// read lock. Entries are ordered by level, then by direction.
+    pub fn entries(&self) -> Vec<(usize, Direction, Identity)> {
+        let _timer = OpTimer::start("entries");
+        let inner = self.inner.read();
+
+        let mut entries = Vec::new();
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            for (direction, slot_entries) in
+                inner.directions.directions().iter().zip(&inner.entries)
+            {
+                if let Some(identity) = slot_entries[level] {
+                    entries.push((level, *direction, identity));
+                }
+            }
+        }
+        entries
+    }
+
+    /// Renders the populated levels as a compact table, one row per level under a header row, with a column per held
+    /// direction showing the first 8 hex characters of the entry's identifier, or `-` if the entry is empty. Levels
+    /// without any entry are omitted, so an empty table renders as the header alone.
+    pub fn format_table(&self) -> String {
+        let inner = self.inner.read();
+        let mut header = String::from("level");
+        for direction in inner.directions.directions() {
+            header.push_str(&format!(" | {:<8}", direction.to_string().to_lowercase()));
+        }
+        let mut rows = vec![header];
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            if inner.entries.iter().all(|entries| entries[level].is_none()) {
+                continue;
+            }
+            let mut row = format!("{level:>5}");
+            for entries in &inner.entries {
+                let cell = match entries[level] {
+                    Some(identity) => identity.id().to_string()[..8].to_string(),
+                    None => "-".to_string(),
+                };
+                row.push_str(&format!(" | {cell:<8}"));
+            }
+            rows.push(row);
+        }
+        rows.iter()
+            .map(|row| row.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns up to `k` distinct neighbors sampled uniformly from the populated entries of every level and direction,
+    /// e.g., to pick gossip peers. The entries are scanned under a single read lock with reservoir sampling; a
+    /// neighbor held at several positions counts once, so the distinct neighbors seen are tracked in a set sized to
+    /// the populated entries, and the sample to at most that many. If the table holds at most `k` distinct
+    /// neighbors, all of them are returned. The order of the sample is unspecified.
+    pub fn sample_neighbors(
+        &self,
+        k: usize,
+        rng: &mut impl rand::Rng,
+    ) -> anyhow::Result<Vec<Identity>> {
+        let _timer = OpTimer::start("sample_neighbors");
+        let inner = self.inner.read();
+
+        let occupied = inner.entries.iter().flatten().flatten().count();
+        if k == 0 || occupied == 0 {
+            return Ok(Vec::new());
+        }
+        let mut seen = HashSet::with_capacity(occupied);
+        let mut sample = Vec::with_capacity(k.min(occupied));
+        for identity in inner.entries.iter().flatten().flatten() {
+            if !seen.insert(identity.id()) {
+                continue;
+            }
+            // the i-th distinct neighbor (0-based) replaces a sampled one with probability k / (i + 1).
+            let i = seen.len() - 1;
+            if i < k {
+                sample.push(*identity);
+            } else {
+                let j = rng.random_range(0..=i);
+                if j < k {
+                    sample[j] = *identity;
+                }
+            }
+        }
+        Ok(sample)
+    }
+
+    /// Invokes `f` with the entry at the given level and direction under the write lock, and returns what `f`
+    /// returns, so that a read-modify-write of the entry, e.g., replacing it only if some condition holds, cannot
+    /// race with other updates. In strict mode, a change breaking level monotonicity is undone and reported as an
+    /// error. If `f` changes the entry, the observer is notified after the lock is released; `f` itself must not
+    /// access the table, as the lock is held while it runs.
+    pub fn with_entry_mut<R>(
+        &self,
+        level: LookupTableLevel,
+        direction: Direction,
+        f: impl FnOnce(&mut Option<Identity>) -> R,
+    ) -> anyhow::Result<R> {
+        if level >= LOOKUP_TABLE_LEVELS {
+            return Err(anyhow!(
+                "position is larger than the max lookup table entry number: {}",
+                level
+            ));
+        }
+
+        let (result, previous_entry, current_entry, observer) = {
+            let _timer = OpTimer::start("with_entry_mut");
+            let mut inner = self.inner.write();
+            let slot = &mut inner.direction_entries_mut(direction)?[level];
+            let previous_entry = *slot;
+            let result = f(slot);
+            let current_entry = *slot;
+
+            if inner.strict_monotonicity && current_entry != previous_entry {
+                if let Some(identity) = current_entry {
+                    if let Err(e) = inner.check_monotonicity(&identity, level, direction) {
+                        inner.direction_entries_mut(direction)?[level] = previous_entry;
+                        return Err(e);
+                    }
+                }
+            }
+            (
+                result,
+                previous_entry,
+                current_entry,
+                inner.observer.clone(),
+            )
+        };
+
+        if current_entry != previous_entry {
+            tracing::trace!(
+                "lookup table entry modified in place: level {}, direction {}, from {:?} to {:?}",
+                level,
+                direction,
+                previous_entry,
+                current_entry
+            );
+
+            notify(
+                observer,
+                [(level, direction, previous_entry, current_entry)],
+            );
+        }
+        Ok(result)
+    }
+
+    /// Returns the populated entries of the given direction as (level, identity) tuples.
+    fn neighbors(&self, direction: Direction) -> anyhow::Result<Vec<(usize, Identity)>> {
+        let _timer = OpTimer::start("neighbors");
+        let inner = self.inner.read();
+
+        let mut neighbors = Vec::new();
+        for (level, entry) in inner.direction_entries(direction)?.iter().enumerate() {
+            if let Some(identity) = entry {
+                neighbors.push((level, *identity));
+            }
+        }
+        Ok(neighbors)
+    }
 }
 
 impl Clone for ArrayLookupTable {
@@ -43,17 +366,37 @@ impl Clone for ArrayLookupTable {
     }
 }
 
+/// `{:?}` prints only the populated levels, while `{:#?}` prints every level, including empty ones.
 impl Debug for ArrayLookupTable {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let inner = self.inner.read();
+        let verbose = f.alternate();
         writeln!(f, "ArrayLookupTable: {{")?;
-        for (i, (l, r)) in inner.left.iter().zip(inner.right.iter()).enumerate() {
-            writeln!(f, "Level: {i}, Left: {l:?}, Right: {r:?}")?;
+        for i in 0..LOOKUP_TABLE_LEVELS {
+            if verbose || inner.entries.iter().any(|entries| entries[i].is_some()) {
+                write!(f, "Level: {i}")?;
+                for (direction, entries) in inner.directions.directions().iter().zip(&inner.entries)
+                {
+                    write!(f, ", {direction}: {:?}", entries[i])?;
+                }
+                writeln!(f)?;
+            }
         }
         write!(f, "}}")
     }
 }
 
+/// Iterates over a snapshot of the populated entries, as returned by `entries`; changes made to the table during
+/// the iteration are not observed.
+impl IntoIterator for &ArrayLookupTable {
+    type Item = (usize, Direction, Identity);
+    type IntoIter = std::vec::IntoIter<(usize, Direction, Identity)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries().into_iter()
+    }
+}
+
 impl Default for ArrayLookupTable {
     fn default() -> Self {
         Self::new()
@@ -75,16 +418,15 @@ impl LookupTable for ArrayLookupTable {
             ));
         }
 
-        let mut inner = self.inner.write();
-
-        match direction {
-            Direction::Left => {
-                inner.left[level] = Some(identity);
-            }
-            Direction::Right => {
-                inner.right[level] = Some(identity);
+        let (previous_entry, observer) = {
+            let _timer = OpTimer::start("update_entry");
+            let mut inner = self.inner.write();
+            if inner.strict_monotonicity {
+                inner.check_monotonicity(&identity, level, direction)?;
             }
-        }
+            let previous_entry = inner.direction_entries_mut(direction)?[level].replace(identity);
+            (previous_entry, inner.observer.clone())
+        };
 
         // Log the update operation
         tracing::trace!(
@@ -93,6 +435,11 @@ impl LookupTable for ArrayLookupTable {
             direction,
             identity.id()
         );
+
+        notify(
+            observer,
+            [(level, direction, previous_entry, Some(identity))],
+        );
         Ok(())
     }
 
@@ -105,23 +452,14 @@ impl LookupTable for ArrayLookupTable {
             ));
         }
 
-        let mut inner = self.inner.write();
-
-        // Record the current entry before removing it for logging
-        let current_entry = match direction {
-            Direction::Left => inner.left[level],
-            Direction::Right => inner.right[level],
+        // Take the current entry out, keeping it for logging and notification
+        let (current_entry, observer) = {
+            let _timer = OpTimer::start("remove_entry");
+            let mut inner = self.inner.write();
+            let current_entry = inner.direction_entries_mut(direction)?[level].take();
+            (current_entry, inner.observer.clone())
         };
 
-        match direction {
-            Direction::Left => {
-                inner.left[level] = None;
-            }
-            Direction::Right => {
-                inner.right[level] = None;
-            }
-        }
-
         // Log the remove operation
         tracing::trace!(
             "removed entry at level {} in direction {:?}: {:?}",
@@ -129,6 +467,8 @@ impl LookupTable for ArrayLookupTable {
             direction,
             current_entry
         );
+
+        notify(observer, [(level, direction, current_entry, None)]);
         Ok(())
     }
 
@@ -148,11 +488,9 @@ impl LookupTable for ArrayLookupTable {
             ));
         }
 
-        let inner = self.inner.read();
-
-        let entry = match direction {
-            Direction::Left => inner.left[level],
-            Direction::Right => inner.right[level],
+        let entry = {
+            let _timer = OpTimer::start("get_entry");
+            self.inner.read().direction_entries(direction)?[level]
         };
 
         // Log the get operation
@@ -170,26 +508,18 @@ impl LookupTable for ArrayLookupTable {
     /// This is a deep comparison of the entries in the table.
     /// Returns true if the entries are equal, false otherwise.
     fn equal(&self, other: &dyn LookupTable) -> bool {
-        // iterates over the levels and compares the entries in the left and right directions
+        // iterates over the levels and compares the entries in every held direction
         let inner = self.inner.read();
         for l in 0..LOOKUP_TABLE_LEVELS {
-            // Check if the left entry is equal
-            if let Ok(other_entry) = other.get_entry(l, Direction::Left) {
-                if inner.left[l].as_ref() != other_entry.as_ref() {
+            for (direction, entries) in inner.directions.directions().iter().zip(&inner.entries) {
+                if let Ok(other_entry) = other.get_entry(l, *direction) {
+                    if entries[l].as_ref() != other_entry.as_ref() {
+                        return false;
+                    }
+                } else {
+                    // if retrieving the entry fails on the other table, return false
                     return false;
                 }
-            } else {
-                // if retrieving the entry fails on the other table, return false
-                return false;
-            }
-
-            if let Ok(other_entry) = other.get_entry(l, Direction::Right) {
-                if inner.right[l].as_ref() != other_entry.as_ref() {
-                    return false;
-                }
-            } else {
-                // if retrieving the entry fails on the other table, return false
-                return false;
             }
         }
         true
@@ -197,33 +527,281 @@ impl LookupTable for ArrayLookupTable {
 
     /// Returns the list of left neighbors at the current node as a vector of tuples containing the level and identity.
     fn left_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
+        self.neighbors(Direction::Left)
+    }
+
+    /// Returns the list of right neighbors at the current node as a vector of tuples containing the level and identity.
+    fn right_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
+        self.neighbors(Direction::Right)
+    }
+
+    /// Returns the number of populated entries in the given direction, counted under a single read lock.
+    fn count_by_direction(&self, direction: Direction) -> anyhow::Result<usize> {
+        let _timer = OpTimer::start("count_by_direction");
         let inner = self.inner.read();
+        Ok(inner
+            .direction_entries(direction)?
+            .iter()
+            .filter(|entry| entry.is_some())
+            .count())
+    }
 
-        let mut neighbors = Vec::new();
-        for (level, entry) in inner.left.iter().enumerate() {
-            if let Some(identity) = entry {
-                neighbors.push((level, *identity));
+    /// Returns every (level, direction) position whose entry has the given identifier, searched under a
+    /// single read lock. Positions are ordered by level, then by direction.
+    fn find_entry_by_id(&self, id: &Identifier) -> anyhow::Result<Vec<(usize, Direction)>> {
+        let _timer = OpTimer::start("find_entry_by_id");
+        let inner = self.inner.read();
+
+        let mut positions = Vec::new();
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            for (direction, entries) in inner.directions.directions().iter().zip(&inner.entries) {
+                if entries[level].is_some_and(|identity| identity.id() == *id) {
+                    positions.push((level, *direction));
+                }
             }
         }
-        Ok(neighbors)
+        Ok(positions)
     }
 
-    /// Returns the list of right neighbors at the current node as a vector of tuples containing the level and identity.
-    fn right_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
+    /// Returns the (successor, predecessor) pair, i.e., the level-0 right and left neighbors: the smallest
+    /// identifier to the right and the largest to the left. Both are read under one read lock. Returns an error if
+    /// the table does not hold both directions.
+    fn successor_predecessor(&self) -> anyhow::Result<(Option<Identity>, Option<Identity>)> {
+        let _timer = OpTimer::start("successor_predecessor");
+        // both neighbors are read under one lock, so no update can land between the two reads.
         let inner = self.inner.read();
+        Ok((
+            inner.direction_entries(Direction::Right)?[0],
+            inner.direction_entries(Direction::Left)?[0],
+        ))
+    }
 
-        let mut neighbors = Vec::new();
-        for (level, entry) in inner.right.iter().enumerate() {
-            if let Some(identity) = entry {
-                neighbors.push((level, *identity));
+    /// Swaps the left and right entries under a single write lock, so no reader sees a half-swapped table. The
+    /// swap bypasses the strict monotonicity check, as it is meant to repair a table whose orientation is wrong.
+    /// The observer, if any, is notified of every position whose entry changed, after the lock is released.
+    /// Returns an error if the table does not hold both directions.
+    fn swap_directions(&self) -> anyhow::Result<()> {
+        let (changes, observer) = {
+            let _timer = OpTimer::start("swap_directions");
+            let mut inner = self.inner.write();
+            let left = inner.slot(Direction::Left)?;
+            let right = inner.slot(Direction::Right)?;
+            inner.entries.swap(left, right);
+
+            // after the swap, each direction holds what the other held before.
+            let mut changes = Vec::new();
+            for level in 0..LOOKUP_TABLE_LEVELS {
+                let (new_left, new_right) =
+                    (inner.entries[left][level], inner.entries[right][level]);
+                if new_left != new_right {
+                    changes.push((level, Direction::Left, new_right, new_left));
+                    changes.push((level, Direction::Right, new_left, new_right));
+                }
+            }
+            (changes, inner.observer.clone())
+        };
+
+        tracing::trace!("swapped left and right entries");
+
+        notify(observer, changes);
+        Ok(())
+    }
+
+    /// Replaces the entry under a single write lock, through `with_entry_mut`.
+    fn replace_if_closer(
+        &self,
+        owner: Identifier,
+        identity: Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<bool> {
+        // whether `a` lies strictly closer to the owner than `b` on the direction side.
+        let closer = |a: Identifier, b: Identifier| match direction {
+            Direction::Left => a > b,
+            Direction::Right => a < b,
+        };
+        if !closer(owner, identity.id()) {
+            // the identity is the owner itself, or lies on the other side of it.
+            return Ok(false);
+        }
+        self.with_entry_mut(level, direction, |entry| match entry {
+            Some(current) if !closer(identity.id(), current.id()) => false,
+            _ => {
+                *entry = Some(identity);
+                true
+            }
+        })
+    }
+
+    /// Resets every entry to `None` under a single write lock, so no reader sees a partially cleared table. The
+    /// observer, if any, is notified of every entry removed, after the lock is released.
+    fn clear(&self) -> anyhow::Result<()> {
+        let (removed, observer) = {
+            let _timer = OpTimer::start("clear");
+            let mut inner = self.inner.write();
+            let directions = inner.directions;
+
+            let mut removed = Vec::new();
+            for (direction, entries) in directions.directions().iter().zip(&mut inner.entries) {
+                for (level, entry) in entries.iter_mut().enumerate() {
+                    if let Some(identity) = entry.take() {
+                        removed.push((level, *direction, identity));
+                    }
+                }
+            }
+            (removed, inner.observer.clone())
+        };
+
+        tracing::trace!("cleared {} lookup table entries", removed.len());
+
+        notify(
+            observer,
+            removed
+                .iter()
+                .map(|&(level, direction, identity)| (level, direction, Some(identity), None)),
+        );
+        Ok(())
+    }
+
+    /// Removes the matching entries under a single write lock, so no reader sees the identifier at some positions
+    /// but not others. The observer, if any, is notified of every entry removed, after the lock is released.
+    fn remove_matching(
+        &self,
+        id: &Identifier,
+    ) -> anyhow::Result<Vec<(LookupTableLevel, Direction)>> {
+        let (removed, observer) = {
+            let _timer = OpTimer::start("remove_matching");
+            let mut inner = self.inner.write();
+            let directions = inner.directions;
+
+            let mut removed = Vec::new();
+            for level in 0..LOOKUP_TABLE_LEVELS {
+                for (direction, entries) in directions.directions().iter().zip(&mut inner.entries) {
+                    if entries[level].is_some_and(|identity| identity.id() == *id) {
+                        let identity = entries[level].take().expect("entry was just matched");
+                        removed.push((level, *direction, identity));
+                    }
+                }
+            }
+            (removed, inner.observer.clone())
+        };
+
+        tracing::trace!("removed {} lookup table entries of {}", removed.len(), id);
+
+        notify(
+            observer,
+            removed
+                .iter()
+                .map(|&(level, direction, identity)| (level, direction, Some(identity), None)),
+        );
+        Ok(removed
+            .into_iter()
+            .map(|(level, direction, _)| (level, direction))
+            .collect())
+    }
+
+    /// Compares the tables position by position under a single read lock of this table. Changes
+    /// are ordered by level, then by direction.
+    fn diff(&self, other: &dyn LookupTable) -> anyhow::Result<Vec<LookupDiff>> {
+        let _timer = OpTimer::start("diff");
+        let inner = self.inner.read();
+
+        let mut diff = Vec::new();
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            for (direction, entries) in inner.directions.directions().iter().zip(&inner.entries) {
+                let direction = *direction;
+                let change = match (entries[level], other.get_entry(level, direction)?) {
+                    (None, Some(identity)) => LookupDiff::Added {
+                        level,
+                        direction,
+                        identity,
+                    },
+                    (Some(identity), None) => LookupDiff::Removed {
+                        level,
+                        direction,
+                        identity,
+                    },
+                    (Some(from), Some(to)) if from != to => LookupDiff::Changed {
+                        level,
+                        direction,
+                        from,
+                        to,
+                    },
+                    _ => continue,
+                };
+                diff.push(change);
             }
         }
-        Ok(neighbors)
+        Ok(diff)
+    }
+
+    /// Applies the changes under a single write lock, so no reader sees a partially applied diff. Every change is
+    /// checked against the entry at its position before any is applied; in strict mode, a diff whose result breaks
+    /// level monotonicity is rejected as a whole. The observer, if any, is notified of every change, in order, after
+    /// the lock is released.
+    fn apply_diff(&self, diff: &[LookupDiff]) -> anyhow::Result<()> {
+        let observer = {
+            let _timer = OpTimer::start("apply_diff");
+            let mut inner = self.inner.write();
+
+            let mut entries = inner.entries.clone();
+            for change in diff {
+                let (level, direction) = change.position();
+                if level >= LOOKUP_TABLE_LEVELS {
+                    return Err(anyhow!(
+                        "position is larger than the max lookup table entry number: {}",
+                        level
+                    ));
+                }
+                let (previous, current) = change.entries();
+                let entry = &mut entries[inner.slot(direction)?][level];
+                if *entry != previous {
+                    return Err(anyhow!(
+                        "diff expects {:?} at level {} in direction {}, but the table holds {:?}",
+                        previous,
+                        level,
+                        direction,
+                        entry
+                    ));
+                }
+                *entry = current;
+            }
+
+            let previous_entries = std::mem::replace(&mut inner.entries, entries);
+            if inner.strict_monotonicity {
+                for change in diff {
+                    let (level, direction) = change.position();
+                    if let (_, Some(identity)) = change.entries() {
+                        if let Err(e) = inner.check_monotonicity(&identity, level, direction) {
+                            inner.entries = previous_entries;
+                            return Err(e);
+                        }
+                    }
+                }
+            }
+            inner.observer.clone()
+        };
+
+        tracing::trace!("applied diff of {} changes", diff.len());
+
+        notify(
+            observer,
+            diff.iter().map(|change| {
+                let (level, direction) = change.position();
+                let (previous, current) = change.entries();
+                (level, direction, previous, current)
+            }),
+        );
+        Ok(())
     }
 
     fn clone_box(&self) -> Box<dyn LookupTable> {
         Box::new(self.clone())
     }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 impl PartialEq for ArrayLookupTable {
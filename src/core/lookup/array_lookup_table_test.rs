@@ -1,10 +1,13 @@
 #[cfg(test)]
 mod tests {
-    use crate::core::model::direction::Direction;
+    use crate::core::model::direction::{Direction, DirectionSet, DIRECTIONS};
     use crate::core::model::identity::Identity;
     use crate::core::testutil::fixtures::*;
-    use crate::core::{model, ArrayLookupTable, LookupTable, LOOKUP_TABLE_LEVELS};
+    use crate::core::{
+        model, ArrayLookupTable, Identifier, LookupDiff, LookupTable, LOOKUP_TABLE_LEVELS,
+    };
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     #[test]
     /// A new lookup table should be empty.
@@ -366,6 +369,263 @@ mod tests {
         }
     }
 
+    /// Tests that populated entries are counted per direction.
+    #[test]
+    fn test_count_by_direction() {
+        let lt = random_lookup_table(10);
+        assert_eq!(lt.count_by_direction(Direction::Left).unwrap(), 10);
+        assert_eq!(lt.count_by_direction(Direction::Right).unwrap(), 10);
+
+        lt.remove_entry(3, Direction::Left).unwrap();
+        assert_eq!(lt.count_by_direction(Direction::Left).unwrap(), 9);
+        assert_eq!(lt.count_by_direction(Direction::Right).unwrap(), 10);
+
+        let empty = ArrayLookupTable::new();
+        assert_eq!(empty.count_by_direction(Direction::Left).unwrap(), 0);
+        assert_eq!(empty.count_by_direction(Direction::Right).unwrap(), 0);
+    }
+
+    /// Tests that the compact debug output lists only populated levels, while the alternate
+    /// (verbose) output lists every level.
+    #[test]
+    fn test_debug_hides_empty_levels() {
+        let lt = ArrayLookupTable::new();
+        let left = random_identity();
+        let right = random_identity();
+        lt.update_entry(left, 3, Direction::Left).unwrap();
+        lt.update_entry(right, 7, Direction::Right).unwrap();
+
+        let compact = format!("{lt:?}");
+        assert_eq!(compact.matches("Level: ").count(), 2);
+        assert!(compact.contains(&format!("Level: 3, Left: Some({left:?}), Right: None")));
+        assert!(compact.contains(&format!("Level: 7, Left: None, Right: Some({right:?})")));
+        assert!(!compact.contains("Level: 0,"));
+
+        let verbose = format!("{lt:#?}");
+        assert_eq!(verbose.matches("Level: ").count(), LOOKUP_TABLE_LEVELS);
+        assert!(verbose.contains("Level: 0, Left: None, Right: None"));
+    }
+
+    /// Tests that a table built over an explicit two-direction set behaves exactly like the
+    /// default table: the same operations yield equal tables, neighbors, and debug output.
+    #[test]
+    fn test_explicit_two_direction_set_matches_default() {
+        let default_lt = ArrayLookupTable::new();
+        let explicit_lt =
+            ArrayLookupTable::with_direction_set(DirectionSet::new(&DIRECTIONS).unwrap());
+        assert_eq!(explicit_lt.direction_set(), default_lt.direction_set());
+
+        for level in 0..10 {
+            for direction in DIRECTIONS {
+                let identity = random_identity();
+                default_lt.update_entry(identity, level, direction).unwrap();
+                explicit_lt
+                    .update_entry(identity, level, direction)
+                    .unwrap();
+            }
+        }
+        default_lt.remove_entry(4, Direction::Left).unwrap();
+        explicit_lt.remove_entry(4, Direction::Left).unwrap();
+
+        assert!(default_lt.equal(&explicit_lt));
+        assert!(explicit_lt.equal(&default_lt));
+        assert_eq!(
+            default_lt.left_neighbors().unwrap(),
+            explicit_lt.left_neighbors().unwrap()
+        );
+        assert_eq!(
+            default_lt.right_neighbors().unwrap(),
+            explicit_lt.right_neighbors().unwrap()
+        );
+        assert_eq!(format!("{default_lt:?}"), format!("{explicit_lt:?}"));
+    }
+
+    /// Tests that accessing a direction outside the table's direction set is an error.
+    #[test]
+    fn test_direction_outside_set() {
+        let lt =
+            ArrayLookupTable::with_direction_set(DirectionSet::new(&[Direction::Left]).unwrap());
+        lt.update_entry(random_identity(), 0, Direction::Left)
+            .unwrap();
+
+        assert!(lt
+            .update_entry(random_identity(), 0, Direction::Right)
+            .is_err());
+        assert!(lt.get_entry(0, Direction::Right).is_err());
+        assert!(lt.remove_entry(0, Direction::Right).is_err());
+        assert!(lt.right_neighbors().is_err());
+        assert_eq!(lt.left_neighbors().unwrap().len(), 1);
+    }
+
+    /// Tests that the observer sees the old and new entry of every change, including an
+    /// override and a removal, and that it can read the table from within the callback.
+    #[test]
+    fn test_observer_sees_old_and_new_entries() {
+        type Change = (usize, Direction, Option<Identity>, Option<Identity>);
+
+        let lt = ArrayLookupTable::new();
+        let changes: Arc<parking_lot::Mutex<Vec<Change>>> =
+            Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let changes_clone = changes.clone();
+        let lt_clone = lt.clone();
+        lt.set_observer(Arc::new(move |level, direction, old, new| {
+            // reading the table here would deadlock if the observer ran under the table lock.
+            assert_eq!(lt_clone.get_entry(level, direction).unwrap().as_ref(), new);
+            changes_clone
+                .lock()
+                .push((level, direction, old.copied(), new.copied()));
+        }));
+
+        let first = random_identity();
+        let second = random_identity();
+        lt.update_entry(first, 2, Direction::Left).unwrap();
+        lt.update_entry(second, 2, Direction::Left).unwrap();
+        lt.remove_entry(2, Direction::Left).unwrap();
+
+        assert_eq!(
+            *changes.lock(),
+            vec![
+                (2, Direction::Left, None, Some(first)),
+                (2, Direction::Left, Some(first), Some(second)),
+                (2, Direction::Left, Some(second), None),
+            ]
+        );
+    }
+
+    /// Tests that the reverse lookup finds every position holding an identifier, and nothing
+    /// for an identifier that is not in the table.
+    #[test]
+    fn test_find_entry_by_id() {
+        let lt = random_lookup_table(10);
+        let identity = random_identity();
+        lt.update_entry(identity, 2, Direction::Left).unwrap();
+        lt.update_entry(identity, 7, Direction::Right).unwrap();
+
+        assert_eq!(
+            lt.find_entry_by_id(&identity.id()).unwrap(),
+            vec![(2, Direction::Left), (7, Direction::Right)]
+        );
+        assert!(lt
+            .find_entry_by_id(&random_identifier())
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Tests that iterating over a table by reference yields its populated entries, ordered by level then by
+    /// direction, and that the iteration is a snapshot unaffected by later updates.
+    #[test]
+    fn test_into_iterator_matches_entries() {
+        let lt = ArrayLookupTable::new();
+        let identities = random_identities(3);
+        lt.update_entry(identities[0], 5, Direction::Right).unwrap();
+        lt.update_entry(identities[1], 5, Direction::Left).unwrap();
+        lt.update_entry(identities[2], 1, Direction::Right).unwrap();
+
+        let expected = vec![
+            (1, Direction::Right, identities[2]),
+            (5, Direction::Left, identities[1]),
+            (5, Direction::Right, identities[0]),
+        ];
+        assert_eq!(lt.entries(), expected);
+
+        let mut collected = Vec::new();
+        for (level, direction, identity) in &lt {
+            // updates made while iterating are not observed by the iteration.
+            lt.update_entry(random_identity(), 0, Direction::Left)
+                .unwrap();
+            collected.push((level, direction, identity));
+        }
+        assert_eq!(collected, expected);
+        assert_eq!(lt.entries().len(), expected.len() + 1);
+    }
+
+    /// Tests that, with the `timing` feature, an update emits a trace event carrying the elapsed time of the
+    /// operation.
+    #[cfg(feature = "timing")]
+    #[test]
+    fn test_timing_records_update_entry_latency() {
+        use crate::core::testutil::capture::capture_events;
+
+        let lt = ArrayLookupTable::new();
+        let ((), events) = capture_events(|| {
+            lt.update_entry(random_identity(), 0, Direction::Left)
+                .unwrap();
+        });
+
+        let timed = events
+            .iter()
+            .find(|event| event.fields.get("op").map(String::as_str) == Some("\"update_entry\""))
+            .expect("update_entry should emit a timing event");
+        let elapsed_us = timed
+            .fields
+            .get("elapsed_us")
+            .expect("timing event should carry elapsed_us");
+        assert!(
+            elapsed_us.parse::<u64>().is_ok(),
+            "elapsed_us is not a number: {elapsed_us}"
+        );
+    }
+
+    /// Tests that an update breaking level monotonicity is rejected, and leaves the table
+    /// unchanged, in strict mode, while lenient mode accepts it.
+    #[test]
+    fn test_strict_monotonicity() {
+        let ids = random_sorted_identifiers(4);
+        let identity =
+            |i: usize| Identity::new(ids[i], random_membership_vector(), random_address());
+
+        let strict = ArrayLookupTable::new();
+        strict.set_strict_monotonicity(true);
+        // right neighbors grow with the level, left neighbors shrink.
+        strict
+            .update_entry(identity(1), 0, Direction::Right)
+            .unwrap();
+        strict
+            .update_entry(identity(2), 1, Direction::Right)
+            .unwrap();
+        strict
+            .update_entry(identity(1), 0, Direction::Left)
+            .unwrap();
+        strict
+            .update_entry(identity(0), 1, Direction::Left)
+            .unwrap();
+
+        // a smaller right neighbor above level 1, and a larger left neighbor above level 1.
+        assert!(strict
+            .update_entry(identity(0), 2, Direction::Right)
+            .is_err());
+        assert!(strict
+            .update_entry(identity(2), 2, Direction::Left)
+            .is_err());
+        // replacing a lower level with an entry beyond its upper neighbor.
+        assert!(strict
+            .update_entry(identity(3), 0, Direction::Right)
+            .is_err());
+        assert_eq!(strict.get_entry(2, Direction::Right).unwrap(), None);
+        assert_eq!(
+            strict
+                .get_entry(0, Direction::Right)
+                .unwrap()
+                .map(|e| e.id()),
+            Some(ids[1])
+        );
+
+        let lenient = ArrayLookupTable::new();
+        lenient
+            .update_entry(identity(1), 0, Direction::Right)
+            .unwrap();
+        lenient
+            .update_entry(identity(0), 2, Direction::Right)
+            .unwrap();
+        assert_eq!(
+            lenient
+                .get_entry(2, Direction::Right)
+                .unwrap()
+                .map(|e| e.id()),
+            Some(ids[0])
+        );
+    }
+
     /// Tests that cloning ArrayLookupTable creates a shallow copy.
     /// Changes made to one instance should be visible in the cloned instance.
     #[test]
@@ -439,4 +699,337 @@ mod tests {
         assert_eq!(lt2.get_entry(2, Direction::Left).unwrap(), Some(id3));
         assert_eq!(lt3.get_entry(2, Direction::Left).unwrap(), Some(id3));
     }
+
+    /// Tests that swapping directions exchanges the left and right entries at every level, leaving levels
+    /// populated in only one direction in the other, and that a table without both directions refuses to swap.
+    #[test]
+    fn test_swap_directions() {
+        let lt = ArrayLookupTable::new();
+        let (left_0, right_0, left_only, right_only) = (
+            random_identity(),
+            random_identity(),
+            random_identity(),
+            random_identity(),
+        );
+        lt.update_entry(left_0, 0, Direction::Left).unwrap();
+        lt.update_entry(right_0, 0, Direction::Right).unwrap();
+        lt.update_entry(left_only, 1, Direction::Left).unwrap();
+        lt.update_entry(right_only, 2, Direction::Right).unwrap();
+
+        lt.swap_directions().unwrap();
+        assert_eq!(lt.get_entry(0, Direction::Left).unwrap(), Some(right_0));
+        assert_eq!(lt.get_entry(0, Direction::Right).unwrap(), Some(left_0));
+        assert_eq!(lt.get_entry(1, Direction::Left).unwrap(), None);
+        assert_eq!(lt.get_entry(1, Direction::Right).unwrap(), Some(left_only));
+        assert_eq!(lt.get_entry(2, Direction::Left).unwrap(), Some(right_only));
+        assert_eq!(lt.get_entry(2, Direction::Right).unwrap(), None);
+        assert_eq!(lt.count_by_direction(Direction::Left).unwrap(), 2);
+        assert_eq!(lt.count_by_direction(Direction::Right).unwrap(), 2);
+
+        let left_only_table =
+            ArrayLookupTable::with_direction_set(DirectionSet::new(&[Direction::Left]).unwrap());
+        assert!(left_only_table.swap_directions().is_err());
+    }
+
+    /// Tests that `with_entry_mut` hands the closure the entry in place: a conditional clear returns the previous
+    /// entry and empties the slot only when the condition holds, and an out-of-range level is rejected.
+    #[test]
+    fn test_with_entry_mut_conditional_clear() {
+        let lt = ArrayLookupTable::new();
+        let identity = random_identity();
+        lt.update_entry(identity, 3, Direction::Right).unwrap();
+
+        // clears the entry only if it holds the given identifier, returning what was cleared.
+        let clear_if = |id| {
+            lt.with_entry_mut(3, Direction::Right, |entry| {
+                if entry.is_some_and(|e| e.id() == id) {
+                    entry.take()
+                } else {
+                    None
+                }
+            })
+            .unwrap()
+        };
+
+        assert_eq!(clear_if(random_identity().id()), None);
+        assert_eq!(lt.get_entry(3, Direction::Right).unwrap(), Some(identity));
+
+        assert_eq!(clear_if(identity.id()), Some(identity));
+        assert_eq!(lt.get_entry(3, Direction::Right).unwrap(), None);
+
+        assert!(lt
+            .with_entry_mut(LOOKUP_TABLE_LEVELS, Direction::Right, |_| ())
+            .is_err());
+    }
+
+    /// Tests that `replace_if_closer` fills an empty entry, replaces an entry only with a node closer to the owner,
+    /// and never installs a node on the wrong side of the owner, in either direction.
+    #[test]
+    fn test_replace_if_closer() {
+        let ids = random_sorted_identifiers(5);
+        let owner = ids[2];
+        let identity =
+            |i: usize| Identity::new(ids[i], random_membership_vector(), random_address());
+        let lt = ArrayLookupTable::new();
+
+        // right of the owner: ids[3] is closer than ids[4].
+        assert!(lt
+            .replace_if_closer(owner, identity(4), 1, Direction::Right)
+            .unwrap());
+        assert!(lt
+            .replace_if_closer(owner, identity(3), 1, Direction::Right)
+            .unwrap());
+        assert!(!lt
+            .replace_if_closer(owner, identity(4), 1, Direction::Right)
+            .unwrap());
+        assert_eq!(
+            lt.get_entry(1, Direction::Right).unwrap().unwrap().id(),
+            ids[3]
+        );
+
+        // left of the owner: ids[1] is closer than ids[0].
+        assert!(lt
+            .replace_if_closer(owner, identity(0), 1, Direction::Left)
+            .unwrap());
+        assert!(lt
+            .replace_if_closer(owner, identity(1), 1, Direction::Left)
+            .unwrap());
+        assert!(!lt
+            .replace_if_closer(owner, identity(0), 1, Direction::Left)
+            .unwrap());
+        assert_eq!(
+            lt.get_entry(1, Direction::Left).unwrap().unwrap().id(),
+            ids[1]
+        );
+
+        // wrong side of the owner, or the owner itself.
+        assert!(!lt
+            .replace_if_closer(owner, identity(1), 2, Direction::Right)
+            .unwrap());
+        assert!(!lt
+            .replace_if_closer(owner, identity(3), 2, Direction::Left)
+            .unwrap());
+        assert!(!lt
+            .replace_if_closer(owner, identity(2), 2, Direction::Left)
+            .unwrap());
+        assert_eq!(lt.get_entry(2, Direction::Right).unwrap(), None);
+        assert_eq!(lt.get_entry(2, Direction::Left).unwrap(), None);
+    }
+
+    /// Tests that a boxed `dyn LookupTable` downcasts back to the `ArrayLookupTable` it was created from, sharing
+    /// its entries, and not to any other type.
+    #[test]
+    fn test_as_any_downcast() {
+        let boxed: Box<dyn LookupTable> = Box::new(ArrayLookupTable::new());
+        let identity = random_identity();
+
+        let table = boxed
+            .as_any()
+            .downcast_ref::<ArrayLookupTable>()
+            .expect("should downcast to ArrayLookupTable");
+        let previous = table
+            .with_entry_mut(3, Direction::Left, |entry| entry.replace(identity))
+            .unwrap();
+        assert_eq!(previous, None);
+        assert_eq!(boxed.get_entry(3, Direction::Left).unwrap(), Some(identity));
+
+        assert!(boxed.as_any().downcast_ref::<String>().is_none());
+    }
+
+    /// Tests that `successor_predecessor` returns the level-0 right and left neighbors, i.e., the maximum and zero
+    /// extremes of `random_lookup_table_with_extremes`, and nothing for an empty table.
+    #[test]
+    fn test_successor_predecessor() {
+        let lt = random_lookup_table_with_extremes(LOOKUP_TABLE_LEVELS);
+        let (successor, predecessor) = lt.successor_predecessor().unwrap();
+        assert!(successor.unwrap().id().is_max());
+        assert!(predecessor.unwrap().id().is_zero());
+
+        let empty = ArrayLookupTable::new();
+        assert_eq!(empty.successor_predecessor().unwrap(), (None, None));
+    }
+
+    /// Tests that applying the diff of two tables to the first makes it equal to the second, covering added, removed,
+    /// and changed entries, and that a diff not matching the table is rejected without changing it.
+    #[test]
+    fn test_apply_diff() {
+        let a = ArrayLookupTable::new();
+        let b = ArrayLookupTable::new();
+        let (kept, removed, changed, added) = (
+            random_identity(),
+            random_identity(),
+            random_identity(),
+            random_identity(),
+        );
+        a.update_entry(kept, 0, Direction::Left).unwrap();
+        b.update_entry(kept, 0, Direction::Left).unwrap();
+        a.update_entry(removed, 1, Direction::Right).unwrap();
+        a.update_entry(changed, 2, Direction::Left).unwrap();
+        b.update_entry(added, 2, Direction::Left).unwrap();
+        b.update_entry(added, 3, Direction::Right).unwrap();
+
+        let diff = a.diff(&b).unwrap();
+        assert_eq!(
+            diff,
+            vec![
+                LookupDiff::Removed {
+                    level: 1,
+                    direction: Direction::Right,
+                    identity: removed
+                },
+                LookupDiff::Changed {
+                    level: 2,
+                    direction: Direction::Left,
+                    from: changed,
+                    to: added
+                },
+                LookupDiff::Added {
+                    level: 3,
+                    direction: Direction::Right,
+                    identity: added
+                },
+            ]
+        );
+
+        a.apply_diff(&diff).unwrap();
+        assert!(a.equal(&b));
+        assert!(a.diff(&b).unwrap().is_empty());
+
+        // the diff no longer matches the table, so replaying it again fails and changes nothing.
+        let before = a.entries();
+        assert!(a.apply_diff(&diff).is_err());
+        assert_eq!(a.entries(), before);
+    }
+
+    /// Tests that `format_table` renders a row for each populated level only, with the 8-character identifier prefix
+    /// of each entry and `-` for an empty entry, and only the header for an empty table.
+    #[test]
+    fn test_format_table() {
+        let lt = ArrayLookupTable::new();
+        assert_eq!(lt.format_table(), "level | left     | right");
+
+        let (left, right) = (random_identity(), random_identity());
+        lt.update_entry(left, 0, Direction::Left).unwrap();
+        lt.update_entry(right, 0, Direction::Right).unwrap();
+        lt.update_entry(right, 5, Direction::Right).unwrap();
+
+        let formatted = lt.format_table();
+        let rows: Vec<&str> = formatted.lines().collect();
+        assert_eq!(rows.len(), 3, "expected a header and two populated levels");
+        let left_prefix = &left.id().to_string()[..8];
+        let right_prefix = &right.id().to_string()[..8];
+        assert_eq!(rows[1], format!("    0 | {left_prefix} | {right_prefix}"));
+        assert_eq!(rows[2], format!("    5 | -        | {right_prefix}"));
+        assert!(!formatted.contains(&right.id().to_string()));
+    }
+
+    /// Tests that `sample_neighbors` returns every distinct neighbor once when `k` exceeds the occupancy, however
+    /// large `k` is, and otherwise exactly `k` distinct neighbors drawn from the populated entries.
+    #[test]
+    fn test_sample_neighbors() {
+        let lt = random_lookup_table(8);
+        // a neighbor held at two positions is sampled at most once.
+        let repeated = lt.get_entry(0, Direction::Left).unwrap().unwrap();
+        lt.update_entry(repeated, 10, Direction::Left).unwrap();
+        let populated: Vec<Identifier> = lt
+            .entries()
+            .iter()
+            .map(|(_, _, identity)| identity.id())
+            .collect();
+        let mut rng = rand::rng();
+
+        assert!(ArrayLookupTable::new()
+            .sample_neighbors(3, &mut rng)
+            .unwrap()
+            .is_empty());
+        assert!(lt.sample_neighbors(0, &mut rng).unwrap().is_empty());
+
+        // the sample is sized to the populated entries, not to `k`.
+        let mut all: Vec<Identifier> = lt
+            .sample_neighbors(usize::MAX, &mut rng)
+            .unwrap()
+            .iter()
+            .map(Identity::id)
+            .collect();
+        all.sort();
+        let mut distinct = populated.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(all, distinct);
+
+        for _ in 0..50 {
+            let mut sample: Vec<Identifier> = lt
+                .sample_neighbors(5, &mut rng)
+                .unwrap()
+                .iter()
+                .map(Identity::id)
+                .collect();
+            assert!(sample.iter().all(|id| populated.contains(id)));
+            sample.sort();
+            sample.dedup();
+            assert_eq!(sample.len(), 5);
+        }
+    }
+
+    /// Tests that `remove_matching` removes every entry of the identifier, reports their positions ordered by level,
+    /// then by direction, leaves other entries in place, and notifies the observer of each removal.
+    #[test]
+    fn test_remove_matching() {
+        let lt = random_lookup_table(4);
+        let leaver = random_identity();
+        lt.update_entry(leaver, 2, Direction::Right).unwrap();
+        lt.update_entry(leaver, 2, Direction::Left).unwrap();
+        lt.update_entry(leaver, 6, Direction::Right).unwrap();
+        let kept = lt.get_entry(0, Direction::Left).unwrap();
+
+        let notified = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let notified_clone = notified.clone();
+        lt.set_observer(Arc::new(move |level, direction, previous, current| {
+            assert_eq!(current, None);
+            notified_clone
+                .lock()
+                .unwrap()
+                .push((level, direction, previous.map(Identity::id)));
+        }));
+
+        let removed = lt.remove_matching(&leaver.id()).unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                (2, Direction::Left),
+                (2, Direction::Right),
+                (6, Direction::Right)
+            ]
+        );
+        assert!(lt.find_entry_by_id(&leaver.id()).unwrap().is_empty());
+        assert_eq!(lt.get_entry(0, Direction::Left).unwrap(), kept);
+        assert_eq!(notified.lock().unwrap().len(), 3);
+        assert!(notified
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|(_, _, previous)| *previous == Some(leaver.id())));
+
+        assert!(lt.remove_matching(&leaver.id()).unwrap().is_empty());
+    }
+
+    /// Tests that clearing a populated table leaves no entry in either direction, so every `get_entry` returns
+    /// `None`, and that the clear is visible through a shallow clone.
+    #[test]
+    fn test_clear() {
+        let lt = random_lookup_table(LOOKUP_TABLE_LEVELS);
+        let clone = lt.clone();
+        assert!(!lt.entries().is_empty());
+
+        lt.clear().unwrap();
+        for direction in DIRECTIONS {
+            assert_eq!(clone.count_by_direction(direction).unwrap(), 0);
+        }
+        assert!(clone.entries().is_empty());
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            for direction in DIRECTIONS {
+                assert_eq!(clone.get_entry(level, direction).unwrap(), None);
+            }
+        }
+    }
 }
@@ -1,8 +1,81 @@
 use crate::core::model::direction::Direction;
 use crate::core::model::identity::Identity;
+use crate::core::{Identifier, MembershipVector, LOOKUP_TABLE_LEVELS};
 
 pub mod array_lookup_table;
 mod array_lookup_table_test;
+pub mod persistence;
+
+/// LookupTableError enumerates lookup table failures that callers may need to tell apart from
+/// other errors. It is returned wrapped in `anyhow::Error`, and recovered by downcasting.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LookupTableError {
+    /// The lock guarding the table was poisoned by a writer that panicked while holding it.
+    /// `recovered` is the entry as it was last written before the panic, which may be
+    /// inconsistent with the rest of the table.
+    Poisoned { recovered: Option<Identity> },
+}
+
+impl std::fmt::Display for LookupTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupTableError::Poisoned { .. } => write!(f, "lookup table lock is poisoned"),
+        }
+    }
+}
+
+impl std::error::Error for LookupTableError {}
+
+/// LookupDiff is a change to one (level, direction) position of a lookup table, as computed by
+/// `LookupTable::diff` and replayed by `LookupTable::apply_diff`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LookupDiff {
+    /// The position is empty and becomes `identity`.
+    Added {
+        level: LookupTableLevel,
+        direction: Direction,
+        identity: Identity,
+    },
+    /// The position holds `identity` and becomes empty.
+    Removed {
+        level: LookupTableLevel,
+        direction: Direction,
+        identity: Identity,
+    },
+    /// The position holds `from` and becomes `to`.
+    Changed {
+        level: LookupTableLevel,
+        direction: Direction,
+        from: Identity,
+        to: Identity,
+    },
+}
+
+impl LookupDiff {
+    /// Returns the (level, direction) position the change applies to.
+    pub fn position(&self) -> (LookupTableLevel, Direction) {
+        match *self {
+            LookupDiff::Added {
+                level, direction, ..
+            }
+            | LookupDiff::Removed {
+                level, direction, ..
+            }
+            | LookupDiff::Changed {
+                level, direction, ..
+            } => (level, direction),
+        }
+    }
+
+    /// Returns the entry at the position before and after the change.
+    pub fn entries(&self) -> (Option<Identity>, Option<Identity>) {
+        match *self {
+            LookupDiff::Added { identity, .. } => (None, Some(identity)),
+            LookupDiff::Removed { identity, .. } => (Some(identity), None),
+            LookupDiff::Changed { from, to, .. } => (Some(from), Some(to)),
+        }
+    }
+}
 
 /// LookupTableLevel represents level of a lookup table. entry in the table.
 pub type LookupTableLevel = usize;
@@ -38,6 +111,50 @@ pub trait LookupTable: Send + Sync {
     /// Returns the list of right neighbors at the current node as a vector of tuples containing the level and identity.
     fn right_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>>;
 
+    /// Returns the number of populated entries in the given direction, across all levels.
+    fn count_by_direction(&self, direction: Direction) -> anyhow::Result<usize>;
+
+    /// Returns every (level, direction) position whose entry has the given identifier.
+    fn find_entry_by_id(&self, id: &Identifier) -> anyhow::Result<Vec<(usize, Direction)>>;
+
+    /// Moves every left entry to the right at the same level and vice versa, atomically.
+    fn swap_directions(&self) -> anyhow::Result<()>;
+
+    /// Returns the node's immediate successor and predecessor, i.e., its level-0 right and left
+    /// neighbors, read together so that they are consistent with each other.
+    fn successor_predecessor(&self) -> anyhow::Result<(Option<Identity>, Option<Identity>)>;
+
+    /// Sets the entry at the given level and direction to `identity` if it is a closer neighbor
+    /// of the node `owner` than the current entry, atomically: `identity` must lie on the
+    /// `direction` side of `owner`, and the entry must be empty or lie farther from `owner`.
+    /// Returns true if the entry was replaced.
+    fn replace_if_closer(
+        &self,
+        owner: Identifier,
+        identity: Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<bool>;
+
+    /// Removes every entry of the table, atomically.
+    fn clear(&self) -> anyhow::Result<()>;
+
+    /// Removes every entry holding the given identifier, atomically, e.g., once that node left the
+    /// overlay. Returns the positions the identifier was removed from, ordered by level, then by
+    /// direction; empty if the table did not hold it.
+    fn remove_matching(
+        &self,
+        id: &Identifier,
+    ) -> anyhow::Result<Vec<(LookupTableLevel, Direction)>>;
+
+    /// Returns the changes that turn this table into `other`, one per position whose entries
+    /// differ, over the directions held by this table.
+    fn diff(&self, other: &dyn LookupTable) -> anyhow::Result<Vec<LookupDiff>>;
+
+    /// Applies the changes in `diff` atomically: either every change is applied, or, if any change
+    /// does not match the entry currently at its position, none is and an error is returned.
+    fn apply_diff(&self, diff: &[LookupDiff]) -> anyhow::Result<()>;
+
     /// Creates a shallow copy of this lookup table.
     ///
     /// Implementations should ensure that cloned instances share the same underlying data
@@ -45,6 +162,11 @@ pub trait LookupTable: Send + Sync {
     /// visible in all cloned instances. This is the standard cloning behavior for all
     /// LookupTable implementations.
     fn clone_box(&self) -> Box<dyn LookupTable>;
+
+    /// Returns this lookup table as `Any`, so that a `dyn LookupTable` can be downcast back to
+    /// its concrete type (e.g., with `downcast_ref::<ArrayLookupTable>()`) to reach methods
+    /// outside this trait.
+    fn as_any(&self) -> &dyn std::any::Any;
 }
 
 impl PartialEq for dyn LookupTable {
@@ -58,3 +180,48 @@ impl Clone for Box<dyn LookupTable> {
         self.clone_box()
     }
 }
+
+/// Returns the highest lookup table level at which a node with membership vector `me` can have a
+/// neighbor, given the membership vectors of the other nodes: a level-`l` neighbor shares at least
+/// `l` prefix bits with the node, so no level above the longest common prefix with any other node
+/// is ever populated. Returns 0 if there are no other nodes, and at most the top table level.
+pub fn max_useful_level(me: &MembershipVector, others: &[MembershipVector]) -> LookupTableLevel {
+    others
+        .iter()
+        .map(|other| me.common_prefix_bit(*other))
+        .max()
+        .unwrap_or(0)
+        .min(LOOKUP_TABLE_LEVELS - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testutil::fixtures::random_membership_vector;
+
+    /// Verifies the bound is the longest common prefix with any other membership vector, zero
+    /// without others, and capped at the top level for an identical membership vector.
+    #[test]
+    fn test_max_useful_level() {
+        let me = random_membership_vector();
+        assert_eq!(max_useful_level(&me, &[]), 0);
+
+        let others: Vec<MembershipVector> = (0..50).map(|_| random_membership_vector()).collect();
+        let expected = others
+            .iter()
+            .map(|other| me.common_prefix_bit(*other))
+            .max()
+            .unwrap();
+        assert_eq!(max_useful_level(&me, &others), expected);
+
+        // a vector sharing all but the last bit sets the bound to the last level.
+        let mut bytes = me.to_bytes();
+        *bytes.last_mut().unwrap() ^= 1;
+        let close = MembershipVector::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            max_useful_level(&me, &[others[0], close]),
+            LOOKUP_TABLE_LEVELS - 1
+        );
+        assert_eq!(max_useful_level(&me, &[me]), LOOKUP_TABLE_LEVELS - 1);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::core::lookup::{LookupTable, LookupTableLevel};
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::{ArrayLookupTable, Identifier};
+use anyhow::anyhow;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// TablePersistence stores snapshots of nodes' lookup tables, keyed by node identifier, so that
+/// a node can reload its table, e.g., after a crash.
+pub trait TablePersistence: Send + Sync {
+    /// Stores a snapshot of `table` as the table of node `id`, replacing any previous snapshot.
+    fn save(&self, id: &Identifier, table: &dyn LookupTable) -> anyhow::Result<()>;
+
+    /// Returns a new lookup table holding the last snapshot saved for node `id`, or `None` if
+    /// no snapshot was saved for it.
+    fn load(&self, id: &Identifier) -> anyhow::Result<Option<Box<dyn LookupTable>>>;
+}
+
+/// A persisted lookup table entry: its position, and the identity in its encoded form.
+pub type PersistedEntry = (LookupTableLevel, Direction, Vec<u8>);
+
+/// The version of the `NodeState` format written by this code. It is bumped whenever the format
+/// changes, so that a state written in another format is detected on import instead of misread.
+pub const NODE_STATE_VERSION: u16 = 1;
+
+/// NodeState is the exported state of a node: its identity and its lookup table entries, encoded
+/// the same way identities are sent over the wire, along with the version of the format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeState {
+    /// The version of the format the state was written in.
+    pub version: u16,
+    /// The encoded identity of the node.
+    pub identity: Vec<u8>,
+    /// The populated entries of the node's lookup table.
+    pub entries: Vec<PersistedEntry>,
+}
+
+impl NodeState {
+    /// Exports the state of the node with the given identity and lookup table, in the current
+    /// format version.
+    pub fn export_state(identity: &Identity, table: &dyn LookupTable) -> anyhow::Result<Self> {
+        Ok(NodeState {
+            version: NODE_STATE_VERSION,
+            identity: identity.encode(),
+            entries: encode_entries(table)?,
+        })
+    }
+
+    /// Returns the identity and a new lookup table holding the entries of the state. Returns an
+    /// error if the state was written in a format version other than the current one, e.g., by a
+    /// newer release, or if an identity fails to decode.
+    pub fn import_state(&self) -> anyhow::Result<(Identity, ArrayLookupTable)> {
+        if self.version > NODE_STATE_VERSION {
+            return Err(anyhow!(
+                "node state version {} is newer than the supported version {}",
+                self.version,
+                NODE_STATE_VERSION
+            ));
+        }
+        if self.version != NODE_STATE_VERSION {
+            return Err(anyhow!(
+                "node state version {} is not supported, expected version {}",
+                self.version,
+                NODE_STATE_VERSION
+            ));
+        }
+        Ok((
+            Identity::decode(&self.identity)?,
+            decode_entries(&self.entries)?,
+        ))
+    }
+}
+
+/// Returns the populated entries of `table`, with every identity encoded.
+fn encode_entries(table: &dyn LookupTable) -> anyhow::Result<Vec<PersistedEntry>> {
+    let mut entries = Vec::new();
+    for (direction, neighbors) in [
+        (Direction::Left, table.left_neighbors()?),
+        (Direction::Right, table.right_neighbors()?),
+    ] {
+        for (level, identity) in neighbors {
+            entries.push((level, direction, identity.encode()));
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns a new lookup table holding the given encoded entries.
+fn decode_entries(entries: &[PersistedEntry]) -> anyhow::Result<ArrayLookupTable> {
+    let table = ArrayLookupTable::new();
+    for (level, direction, bytes) in entries {
+        table.update_entry(Identity::decode(bytes)?, *level, *direction)?;
+    }
+    Ok(table)
+}
+
+/// InMemoryTablePersistence keeps snapshots in memory, encoded the same way identities are sent
+/// over the wire. Cloning is shallow: clones share the same snapshots.
+#[derive(Clone, Default)]
+pub struct InMemoryTablePersistence {
+    snapshots: Arc<Mutex<HashMap<Identifier, Vec<PersistedEntry>>>>,
+}
+
+impl InMemoryTablePersistence {
+    /// Creates a persistence layer with no snapshots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TablePersistence for InMemoryTablePersistence {
+    fn save(&self, id: &Identifier, table: &dyn LookupTable) -> anyhow::Result<()> {
+        let entries = encode_entries(table)?;
+        self.snapshots.lock().insert(*id, entries);
+        Ok(())
+    }
+
+    fn load(&self, id: &Identifier) -> anyhow::Result<Option<Box<dyn LookupTable>>> {
+        let Some(entries) = self.snapshots.lock().get(id).cloned() else {
+            return Ok(None);
+        };
+        Ok(Some(Box::new(decode_entries(&entries)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testutil::fixtures::{
+        random_identifier, random_identity, random_lookup_table,
+    };
+
+    /// Verifies that a saved table loads back as an equal, independent table, and that nothing
+    /// loads for a node that never saved.
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let persistence = InMemoryTablePersistence::new();
+        let id = random_identifier();
+        let table = random_lookup_table(10);
+
+        assert!(persistence.load(&id).unwrap().is_none());
+        persistence.save(&id, &table).unwrap();
+
+        let loaded = persistence
+            .load(&id)
+            .unwrap()
+            .expect("snapshot should exist");
+        assert!(loaded.equal(&table));
+
+        // the loaded table is a copy, not a view of the saved one.
+        loaded.remove_entry(0, Direction::Left).unwrap();
+        assert!(!loaded.equal(&table));
+        assert!(persistence.load(&id).unwrap().unwrap().equal(&table));
+    }
+
+    /// Verifies that an exported state imports back in the current version, and that a state of
+    /// a newer version is rejected with a version mismatch error.
+    #[test]
+    fn test_import_state_rejects_newer_version() {
+        let identity = random_identity();
+        let table = random_lookup_table(10);
+        let state = NodeState::export_state(&identity, &table).unwrap();
+        assert_eq!(state.version, NODE_STATE_VERSION);
+
+        let (imported, imported_table) = state.import_state().unwrap();
+        assert_eq!(imported, identity);
+        assert!(imported_table.equal(&table));
+
+        let newer = NodeState {
+            version: NODE_STATE_VERSION + 1,
+            ..state.clone()
+        };
+        let err = newer.import_state().unwrap_err().to_string();
+        assert!(
+            err.contains("is newer than the supported version"),
+            "unexpected error: {err}"
+        );
+
+        let older = NodeState {
+            version: 0,
+            ..state
+        };
+        assert!(older.import_state().is_err());
+    }
+}
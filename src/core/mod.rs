@@ -1,16 +1,33 @@
 pub mod context;
 mod lookup;
 pub mod model;
-#[cfg(test)]
+pub mod ring;
+mod search;
+#[cfg(any(test, feature = "bench"))]
 pub mod testutil;
 
 pub use crate::core::context::IrrevocableContext;
 pub use crate::core::lookup::array_lookup_table::ArrayLookupTable;
+pub use crate::core::lookup::array_lookup_table::LookupTableObserver;
 pub use crate::core::lookup::array_lookup_table::LOOKUP_TABLE_LEVELS;
+pub use crate::core::lookup::max_useful_level;
+pub use crate::core::lookup::persistence::InMemoryTablePersistence;
+pub use crate::core::lookup::persistence::NodeState;
+pub use crate::core::lookup::persistence::TablePersistence;
+pub use crate::core::lookup::persistence::NODE_STATE_VERSION;
+pub use crate::core::lookup::LookupDiff;
 pub use crate::core::lookup::LookupTable;
+pub use crate::core::lookup::LookupTableError;
 pub use crate::core::lookup::LookupTableLevel;
 pub use crate::core::model::address::Address;
 pub use crate::core::model::identifier::Identifier;
 pub use crate::core::model::memvec::MembershipVector;
+pub use crate::core::ring::Ring;
+pub use crate::core::search::memvec_index::MemVecIndex;
+pub use model::join::LinkReq;
+pub use model::join::NeighborReq;
+pub use model::join::NeighborRes;
 pub use model::search::IdSearchReq;
+pub use model::search::IdSearchReqBuilder;
 pub use model::search::IdSearchRes;
+pub use model::search::SearchOutcome;
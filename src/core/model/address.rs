@@ -1,8 +1,25 @@
+use anyhow::anyhow;
 use fixedstr::{str128, str8};
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::net::{Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
 
-/// Represents a networking address; composed of host + port
-#[derive(Copy, Clone, PartialEq)]
+/// Maximum length of a host in bytes, bounded by the inline `str128` storage.
+pub const MAX_HOST_LEN: usize = 127;
+
+/// Maximum length of a port in bytes, bounded by the inline `str8` storage.
+pub const MAX_PORT_LEN: usize = 7;
+
+/// Represents a networking address; composed of host + port.
+///
+/// An IPv6 literal host is stored without brackets, e.g., `::1`, and is bracketed when the
+/// address is displayed or parsed, e.g., `[::1]:8080`.
+///
+/// Host names are case-insensitive (ASCII), so two addresses are equal when their hosts match
+/// ignoring ASCII case and their ports match exactly; `Hash` is consistent with this equality.
+#[derive(Copy, Clone)]
 pub struct Address {
     host: str128, // up to 128 bytes (on stack)
     port: str8,   // up to 8 bytes (on stack)
@@ -26,11 +43,117 @@ impl Address {
     pub fn port(&self) -> &str {
         self.port.as_str()
     }
+
+    /// Returns the host with the brackets of a bracketed IPv6 literal removed, or an error if
+    /// the host has unbalanced brackets or brackets around something other than an IPv6 literal.
+    fn unbracketed_host(&self) -> anyhow::Result<&str> {
+        let host = self.host();
+        match (host.strip_prefix('['), host.ends_with(']')) {
+            (Some(inner), true) => {
+                let inner = &inner[..inner.len() - 1];
+                inner
+                    .parse::<Ipv6Addr>()
+                    .map_err(|_| anyhow!("bracketed host {} is not an ipv6 address", host))?;
+                Ok(inner)
+            }
+            (None, false) if !host.contains(['[', ']']) => Ok(host),
+            _ => Err(anyhow!("host {} has malformed brackets", host)),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    /// Parses `host:port`, where an IPv6 literal host is bracketed, e.g., `[::1]:8080`.
+    /// Returns an error for malformed brackets, an unbracketed host containing a colon, an empty
+    /// host, a port that is not a number in `0..=65535`, or a host or port too long to store.
+    fn from_str(s: &str) -> anyhow::Result<Address> {
+        let (host, port) = if let Some(rest) = s.strip_prefix('[') {
+            let (host, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| anyhow!("address {} has an unclosed bracket", s))?;
+            let port = rest
+                .strip_prefix(':')
+                .ok_or_else(|| anyhow!("address {} has no port after the bracketed host", s))?;
+            host.parse::<Ipv6Addr>()
+                .map_err(|_| anyhow!("bracketed host {} is not an ipv6 address", host))?;
+            (host, port)
+        } else {
+            let (host, port) = s
+                .split_once(':')
+                .ok_or_else(|| anyhow!("address {} has no port", s))?;
+            if host.contains(['[', ']']) || port.contains(':') {
+                return Err(anyhow!(
+                    "address {} is malformed; ipv6 hosts must be bracketed",
+                    s
+                ));
+            }
+            (host, port)
+        };
+
+        if host.is_empty() {
+            return Err(anyhow!("address {} has an empty host", s));
+        }
+        if host.len() > MAX_HOST_LEN {
+            return Err(anyhow!(
+                "host of address {} is longer than {} bytes",
+                s,
+                MAX_HOST_LEN
+            ));
+        }
+        port.parse::<u16>()
+            .map_err(|_| anyhow!("port {} of address {} is not a valid port", port, s))?;
+        Ok(Address::new(host, port))
+    }
+}
+
+impl ToSocketAddrs for Address {
+    type Iter = std::vec::IntoIter<SocketAddr>;
+
+    /// Resolves the address, accepting an IPv6 literal host with or without brackets.
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        let invalid = |e: anyhow::Error| io::Error::new(io::ErrorKind::InvalidInput, e.to_string());
+        let host = self.unbracketed_host().map_err(invalid)?;
+        let port = self.port().parse::<u16>().map_err(|_| {
+            invalid(anyhow!(
+                "port {} of address {} is not a valid port",
+                self.port(),
+                self
+            ))
+        })?;
+        (host, port).to_socket_addrs()
+    }
+}
+
+impl PartialEq for Address {
+    fn eq(&self, other: &Self) -> bool {
+        self.host().eq_ignore_ascii_case(other.host()) && self.port() == other.port()
+    }
+}
+
+impl Eq for Address {}
+
+impl Hash for Address {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // hashes the lowercased host so that addresses equal under case-insensitive comparison
+        // hash identically.
+        for b in self.host().bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        // separates host from port so that ("ab", "1") and ("a", "b1") hash differently.
+        state.write_u8(0xff);
+        self.port().hash(state);
+    }
 }
 
 impl std::fmt::Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.host(), self.port())
+        if self.host().contains(':') && !self.host().starts_with('[') {
+            write!(f, "[{}]:{}", self.host(), self.port())
+        } else {
+            write!(f, "{}:{}", self.host(), self.port())
+        }
     }
 }
 
@@ -44,10 +167,103 @@ impl Debug for Address {
 mod tests {
     use super::*;
 
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+
+    fn hash_of(address: &Address) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        address.hash(&mut hasher);
+        hasher.finish()
+    }
+
     #[test]
     fn test_address() {
         let address = Address::new("localhost", "1234");
         assert_eq!(address.host(), "localhost");
         assert_eq!(address.port(), "1234");
     }
+
+    /// Verifies hosts differing only in ASCII case are equal, hash identically, and collapse to a
+    /// single entry in a set, while the original host casing is preserved for display.
+    #[test]
+    fn test_address_mixed_case_host_equality() {
+        let upper = Address::new("LOCALHOST", "80");
+        let lower = Address::new("localhost", "80");
+        let mixed = Address::new("LocalHost", "80");
+
+        assert_eq!(upper, lower);
+        assert_eq!(lower, mixed);
+        assert_eq!(hash_of(&upper), hash_of(&lower));
+        assert_eq!(hash_of(&lower), hash_of(&mixed));
+
+        let set: HashSet<Address> = [upper, lower, mixed].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        assert_eq!(upper.host(), "LOCALHOST");
+        assert_eq!(upper.to_string(), "LOCALHOST:80");
+    }
+
+    /// Verifies that a bracketed IPv6 address parses into its bare host, displays bracketed again,
+    /// and resolves to the loopback socket address; and that a host given with brackets to `new`
+    /// resolves the same way.
+    #[test]
+    fn test_address_ipv6_parse_and_resolve() {
+        let address: Address = "[::1]:8080".parse().unwrap();
+        assert_eq!(address.host(), "::1");
+        assert_eq!(address.port(), "8080");
+        assert_eq!(address.to_string(), "[::1]:8080");
+        assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+
+        let expected: SocketAddr = "[::1]:8080".parse().unwrap();
+        let resolved: Vec<SocketAddr> = address.to_socket_addrs().unwrap().collect();
+        assert_eq!(resolved, vec![expected]);
+
+        let bracketed = Address::new("[::1]", "8080");
+        let resolved: Vec<SocketAddr> = bracketed.to_socket_addrs().unwrap().collect();
+        assert_eq!(resolved, vec![expected]);
+
+        let ipv4: Address = "127.0.0.1:80".parse().unwrap();
+        assert_eq!(ipv4.host(), "127.0.0.1");
+        let resolved: Vec<SocketAddr> = ipv4.to_socket_addrs().unwrap().collect();
+        assert_eq!(resolved, vec!["127.0.0.1:80".parse().unwrap()]);
+    }
+
+    /// Verifies that malformed addresses fail to parse, and that a host with malformed brackets
+    /// fails to resolve.
+    #[test]
+    fn test_address_malformed() {
+        for malformed in [
+            "[::1:8080",
+            "[::1]8080",
+            "[::1]",
+            "::1:8080",
+            "[localhost]:80",
+            "local]host:80",
+            ":80",
+            "localhost",
+            "localhost:port",
+            "localhost:65536",
+        ] {
+            assert!(
+                malformed.parse::<Address>().is_err(),
+                "{malformed} should not parse"
+            );
+        }
+
+        assert!(Address::new("[::1", "8080").to_socket_addrs().is_err());
+        assert!(Address::new("[localhost]", "80").to_socket_addrs().is_err());
+        assert!(Address::new("::1", "port").to_socket_addrs().is_err());
+    }
+
+    /// Verifies addresses with the same host but different ports are not equal.
+    #[test]
+    fn test_address_differing_ports() {
+        let a = Address::new("localhost", "80");
+        let b = Address::new("LOCALHOST", "8080");
+        assert_ne!(a, b);
+        assert_ne!(
+            Address::new("localhost", "80"),
+            Address::new("localhost", "81")
+        );
+    }
 }
@@ -0,0 +1,183 @@
+//! Fixed-width arithmetic over big-endian unsigned integers stored as byte arrays, shared by the
+//! identifier methods that treat identifiers as numbers. Every operation reports its overflow
+//! (carry, borrow, or remainder) instead of wrapping silently, so callers decide how to handle it.
+
+use std::cmp::Ordering;
+
+/// Returns `a + b` modulo 2^(8N), and true if the addition carried out of the most significant byte.
+pub(crate) fn add<const N: usize>(a: &[u8; N], b: &[u8; N]) -> ([u8; N], bool) {
+    let mut sum = [0u8; N];
+    let mut carry = 0u16;
+    for i in (0..N).rev() {
+        let current = u16::from(a[i]) + u16::from(b[i]) + carry;
+        sum[i] = (current & 0xff) as u8;
+        carry = current >> 8;
+    }
+    (sum, carry != 0)
+}
+
+/// Returns `a - b` modulo 2^(8N), and true if the subtraction borrowed, i.e., `a < b`.
+pub(crate) fn sub<const N: usize>(a: &[u8; N], b: &[u8; N]) -> ([u8; N], bool) {
+    let mut diff = [0u8; N];
+    let mut borrow = false;
+    for i in (0..N).rev() {
+        let (d, b1) = a[i].overflowing_sub(b[i]);
+        let (d, b2) = d.overflowing_sub(u8::from(borrow));
+        diff[i] = d;
+        borrow = b1 || b2;
+    }
+    (diff, borrow)
+}
+
+/// Shifts `a` right by one bit, shifting `carry_in` into the most significant bit. Together with
+/// the carry of `add` this halves an (8N+1)-bit sum without overflow.
+pub(crate) fn shr1<const N: usize>(a: &[u8; N], carry_in: bool) -> [u8; N] {
+    let mut shifted = [0u8; N];
+    let mut carry = u8::from(carry_in);
+    for i in 0..N {
+        shifted[i] = (carry << 7) | (a[i] >> 1);
+        carry = a[i] & 1;
+    }
+    shifted
+}
+
+/// Compares `a` and `b` as big-endian unsigned integers.
+pub(crate) fn compare<const N: usize>(a: &[u8; N], b: &[u8; N]) -> Ordering {
+    // lexicographic order of big-endian bytes is numeric order.
+    a.cmp(b)
+}
+
+/// Returns `a * multiplier + addend` modulo 2^(8N), and the overflow out of the most significant
+/// byte (zero if the result fits).
+pub(crate) fn mul_add_small<const N: usize>(
+    a: &[u8; N],
+    multiplier: u8,
+    addend: u8,
+) -> ([u8; N], u8) {
+    let mut product = [0u8; N];
+    let mut carry = u16::from(addend);
+    for i in (0..N).rev() {
+        let current = u16::from(a[i]) * u16::from(multiplier) + carry;
+        product[i] = (current & 0xff) as u8;
+        carry = current >> 8;
+    }
+    // carry is at most (255 * multiplier + carry) >> 8, which fits in a byte.
+    (product, carry as u8)
+}
+
+/// Returns `a / divisor` and `a % divisor`. Panics if `divisor` is zero.
+pub(crate) fn div_small<const N: usize>(a: &[u8; N], divisor: u8) -> ([u8; N], u8) {
+    assert_ne!(divisor, 0, "division by zero");
+    let mut quotient = [0u8; N];
+    let mut remainder = 0u16;
+    for i in 0..N {
+        let current = (remainder << 8) | u16::from(a[i]);
+        quotient[i] = (current / u16::from(divisor)) as u8;
+        remainder = current % u16::from(divisor);
+    }
+    (quotient, remainder as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Low byte pairs of `a` and `b` whose addition carries and whose subtraction borrows (the
+    /// first), and neither (the second).
+    const CARRY_PAIRS: [(u8, u8); 2] = [(0x01, 0xff), (0x00, 0x00)];
+
+    /// Two-byte operand pairs standing in for all 2^32 of them, at a few times 2^16 pairs. add and
+    /// sub carry only one bit from the low byte into the high one, and compare reads the low bytes
+    /// only when the high bytes are equal. So every low byte pair under equal high bytes, plus every
+    /// high byte pair under each carry-in, reaches every state the byte loops can be in.
+    fn two_byte_pairs() -> impl Iterator<Item = (u16, u16)> {
+        let join = |high: u8, low: u8| u16::from_be_bytes([high, low]);
+        let bytes = || 0..=u8::MAX;
+        let highs_fixed = bytes().flat_map(move |al| {
+            bytes().flat_map(move |bl| [0x00, 0x7f, 0xff].map(|h| (join(h, al), join(h, bl))))
+        });
+        let lows_fixed = bytes().flat_map(move |ah| {
+            bytes().flat_map(move |bh| CARRY_PAIRS.map(|(al, bl)| (join(ah, al), join(bh, bl))))
+        });
+        highs_fixed.chain(lows_fixed)
+    }
+
+    /// Verifies add, sub, and compare against native `u8` arithmetic for every pair of one-byte operands.
+    #[test]
+    fn test_single_byte_exhaustive() {
+        for a in 0..=u8::MAX {
+            for b in 0..=u8::MAX {
+                let (sum, carry) = add(&[a], &[b]);
+                assert_eq!((sum[0], carry), a.overflowing_add(b));
+
+                let (diff, borrow) = sub(&[a], &[b]);
+                assert_eq!((diff[0], borrow), a.overflowing_sub(b));
+
+                assert_eq!(compare(&[a], &[b]), a.cmp(&b));
+            }
+        }
+    }
+
+    /// Verifies add, sub, and compare against native `u16` arithmetic over every two-byte operand
+    /// pair they can tell apart (see `two_byte_pairs`).
+    #[test]
+    fn test_two_byte_add_sub_compare() {
+        for (a, b) in two_byte_pairs() {
+            let (a_bytes, b_bytes) = (a.to_be_bytes(), b.to_be_bytes());
+
+            let (sum, carry) = add(&a_bytes, &b_bytes);
+            let (expected_sum, expected_carry) = a.overflowing_add(b);
+            assert_eq!(u16::from_be_bytes(sum), expected_sum, "{a} + {b}");
+            assert_eq!(carry, expected_carry, "carry of {a} + {b}");
+
+            let (diff, borrow) = sub(&a_bytes, &b_bytes);
+            let (expected_diff, expected_borrow) = a.overflowing_sub(b);
+            assert_eq!(u16::from_be_bytes(diff), expected_diff, "{a} - {b}");
+            assert_eq!(borrow, expected_borrow, "borrow of {a} - {b}");
+
+            assert_eq!(compare(&a_bytes, &b_bytes), a.cmp(&b), "{a} cmp {b}");
+        }
+    }
+
+    /// Verifies shr1 against native `u16` shifts for every two-byte value, with and without an
+    /// incoming carry.
+    #[test]
+    fn test_two_byte_shr1_exhaustive() {
+        for a in 0..=u16::MAX {
+            let a_bytes = a.to_be_bytes();
+            assert_eq!(u16::from_be_bytes(shr1(&a_bytes, false)), a >> 1);
+            assert_eq!(u16::from_be_bytes(shr1(&a_bytes, true)), (a >> 1) | 0x8000);
+        }
+    }
+
+    /// Verifies that add followed by shr1 of the carry computes the floor of the average of two
+    /// two-byte values without overflow, including at the top of the range.
+    #[test]
+    fn test_two_byte_average() {
+        for (a, b) in two_byte_pairs() {
+            let (sum, carry) = add(&a.to_be_bytes(), &b.to_be_bytes());
+            let expected = ((u32::from(a) + u32::from(b)) / 2) as u16;
+            assert_eq!(u16::from_be_bytes(shr1(&sum, carry)), expected);
+        }
+    }
+
+    /// Verifies mul_add_small and div_small against native `u32` arithmetic for every two-byte
+    /// value, with the decimal radix and the extreme multipliers and divisors.
+    #[test]
+    fn test_two_byte_mul_div_small_exhaustive() {
+        for a in 0..=u16::MAX {
+            let a_bytes = a.to_be_bytes();
+            for (factor, addend) in [(10u8, 9u8), (1, 0), (255, 255), (0, 7)] {
+                let (product, overflow) = mul_add_small(&a_bytes, factor, addend);
+                let expected = u32::from(a) * u32::from(factor) + u32::from(addend);
+                assert_eq!(u32::from(u16::from_be_bytes(product)), expected & 0xffff);
+                assert_eq!(u32::from(overflow), expected >> 16);
+            }
+            for divisor in [10u8, 1, 255] {
+                let (quotient, remainder) = div_small(&a_bytes, divisor);
+                assert_eq!(u16::from_be_bytes(quotient), a / u16::from(divisor));
+                assert_eq!(u16::from(remainder), a % u16::from(divisor));
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use std::fmt::{Debug, Display};
 
 /// Represents the direction of search and lookup table access in SkipGraph.
@@ -7,6 +8,53 @@ pub enum Direction {
     Right,
 }
 
+/// Every direction, in index order.
+pub const DIRECTIONS: [Direction; 2] = [Direction::Left, Direction::Right];
+
+impl Direction {
+    /// Returns the index of this direction, its position in `DIRECTIONS`.
+    pub fn to_index(self) -> usize {
+        match self {
+            Direction::Left => 0,
+            Direction::Right => 1,
+        }
+    }
+
+    /// Returns the direction with the given index, or an error if no direction has it.
+    pub fn from_index(index: usize) -> anyhow::Result<Direction> {
+        DIRECTIONS
+            .get(index)
+            .copied()
+            .ok_or_else(|| anyhow!("no direction with index {}", index))
+    }
+
+    /// Returns the other direction.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Encodes the direction as a single byte for the wire: 0 for `Left`, 1 for `Right`.
+    /// The values are fixed independently of `to_index`, so reordering `DIRECTIONS` cannot change the wire format.
+    pub fn encode(self) -> u8 {
+        match self {
+            Direction::Left => 0,
+            Direction::Right => 1,
+        }
+    }
+
+    /// Decodes a direction produced by `encode`; returns an error for any other byte.
+    pub fn decode(byte: u8) -> anyhow::Result<Direction> {
+        match byte {
+            0 => Ok(Direction::Left),
+            1 => Ok(Direction::Right),
+            _ => Err(anyhow!("invalid encoded direction {}", byte)),
+        }
+    }
+}
+
 impl Display for Direction {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -21,3 +69,102 @@ impl Debug for Direction {
         write!(f, "{}", self)
     }
 }
+
+/// DirectionSet is the set of directions a lookup table holds neighbors in.
+/// The default set is `{Left, Right}`, the two directions of the classic skip graph.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DirectionSet {
+    directions: &'static [Direction],
+}
+
+impl DirectionSet {
+    /// Creates a set from the given directions; returns an error if the set is empty or
+    /// repeats a direction.
+    pub fn new(directions: &'static [Direction]) -> anyhow::Result<DirectionSet> {
+        if directions.is_empty() {
+            return Err(anyhow!("direction set must not be empty"));
+        }
+        for (i, direction) in directions.iter().enumerate() {
+            if directions[..i].contains(direction) {
+                return Err(anyhow!(
+                    "direction {} is repeated in direction set",
+                    direction
+                ));
+            }
+        }
+        Ok(DirectionSet { directions })
+    }
+
+    /// Returns the directions in the set, in slot order.
+    pub fn directions(&self) -> &'static [Direction] {
+        self.directions
+    }
+
+    /// Returns the number of directions in the set.
+    pub fn len(&self) -> usize {
+        self.directions.len()
+    }
+
+    /// Returns whether the set has no directions; always false for a set built through `new`.
+    pub fn is_empty(&self) -> bool {
+        self.directions.is_empty()
+    }
+
+    /// Returns the slot of the given direction within the set, or None if it is not a member.
+    pub fn slot(&self, direction: Direction) -> Option<usize> {
+        self.directions.iter().position(|d| *d == direction)
+    }
+}
+
+impl Default for DirectionSet {
+    fn default() -> Self {
+        DirectionSet {
+            directions: &DIRECTIONS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies that every direction round-trips through its index and that indices out of
+    /// range are rejected.
+    #[test]
+    fn test_direction_index_round_trip() {
+        for (i, direction) in DIRECTIONS.iter().enumerate() {
+            assert_eq!(direction.to_index(), i);
+            assert_eq!(Direction::from_index(i).unwrap(), *direction);
+        }
+        assert!(Direction::from_index(DIRECTIONS.len()).is_err());
+    }
+
+    /// Verifies the wire encoding of each direction is fixed, round-trips, and that decoding any other byte fails.
+    #[test]
+    fn test_direction_encode_decode() {
+        assert_eq!(Direction::Left.encode(), 0);
+        assert_eq!(Direction::Right.encode(), 1);
+        for direction in DIRECTIONS {
+            assert_eq!(Direction::decode(direction.encode()).unwrap(), direction);
+        }
+        for corrupt in [2u8, 0x80, u8::MAX] {
+            assert!(Direction::decode(corrupt).is_err());
+        }
+    }
+
+    /// Verifies direction set construction and membership.
+    #[test]
+    fn test_direction_set() {
+        let default = DirectionSet::default();
+        assert_eq!(default.len(), 2);
+        assert_eq!(default.slot(Direction::Left), Some(0));
+        assert_eq!(default.slot(Direction::Right), Some(1));
+
+        let left_only = DirectionSet::new(&[Direction::Left]).unwrap();
+        assert_eq!(left_only.slot(Direction::Left), Some(0));
+        assert_eq!(left_only.slot(Direction::Right), None);
+
+        assert!(DirectionSet::new(&[]).is_err());
+        assert!(DirectionSet::new(&[Direction::Right, Direction::Right]).is_err());
+    }
+}
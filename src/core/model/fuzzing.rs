@@ -0,0 +1,88 @@
+//! `Arbitrary` implementations for the model types, enabled by the `fuzzing` feature.
+//!
+//! Every implementation builds a valid instance from the raw bytes, so that fuzz targets exercise
+//! the logic behind the types rather than their validation: identifiers and membership vectors
+//! take their full width from the input, and addresses are IPv4 literals with a port in range.
+
+use crate::core::model::address::Address;
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::model::IDENTIFIER_SIZE_BYTES;
+use crate::core::{Identifier, MembershipVector};
+use arbitrary::{Arbitrary, Unstructured};
+use std::net::Ipv4Addr;
+
+impl<'a> Arbitrary<'a> for Identifier {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bytes = <[u8; IDENTIFIER_SIZE_BYTES]>::arbitrary(u)?;
+        Identifier::from_bytes(&bytes).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for MembershipVector {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bytes = <[u8; IDENTIFIER_SIZE_BYTES]>::arbitrary(u)?;
+        MembershipVector::from_bytes(&bytes).map_err(|_| arbitrary::Error::IncorrectFormat)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Direction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(if bool::arbitrary(u)? {
+            Direction::Right
+        } else {
+            Direction::Left
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Address {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let host = Ipv4Addr::from(<[u8; 4]>::arbitrary(u)?);
+        let port = u16::arbitrary(u)?;
+        Ok(Address::new(&host.to_string(), &port.to_string()))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Identity {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Identity::new(
+            Identifier::arbitrary(u)?,
+            MembershipVector::arbitrary(u)?,
+            Address::arbitrary(u)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies that each type is built from a small buffer, taking its bytes from the input in
+    /// order and padding with zeros once the input runs out, and that the address is valid.
+    #[test]
+    fn test_arbitrary_from_small_buffer() {
+        let data: Vec<u8> = (1..=40).collect();
+
+        let id = Identifier::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(id.as_bytes(), &data[..IDENTIFIER_SIZE_BYTES]);
+
+        let mem_vec = MembershipVector::arbitrary(&mut Unstructured::new(&data[..8])).unwrap();
+        assert_eq!(&mem_vec.as_bytes()[..8], &data[..8]);
+        assert!(mem_vec.as_bytes()[8..].iter().all(|b| *b == 0));
+
+        let address =
+            Address::arbitrary(&mut Unstructured::new(&[10, 0, 0, 1, 0x1f, 0x90])).unwrap();
+        assert_eq!(address.host(), "10.0.0.1");
+        assert!(address.port().parse::<u16>().is_ok());
+
+        let identity = Identity::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(identity.id(), id);
+
+        let directions: Vec<Direction> = [0u8, 1]
+            .iter()
+            .map(|b| Direction::arbitrary(&mut Unstructured::new(&[*b])).unwrap())
+            .collect();
+        assert_eq!(directions, vec![Direction::Left, Direction::Right]);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core::model;
+use crate::core::model::bigint;
 use crate::core::model::identifier::ComparisonResult::{CompareEqual, CompareGreater, CompareLess};
 use crate::core::model::IDENTIFIER_SIZE_BYTES;
 use anyhow::anyhow;
@@ -136,6 +137,26 @@ impl Identifier {
         Ok(Identifier(identifier))
     }
 
+    /// Returns the smallest and the largest identifiers whose leading bytes are `prefix`, i.e., the
+    /// prefix padded on the right with `0x00` and with `0xFF` bytes respectively, so that every
+    /// identifier with that prefix lies in the inclusive range between them. An empty prefix spans
+    /// `ZERO..=MAX`, and a full-length prefix is a single identifier. Returns an error if the
+    /// prefix is longer than an identifier.
+    pub fn prefix_range(prefix: &[u8]) -> anyhow::Result<(Identifier, Identifier)> {
+        if prefix.len() > model::IDENTIFIER_SIZE_BYTES {
+            return Err(anyhow!(
+                "identifier prefix is too large, expected at most {} bytes, got {} bytes",
+                model::IDENTIFIER_SIZE_BYTES,
+                prefix.len()
+            ));
+        }
+        let mut low = [0x00; model::IDENTIFIER_SIZE_BYTES];
+        let mut high = [0xFF; model::IDENTIFIER_SIZE_BYTES];
+        low[..prefix.len()].copy_from_slice(prefix);
+        high[..prefix.len()].copy_from_slice(prefix);
+        Ok((Identifier(low), Identifier(high)))
+    }
+
     /// Converts the input hex string into an Identifier. The input must be at most 32 characters long.
     /// Note: the input string is expected to be a valid base58 string (NOT a hex string).
     pub fn from_string(s: &str) -> anyhow::Result<Identifier> {
@@ -143,11 +164,116 @@ impl Identifier {
         Identifier::from_bytes(&decoded)
     }
 
+    /// Returns the identifier halfway between `self` and `other`, reading both as big-endian
+    /// unsigned integers and rounding down. The sum is computed with a carry bit, so it does not
+    /// overflow even for `MAX`.
+    pub fn midpoint(&self, other: &Identifier) -> Identifier {
+        let (sum, carry) = bigint::add(&self.0, &other.0);
+        // shifts the 257-bit sum (carry followed by `sum`) right by one bit.
+        Identifier(bigint::shr1(&sum, carry))
+    }
+
+    /// Returns the absolute difference between `self` and `other`, reading both as big-endian
+    /// unsigned integers, e.g., to rank candidates by how close they lie to a target.
+    pub fn distance(&self, other: &Identifier) -> Identifier {
+        let (low, high) = if self <= other {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let (diff, borrow) = bigint::sub(&high.0, &low.0);
+        debug_assert!(!borrow, "subtracting the smaller identifier never borrows");
+        Identifier(diff)
+    }
+
+    /// Returns the position of the identifier across the identifier space as a fraction in
+    /// `[0.0, 1.0]`, where `ZERO` is 0.0 and `MAX` is 1.0, e.g., to place it on a number line.
+    /// Only the top 8 bytes are read, which is already beyond the precision of an `f64`.
+    pub fn position_fraction(&self) -> f64 {
+        let mut top = [0u8; 8];
+        top.copy_from_slice(&self.0[..8]);
+        u64::from_be_bytes(top) as f64 / u64::MAX as f64
+    }
+
+    /// Converts the Identifier into the decimal representation of its bytes read as a big-endian
+    /// unsigned integer, without leading zeros (ZERO is `"0"`).
+    pub fn to_decimal_string(&self) -> String {
+        // repeatedly divides the big-endian number by 10, collecting remainders as digits.
+        let mut value = self.0;
+        let mut digits = Vec::new();
+        while value.iter().any(|b| *b != 0) {
+            let (quotient, remainder) = bigint::div_small(&value, 10);
+            value = quotient;
+            digits.push(b'0' + remainder);
+        }
+        if digits.is_empty() {
+            return "0".to_string();
+        }
+        digits.reverse();
+        String::from_utf8(digits).expect("decimal digits are valid utf-8")
+    }
+
+    /// Converts a decimal string into an Identifier, reading it as a big-endian unsigned integer.
+    /// The input must be non-empty, consist only of ASCII digits, and not exceed MAX.
+    pub fn from_decimal_string(s: &str) -> anyhow::Result<Identifier> {
+        if s.is_empty() {
+            return Err(anyhow!("decimal identifier string is empty"));
+        }
+
+        let mut value = [0u8; IDENTIFIER_SIZE_BYTES];
+        for c in s.chars() {
+            let digit = c
+                .to_digit(10)
+                .ok_or_else(|| anyhow!("invalid decimal digit '{}' in identifier string", c))?;
+
+            let (product, overflow) = bigint::mul_add_small(&value, 10, digit as u8);
+            if overflow != 0 {
+                return Err(anyhow!(
+                    "decimal identifier string {} exceeds the maximum identifier value",
+                    s
+                ));
+            }
+            value = product;
+        }
+        Ok(Identifier(value))
+    }
+
     /// Converts the Identifier into a byte slice.
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
     }
 
+    /// Returns the first 8 hex characters of the identifier followed by an ellipsis, e.g.,
+    /// `"0a1b2c3d…"`, to keep logs readable. Prefixes of distinct identifiers may collide, so
+    /// `Display` remains the full-length form wherever the exact identifier matters.
+    pub fn short(&self) -> String {
+        format!("{}…", hex::encode(&self.0[..4]))
+    }
+
+    /// Returns true if the identifier is `ZERO`. Every byte is inspected regardless of the
+    /// others, so the time taken does not depend on the value.
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().fold(0u8, |acc, byte| acc | byte) == 0
+    }
+
+    /// Returns true if both identifiers are equal, comparing every byte regardless of the others
+    /// instead of stopping at the first difference as `==` does, so the time taken does not
+    /// depend on where the identifiers differ. Meant for comparisons involving secret values;
+    /// `==` stays the fast comparison for everything else.
+    pub fn ct_eq(&self, other: &Identifier) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+    }
+
+    /// Returns true if the identifier is `MAX`. Every byte is inspected regardless of the
+    /// others, so the time taken does not depend on the value.
+    pub fn is_max(&self) -> bool {
+        self.0.iter().fold(u8::MAX, |acc, byte| acc & byte) == u8::MAX
+    }
+
     /// Converts the Identifier into a owned byte vector.
     /// Consider using `as_bytes()` if you don't need ownership.
     pub fn to_bytes(&self) -> Vec<u8> {
@@ -172,11 +298,7 @@ impl Debug for Identifier {
 
 impl Ord for Identifier {
     fn cmp(&self, other: &Identifier) -> std::cmp::Ordering {
-        match self.compare(other).result {
-            CompareLess => std::cmp::Ordering::Less,
-            CompareEqual => std::cmp::Ordering::Equal,
-            CompareGreater => std::cmp::Ordering::Greater,
-        }
+        bigint::compare(&self.0, &other.0)
     }
 }
 
@@ -505,4 +627,187 @@ mod tests {
         let id_from_str = Identifier::from_string(&id_str).unwrap();
         assert_eq!(id, id_from_str);
     }
+
+    /// Tests the conversion of an `Identifier` to its decimal string and back, covering the
+    /// boundary values ZERO and MAX, small values, and random identifiers.
+    #[test]
+    fn test_identifier_decimal_string_round_trip() {
+        assert_eq!(ZERO.to_decimal_string(), "0");
+        assert_eq!(Identifier::from_decimal_string("0").unwrap(), ZERO);
+
+        let max_decimal =
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(MAX.to_decimal_string(), max_decimal);
+        assert_eq!(Identifier::from_decimal_string(max_decimal).unwrap(), MAX);
+
+        let small = Identifier::from_bytes(&[1, 0]).unwrap();
+        assert_eq!(small.to_decimal_string(), "256");
+        assert_eq!(Identifier::from_decimal_string("256").unwrap(), small);
+        assert_eq!(Identifier::from_decimal_string("000256").unwrap(), small);
+
+        for _ in 0..100 {
+            let id = random_identifier();
+            let decimal = id.to_decimal_string();
+            assert_eq!(Identifier::from_decimal_string(&decimal).unwrap(), id);
+        }
+    }
+
+    /// Tests that malformed or out-of-range decimal strings are rejected.
+    #[test]
+    fn test_identifier_from_decimal_string_invalid() {
+        assert!(Identifier::from_decimal_string("").is_err());
+        assert!(Identifier::from_decimal_string("12a4").is_err());
+        assert!(Identifier::from_decimal_string("-1").is_err());
+        // MAX + 1
+        assert!(Identifier::from_decimal_string(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+        )
+        .is_err());
+    }
+
+    /// Tests `Identifier::midpoint` on boundary values, small values, and its symmetry.
+    #[test]
+    fn test_identifier_midpoint() {
+        assert_eq!(ZERO.midpoint(&ZERO), ZERO);
+        assert_eq!(MAX.midpoint(&MAX), MAX);
+
+        // (2^256 - 1) / 2 rounds down to 0x7fff...ff.
+        let mut half = [255u8; IDENTIFIER_SIZE_BYTES];
+        half[0] = 0x7f;
+        assert_eq!(ZERO.midpoint(&MAX), Identifier(half));
+        assert_eq!(MAX.midpoint(&ZERO), Identifier(half));
+
+        let ten = Identifier::from_bytes(&[10]).unwrap();
+        let three = Identifier::from_bytes(&[3]).unwrap();
+        assert_eq!(ten.midpoint(&three), Identifier::from_bytes(&[6]).unwrap());
+
+        let a = Identifier::from_bytes(&[1, 0]).unwrap(); // 256
+        let b = Identifier::from_bytes(&[2]).unwrap();
+        assert_eq!(a.midpoint(&b), Identifier::from_bytes(&[129]).unwrap());
+
+        for _ in 0..100 {
+            let x = random_identifier();
+            let y = random_identifier();
+            let mid = x.midpoint(&y);
+            assert_eq!(mid, y.midpoint(&x));
+            assert!(mid >= std::cmp::min(x, y) && mid <= std::cmp::max(x, y));
+        }
+    }
+
+    /// Tests `Identifier::distance` on boundary values, a difference that borrows across bytes, and
+    /// its symmetry.
+    #[test]
+    fn test_identifier_distance() {
+        assert_eq!(ZERO.distance(&ZERO), ZERO);
+        assert_eq!(ZERO.distance(&MAX), MAX);
+        assert_eq!(MAX.distance(&ZERO), MAX);
+
+        let a = Identifier::from_bytes(&[1, 0]).unwrap(); // 256
+        let b = Identifier::from_bytes(&[1]).unwrap();
+        assert_eq!(a.distance(&b), Identifier::from_bytes(&[255]).unwrap());
+
+        for _ in 0..100 {
+            let x = random_identifier();
+            let y = random_identifier();
+            assert_eq!(x.distance(&y), y.distance(&x));
+            assert_eq!(x.distance(&x), ZERO);
+        }
+    }
+
+    /// Tests `is_zero` and `is_max` on the boundary values, values one bit away from them, and a
+    /// random value.
+    #[test]
+    fn test_identifier_is_zero_is_max() {
+        assert!(ZERO.is_zero());
+        assert!(!ZERO.is_max());
+        assert!(MAX.is_max());
+        assert!(!MAX.is_zero());
+
+        let mut almost_zero = [0u8; IDENTIFIER_SIZE_BYTES];
+        almost_zero[IDENTIFIER_SIZE_BYTES - 1] = 1;
+        assert!(!Identifier(almost_zero).is_zero());
+        let mut almost_max = [255u8; IDENTIFIER_SIZE_BYTES];
+        almost_max[0] = 0x7f;
+        assert!(!Identifier(almost_max).is_max());
+
+        let id = random_identifier();
+        assert_eq!(id.is_zero(), id == ZERO);
+        assert_eq!(id.is_max(), id == MAX);
+    }
+
+    /// Tests that `short` is the first 8 hex characters of the full form followed by an ellipsis.
+    #[test]
+    fn test_identifier_short() {
+        let id = random_identifier();
+        let short = id.short();
+        assert_eq!(short.chars().count(), 9);
+        assert!(short.ends_with('…'));
+        assert!(id.to_string().starts_with(short.trim_end_matches('…')));
+        assert_eq!(MAX.short(), "ffffffff…");
+    }
+
+    /// Tests that `ct_eq` agrees with `==` for equal identifiers, and for identifiers differing in
+    /// the first, a middle, or the last byte.
+    #[test]
+    fn test_identifier_ct_eq() {
+        let id = random_identifier();
+        let copy = Identifier::from_bytes(id.as_bytes()).unwrap();
+        assert!(id.ct_eq(&copy));
+        assert_eq!(id.ct_eq(&copy), id == copy);
+        assert!(ZERO.ct_eq(&ZERO));
+
+        for i in [0, IDENTIFIER_SIZE_BYTES / 2, IDENTIFIER_SIZE_BYTES - 1] {
+            let mut bytes = id.to_bytes();
+            bytes[i] ^= 0x01;
+            let other = Identifier::from_bytes(&bytes).unwrap();
+            assert!(!id.ct_eq(&other));
+            assert_eq!(id.ct_eq(&other), id == other);
+        }
+        assert!(!ZERO.ct_eq(&MAX));
+    }
+
+    /// Tests `position_fraction` on the boundary values and the midpoint of the space, and that it
+    /// preserves the order of identifiers.
+    #[test]
+    fn test_identifier_position_fraction() {
+        assert_eq!(ZERO.position_fraction(), 0.0);
+        assert!((MAX.position_fraction() - 1.0).abs() < 1e-9);
+        assert!((ZERO.midpoint(&MAX).position_fraction() - 0.5).abs() < 1e-9);
+
+        for _ in 0..100 {
+            let x = random_identifier();
+            let y = random_identifier();
+            let (fx, fy) = (x.position_fraction(), y.position_fraction());
+            assert!((0.0..=1.0).contains(&fx));
+            if x <= y {
+                assert!(fx <= fy);
+            }
+        }
+    }
+
+    /// Tests `prefix_range` for an empty prefix, a single-byte prefix, a full-length prefix, and a
+    /// prefix longer than an identifier.
+    #[test]
+    fn test_identifier_prefix_range() {
+        assert_eq!(Identifier::prefix_range(&[]).unwrap(), (ZERO, MAX));
+
+        let (low, high) = Identifier::prefix_range(&[0xAB]).unwrap();
+        let mut expected_low = [0x00; IDENTIFIER_SIZE_BYTES];
+        expected_low[0] = 0xAB;
+        let mut expected_high = [0xFF; IDENTIFIER_SIZE_BYTES];
+        expected_high[0] = 0xAB;
+        assert_eq!(low, Identifier(expected_low));
+        assert_eq!(high, Identifier(expected_high));
+
+        let id = random_identifier();
+        assert_eq!(Identifier::prefix_range(id.as_bytes()).unwrap(), (id, id));
+
+        // every identifier sharing the prefix lies within the range.
+        let mut bytes = id.to_bytes();
+        bytes[0] = 0xAB;
+        let prefixed = Identifier::from_bytes(&bytes).unwrap();
+        assert!(low <= prefixed && prefixed <= high);
+
+        assert!(Identifier::prefix_range(&[0u8; IDENTIFIER_SIZE_BYTES + 1]).is_err());
+    }
 }
@@ -1,6 +1,11 @@
+use crate::core::model::address::{MAX_HOST_LEN, MAX_PORT_LEN};
+use crate::core::model::IDENTIFIER_SIZE_BYTES;
 use crate::core::{Address, Identifier, MembershipVector};
+use anyhow::anyhow;
 
 /// Identity is an immutable struct that represents a node's identity in the network (ID, MembershipVector, Address).
+/// Equality is strict and compares the address too; use `Identity::same_node` to compare nodes
+/// regardless of where they are reached.
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Identity {
     id: Identifier,
@@ -32,12 +37,90 @@ impl Identity {
     pub fn address(&self) -> Address {
         self.address
     }
+
+    /// Returns true if both identities describe the same node, i.e., their identifiers and
+    /// membership vectors match, regardless of their addresses. Unlike `==`, this treats a stale
+    /// and a fresh address of one node as the same node.
+    pub fn same_node(&self, other: &Identity) -> bool {
+        self.id == other.id && self.mem_vec == other.mem_vec
+    }
+
+    /// Encodes the identity as: 32 bytes id, 32 bytes membership vector, then the host and the
+    /// port, each prefixed by its length as a single byte.
+    pub fn encode(&self) -> Vec<u8> {
+        let host = self.address.host().as_bytes();
+        let port = self.address.port().as_bytes();
+
+        let mut bytes = Vec::with_capacity(2 * IDENTIFIER_SIZE_BYTES + 2 + host.len() + port.len());
+        bytes.extend_from_slice(self.id.as_bytes());
+        bytes.extend_from_slice(self.mem_vec.as_bytes());
+        // host and port lengths are bounded by their inline storage, so they always fit a byte.
+        bytes.push(host.len() as u8);
+        bytes.extend_from_slice(host);
+        bytes.push(port.len() as u8);
+        bytes.extend_from_slice(port);
+        bytes
+    }
+
+    /// Decodes an identity produced by `encode`.
+    /// Returns an error if the input is truncated, has trailing bytes, or carries a host or port
+    /// longer than an `Address` can hold.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Identity> {
+        let mut rest = bytes;
+        let id = Identifier::from_bytes(take(&mut rest, IDENTIFIER_SIZE_BYTES, "identifier")?)?;
+        let mem_vec = MembershipVector::from_bytes(take(
+            &mut rest,
+            IDENTIFIER_SIZE_BYTES,
+            "membership vector",
+        )?)?;
+        let host = take_str(&mut rest, MAX_HOST_LEN, "host")?;
+        let port = take_str(&mut rest, MAX_PORT_LEN, "port")?;
+        if !rest.is_empty() {
+            return Err(anyhow!(
+                "encoded identity has {} trailing bytes",
+                rest.len()
+            ));
+        }
+        Ok(Identity::new(id, mem_vec, Address::new(host, port)))
+    }
+}
+
+/// Splits the first `len` bytes off `rest`, failing if fewer remain.
+fn take<'a>(rest: &mut &'a [u8], len: usize, field: &str) -> anyhow::Result<&'a [u8]> {
+    if rest.len() < len {
+        return Err(anyhow!(
+            "encoded identity is truncated: {} needs {} bytes, {} left",
+            field,
+            len,
+            rest.len()
+        ));
+    }
+    let (head, tail) = rest.split_at(len);
+    *rest = tail;
+    Ok(head)
+}
+
+/// Splits a length-prefixed utf-8 string off `rest`, failing if it exceeds `max_len` bytes.
+fn take_str<'a>(rest: &mut &'a [u8], max_len: usize, field: &str) -> anyhow::Result<&'a str> {
+    let len = take(rest, 1, field)?[0] as usize;
+    if len > max_len {
+        return Err(anyhow!(
+            "encoded {} is {} bytes, at most {} allowed",
+            field,
+            len,
+            max_len
+        ));
+    }
+    std::str::from_utf8(take(rest, len, field)?)
+        .map_err(|e| anyhow!("encoded {} is not valid utf-8: {}", field, e))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::testutil::fixtures::{random_identifier, random_membership_vector};
+    use crate::core::testutil::fixtures::{
+        random_identifier, random_identity, random_membership_vector,
+    };
     use crate::core::Address;
 
     #[test]
@@ -50,4 +133,87 @@ mod tests {
         assert_eq!(identity.mem_vec(), mem_vec);
         assert_eq!(identity.address(), address);
     }
+
+    /// Verifies that encoding and decoding an identity yields the same identity.
+    #[test]
+    fn test_identity_encode_decode_round_trip() {
+        for _ in 0..10 {
+            let identity = random_identity();
+            let decoded = Identity::decode(&identity.encode()).unwrap();
+            assert_eq!(decoded, identity);
+            assert_eq!(decoded.address().host(), identity.address().host());
+        }
+
+        let empty_address = Identity::new(
+            random_identifier(),
+            random_membership_vector(),
+            Address::new("", ""),
+        );
+        assert_eq!(
+            Identity::decode(&empty_address.encode()).unwrap(),
+            empty_address
+        );
+    }
+
+    /// Verifies that truncated, over-long, and over-length-field inputs are rejected.
+    #[test]
+    fn test_identity_decode_invalid() {
+        let encoded = random_identity().encode();
+        for len in 0..encoded.len() {
+            assert!(
+                Identity::decode(&encoded[..len]).is_err(),
+                "decoding a {len}-byte prefix should fail"
+            );
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(Identity::decode(&trailing).is_err());
+
+        // a host longer than an address can hold.
+        let mut long_host = encoded[..2 * IDENTIFIER_SIZE_BYTES].to_vec();
+        long_host.push((MAX_HOST_LEN + 1) as u8);
+        long_host.extend(std::iter::repeat_n(b'a', MAX_HOST_LEN + 1));
+        long_host.extend_from_slice(&[1, b'1']);
+        assert!(Identity::decode(&long_host).is_err());
+
+        // a port longer than an address can hold.
+        let mut long_port = encoded[..2 * IDENTIFIER_SIZE_BYTES].to_vec();
+        long_port.extend_from_slice(&[1, b'h']);
+        long_port.push((MAX_PORT_LEN + 1) as u8);
+        long_port.extend(std::iter::repeat_n(b'1', MAX_PORT_LEN + 1));
+        assert!(Identity::decode(&long_port).is_err());
+    }
+
+    /// Verifies that two identities differing only in address are the same node, while `==`
+    /// tells them apart.
+    #[test]
+    fn test_same_node_ignores_address() {
+        let identity = random_identity();
+        let moved = Identity::new(
+            identity.id(),
+            identity.mem_vec(),
+            Address::new("10.0.0.1", "4242"),
+        );
+        assert!(identity.same_node(&moved));
+        assert!(moved.same_node(&identity));
+        assert_ne!(identity, moved);
+    }
+
+    /// Verifies that identities differing in identifier or membership vector are not the same
+    /// node, even at the same address.
+    #[test]
+    fn test_same_node_compares_id_and_mem_vec() {
+        let identity = random_identity();
+        assert!(identity.same_node(&identity));
+
+        let other_id = Identity::new(random_identifier(), identity.mem_vec(), identity.address());
+        assert!(!identity.same_node(&other_id));
+        let other_mem_vec = Identity::new(
+            identity.id(),
+            random_membership_vector(),
+            identity.address(),
+        );
+        assert!(!identity.same_node(&other_mem_vec));
+    }
 }
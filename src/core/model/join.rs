@@ -0,0 +1,38 @@
+use crate::core::lookup::LookupTableLevel;
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::model::search::Nonce;
+
+/// NeighborReq asks a node for its neighbor at a given level and direction of its lookup table.
+/// A joining node issues it to walk the overlay while it picks its own neighbors.
+#[derive(Debug, Copy, Clone)]
+pub struct NeighborReq {
+    /// The unique identifier of the request, echoed by the response (randomly generated).
+    pub nonce: Nonce,
+    /// The level of the lookup table entry being asked for.
+    pub level: LookupTableLevel,
+    /// The direction of the lookup table entry being asked for.
+    pub direction: Direction,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct NeighborRes {
+    /// The nonce of the request this response answers.
+    pub nonce: Nonce,
+    /// The identity of the node that answered the request.
+    pub responder: Identity,
+    /// The responder's neighbor at the requested level and direction, if any.
+    pub neighbor: Option<Identity>,
+}
+
+/// LinkReq asks a node to install `identity` as its neighbor at a given level and direction of
+/// its lookup table, replacing any existing entry there.
+#[derive(Debug, Copy, Clone)]
+pub struct LinkReq {
+    /// The identity of the node to link to.
+    pub identity: Identity,
+    /// The level of the lookup table entry to set.
+    pub level: LookupTableLevel,
+    /// The direction of the lookup table entry to set.
+    pub direction: Direction,
+}
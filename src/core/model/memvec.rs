@@ -64,6 +64,24 @@ impl MembershipVector {
         &self.0
     }
 
+    /// Returns true if every bit of the membership vector is zero. Every byte is inspected
+    /// regardless of the others, so the time taken does not depend on the value.
+    pub fn is_zero(&self) -> bool {
+        self.0.iter().fold(0u8, |acc, byte| acc | byte) == 0
+    }
+
+    /// Returns the membership vector with the order of all its bits reversed, so that the least
+    /// significant bit becomes the most significant one. Routing on the reversed vector follows
+    /// the bits of the original from the least significant end, e.g., for variants of the skip
+    /// graph that index levels from that end.
+    pub fn reversed(&self) -> MembershipVector {
+        let mut mv = [0u8; model::IDENTIFIER_SIZE_BYTES];
+        for (dst, src) in mv.iter_mut().zip(self.0.iter().rev()) {
+            *dst = src.reverse_bits();
+        }
+        MembershipVector(mv)
+    }
+
     /// Converts the MembershipVector into a byte slice.
     ///
     /// # Returns
@@ -435,6 +453,38 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_membership_vector_is_zero() {
+        let zero = MembershipVector::from_bytes(&[0u8; model::IDENTIFIER_SIZE_BYTES]).unwrap();
+        assert!(zero.is_zero());
+        assert!(!MembershipVector::from_bytes(&[1u8]).unwrap().is_zero());
+        assert!(
+            !MembershipVector::from_bytes(&[0xffu8; model::IDENTIFIER_SIZE_BYTES])
+                .unwrap()
+                .is_zero()
+        );
+
+        let mv = random_membership_vector();
+        assert_eq!(mv.is_zero(), mv == zero);
+    }
+
+    /// Verifies that reversing moves the last bit to the front and mirrors the bits within each
+    /// byte, and that reversing twice restores the original vector.
+    #[test]
+    fn test_membership_vector_reversed() {
+        let mv = MembershipVector::from_bytes(&[0x12, 0x01]).unwrap();
+        let mut expected = [0u8; model::IDENTIFIER_SIZE_BYTES];
+        expected[0] = 0x80;
+        expected[1] = 0x48;
+        assert_eq!(mv.reversed().as_bytes(), &expected);
+        assert_eq!(mv.reversed().reversed(), mv);
+
+        let mv = random_membership_vector();
+        assert_eq!(mv.reversed().reversed(), mv);
+        let ones = MembershipVector::from_bytes(&[0xffu8; model::IDENTIFIER_SIZE_BYTES]).unwrap();
+        assert_eq!(ones.reversed(), ones);
+    }
+
     fn assert_valid_decompose(
         mv: &MembershipVector,
         pivot_index: usize,
@@ -2,8 +2,14 @@
 pub const IDENTIFIER_SIZE_BYTES: usize = 32;
 
 pub mod address;
+pub(crate) mod bigint;
 pub(crate) mod direction;
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
 pub mod identifier;
 pub mod identity;
+pub(crate) mod join;
 pub mod memvec;
 pub(crate) mod search;
+#[cfg(feature = "zeroize")]
+pub mod secret;
@@ -1,6 +1,8 @@
 use crate::core::lookup::LookupTableLevel;
 use crate::core::model::direction::Direction;
-use crate::core::Identifier;
+use crate::core::model::identity::Identity;
+use crate::core::{Identifier, LOOKUP_TABLE_LEVELS};
+use anyhow::anyhow;
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub struct Nonce {
@@ -13,6 +15,22 @@ impl Nonce {
             id: rand::random::<u128>(),
         }
     }
+
+    /// Returns the nonce whose big-endian encoding is `bytes`.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Nonce {
+            id: u128::from_be_bytes(bytes),
+        }
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+impl<'a> arbitrary::Arbitrary<'a> for Nonce {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Nonce {
+            id: u128::arbitrary(u)?,
+        })
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -25,8 +43,172 @@ pub struct IdSearchReq {
     pub origin: Identifier,
     /// The level of the lookup table where the search is being performed.
     pub level: LookupTableLevel,
+    /// The lowest lookup table level the search considers; entries below it are ignored, e.g., as
+    /// a repeated descending search already ruled them out.
+    pub min_level: LookupTableLevel,
     /// The direction of the search.
     pub direction: Direction,
+    /// Set by the node that originated the search and carried unchanged through every relay, so
+    /// that the originator can check a response answers its own request.
+    pub correlation: Option<u64>,
+    /// Whether a node with the target identifier itself satisfies the search. An inclusive search
+    /// settles on the greatest identifier <= target (right) or the smallest >= target (left); an
+    /// exclusive one on the greatest < target (right) or the smallest > target (left).
+    pub inclusive: bool,
+    /// Generated by the node that originated the search and carried unchanged through every
+    /// relay and response; every node records it on the spans of its processing of the search,
+    /// so that logs of one search can be stitched together across nodes.
+    pub trace_id: u128,
+    /// The number of times the request has been sent over the network so far: zero at the
+    /// originator, and one more at every relay.
+    pub hops: usize,
+}
+
+impl IdSearchReq {
+    /// Returns a builder for a search request initiated by `origin`.
+    pub fn builder(origin: Identifier) -> IdSearchReqBuilder {
+        IdSearchReqBuilder {
+            origin,
+            target: None,
+            direction: None,
+            level: LOOKUP_TABLE_LEVELS - 1,
+            min_level: 0,
+            nonce: None,
+            correlation: None,
+            inclusive: true,
+            trace_id: None,
+        }
+    }
+
+    /// Creates an inclusive search request initiated by `origin` for `target`, starting at
+    /// `level` in `direction`, with a random nonce and trace id. Returns an error if the level is
+    /// beyond the lookup table, instead of leaving it to fail once the table is indexed.
+    pub fn try_new(
+        origin: Identifier,
+        target: Identifier,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<Self> {
+        Self::builder(origin)
+            .target(target)
+            .direction(direction)
+            .level(level)
+            .build()
+    }
+
+    /// Returns the request continuing the search `req` at the next hop, i.e., at the node in the
+    /// result `res` of `req`, as `IdSearchRes::continue_request` does in the direction of `req`,
+    /// but keeping the minimum level and inclusiveness of `req` rather than the defaults.
+    pub fn continue_from(req: &IdSearchReq, res: &IdSearchRes) -> IdSearchReq {
+        IdSearchReq {
+            min_level: req.min_level,
+            inclusive: req.inclusive,
+            ..res.continue_request(req.direction)
+        }
+    }
+}
+
+/// IdSearchReqBuilder builds an `IdSearchReq`. The target and direction must be set; the level
+/// defaults to the top lookup table level and the minimum level to zero (a search over the whole
+/// table), the nonce and the trace id to random ones, and the search is inclusive.
+#[derive(Debug, Copy, Clone)]
+pub struct IdSearchReqBuilder {
+    origin: Identifier,
+    target: Option<Identifier>,
+    direction: Option<Direction>,
+    level: LookupTableLevel,
+    min_level: LookupTableLevel,
+    nonce: Option<Nonce>,
+    correlation: Option<u64>,
+    inclusive: bool,
+    trace_id: Option<u128>,
+}
+
+impl IdSearchReqBuilder {
+    /// Sets the identifier being searched for.
+    pub fn target(mut self, target: Identifier) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Sets the direction of the search.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Sets the highest lookup table level the search starts from.
+    pub fn level(mut self, level: LookupTableLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets the lowest lookup table level the search considers.
+    pub fn min_level(mut self, min_level: LookupTableLevel) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Sets the nonce of the request, e.g., to relay an existing search.
+    pub fn nonce(mut self, nonce: Nonce) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Sets the correlation of the request; unset by default.
+    pub fn correlation(mut self, correlation: u64) -> Self {
+        self.correlation = Some(correlation);
+        self
+    }
+
+    /// Sets whether a node with the target identifier itself satisfies the search.
+    pub fn inclusive(mut self, inclusive: bool) -> Self {
+        self.inclusive = inclusive;
+        self
+    }
+
+    /// Sets the trace id of the request, e.g., to relay an existing search.
+    pub fn trace_id(mut self, trace_id: u128) -> Self {
+        self.trace_id = Some(trace_id);
+        self
+    }
+
+    /// Builds the request; returns an error if the target or direction is missing, if the level
+    /// is beyond the lookup table, or if the minimum level is above the level.
+    pub fn build(self) -> anyhow::Result<IdSearchReq> {
+        let target = self
+            .target
+            .ok_or_else(|| anyhow!("search request target is not set"))?;
+        let direction = self
+            .direction
+            .ok_or_else(|| anyhow!("search request direction is not set"))?;
+        if self.level >= LOOKUP_TABLE_LEVELS {
+            return Err(anyhow!(
+                "search request level {} is beyond the lookup table levels {}",
+                self.level,
+                LOOKUP_TABLE_LEVELS
+            ));
+        }
+        if self.min_level > self.level {
+            return Err(anyhow!(
+                "search request minimum level {} is above the level {}",
+                self.min_level,
+                self.level
+            ));
+        }
+        Ok(IdSearchReq {
+            nonce: self.nonce.unwrap_or_else(Nonce::random),
+            target,
+            origin: self.origin,
+            level: self.level,
+            min_level: self.min_level,
+            direction,
+            correlation: self.correlation,
+            inclusive: self.inclusive,
+            trace_id: self.trace_id.unwrap_or_else(rand::random),
+            hops: 0,
+        })
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -35,8 +217,394 @@ pub struct IdSearchRes {
     pub nonce: Nonce,
     /// The identifier that is being searched for.
     pub target: Identifier,
+    /// Echoes the origin of the request this result answers, i.e., the node the search is run for.
+    pub origin: Identifier,
     /// The level of the lookup table where the search was terminated at the current node.
     pub termination_level: LookupTableLevel,
     /// The identifier that was found during the search process at the current node.
     pub result: Identifier,
+    /// Echoes the correlation of the request this result answers.
+    pub correlation: Option<u64>,
+    /// Echoes the trace id of the request this result answers.
+    pub trace_id: u128,
+    /// Echoes the hop count of the request this result answers, i.e., the number of network hops
+    /// the search took to reach the current node.
+    pub hops: usize,
+    /// The full identity of the node in `result`, taken from the lookup table entry the search
+    /// selected (or the searching node's own identity on fallback), so that the caller can
+    /// contact it without another lookup.
+    pub matched_identity: Option<Identity>,
+    /// Set to the direction of the request when the node had no candidate in that direction and
+    /// fell back to its own identifier, i.e., the search reached the edge of the overlay.
+    pub fallback_direction: Option<Direction>,
+}
+
+impl IdSearchRes {
+    /// Creates the result of `req` settling on `matched` at `termination_level`, echoing the
+    /// request's nonce, target, origin, correlation, trace id, and hop count. `fallback_direction` is set
+    /// when `matched` is the searching node itself for lack of a candidate. Returns an error if the
+    /// termination level is beyond the lookup table, as indexing the table at it would panic.
+    pub fn try_new(
+        req: &IdSearchReq,
+        termination_level: LookupTableLevel,
+        matched: Identity,
+        fallback_direction: Option<Direction>,
+    ) -> anyhow::Result<Self> {
+        if termination_level >= LOOKUP_TABLE_LEVELS {
+            return Err(anyhow!(
+                "search result termination level {} is beyond the lookup table levels {}",
+                termination_level,
+                LOOKUP_TABLE_LEVELS
+            ));
+        }
+        Ok(IdSearchRes {
+            nonce: req.nonce,
+            target: req.target,
+            origin: req.origin,
+            termination_level,
+            result: matched.id(),
+            correlation: req.correlation,
+            trace_id: req.trace_id,
+            hops: req.hops,
+            matched_identity: Some(matched),
+            fallback_direction,
+        })
+    }
+
+    /// Returns true if both results settled on the same identifier at the same termination level.
+    /// In an iterative multi-hop search this is the convergence predicate: a hop whose result is
+    /// the same as the previous hop's made no progress, so the search terminates.
+    pub fn same_result(&self, other: &IdSearchRes) -> bool {
+        self.result == other.result && self.termination_level == other.termination_level
+    }
+
+    /// Returns the request continuing this search at the next hop, i.e., at the node in `result`,
+    /// in `direction`. The next hop searches only the levels up to `termination_level`, as a search
+    /// never climbs back above the level it descended to; the target, origin, nonce, correlation,
+    /// and trace id carry over, and the hop count grows by one, as the continuation is sent to the
+    /// next hop. The result does not record the minimum level or inclusiveness of the search, so
+    /// the continuation searches inclusively down to level zero; `IdSearchReq::continue_from`
+    /// carries those over from the original request.
+    pub fn continue_request(&self, direction: Direction) -> IdSearchReq {
+        IdSearchReq {
+            nonce: self.nonce,
+            target: self.target,
+            origin: self.origin,
+            level: self.termination_level,
+            min_level: 0,
+            direction,
+            correlation: self.correlation,
+            inclusive: true,
+            trace_id: self.trace_id,
+            hops: self.hops + 1,
+        }
+    }
+
+    /// Renders the result as a human-readable block, one `field: value` line per field: the
+    /// target, the result, the termination level, the status, and the address of the matched
+    /// identity when present. The status is `exact` if the result is the target itself,
+    /// `fallback (<direction>)` if the search reached the edge of the overlay, and `closest`
+    /// otherwise.
+    pub fn to_pretty(&self) -> String {
+        let status = match self.fallback_direction {
+            Some(direction) => format!("fallback ({direction})"),
+            None if self.result == self.target => "exact".to_string(),
+            None => "closest".to_string(),
+        };
+        let mut pretty = format!(
+            "target: {}\nresult: {}\ntermination level: {}\nstatus: {}",
+            self.target, self.result, self.termination_level, status
+        );
+        if let Some(identity) = self.matched_identity {
+            pretty.push_str(&format!("\naddress: {}", identity.address()));
+        }
+        pretty
+    }
+}
+
+/// SearchOutcome is the answer to a search originated by a node: the identity of the node the
+/// search settled on, and the number of network hops the search took to reach it.
+#[derive(Debug, Copy, Clone)]
+pub struct SearchOutcome {
+    pub identity: Identity,
+    pub hops: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testutil::fixtures::{
+        random_address, random_identifier, random_membership_vector,
+    };
+
+    /// Verifies `same_result` holds only when both the result and the termination level match,
+    /// regardless of the nonce.
+    #[test]
+    fn test_id_search_res_same_result() {
+        let target = random_identifier();
+        let result = random_identifier();
+        let res = IdSearchRes {
+            nonce: Nonce::random(),
+            correlation: None,
+            trace_id: rand::random(),
+            hops: 0,
+            matched_identity: None,
+            fallback_direction: None,
+            target,
+            origin: random_identifier(),
+            termination_level: 3,
+            result,
+        };
+
+        // converged: same result and level, even across different nonces.
+        let converged = IdSearchRes {
+            nonce: Nonce::random(),
+            ..res
+        };
+        assert!(res.same_result(&converged));
+        assert!(converged.same_result(&res));
+
+        // not converged: the result moved.
+        let moved = IdSearchRes {
+            result: random_identifier(),
+            ..res
+        };
+        assert!(!res.same_result(&moved));
+
+        // not converged: same result but at a different level.
+        let descended = IdSearchRes {
+            termination_level: 2,
+            ..res
+        };
+        assert!(!res.same_result(&descended));
+    }
+
+    /// Verifies that the continuation of a result searches from the result's termination level
+    /// for the same target, as part of the same search.
+    #[test]
+    fn test_id_search_res_continue_request() {
+        let res = IdSearchRes {
+            nonce: Nonce::random(),
+            correlation: Some(7),
+            trace_id: rand::random(),
+            hops: 0,
+            matched_identity: None,
+            fallback_direction: None,
+            target: random_identifier(),
+            origin: random_identifier(),
+            termination_level: 5,
+            result: random_identifier(),
+        };
+
+        let req = res.continue_request(Direction::Right);
+        assert_eq!(req.level, res.termination_level);
+        assert_eq!(req.target, res.target);
+        assert_eq!(req.origin, res.origin);
+        assert_eq!(req.nonce, res.nonce);
+        assert_eq!(req.correlation, res.correlation);
+        assert_eq!(req.trace_id, res.trace_id);
+        assert_eq!(req.hops, res.hops + 1);
+        assert_eq!(req.direction, Direction::Right);
+    }
+
+    /// Verifies that the continuation of a search starts from the result's termination level, and
+    /// otherwise carries the request over, including a minimum level and inclusiveness that differ
+    /// from the defaults.
+    #[test]
+    fn test_id_search_req_continue_from() {
+        let req = IdSearchReq::builder(random_identifier())
+            .target(random_identifier())
+            .direction(Direction::Right)
+            .min_level(2)
+            .inclusive(false)
+            .correlation(7)
+            .build()
+            .unwrap();
+        let res = IdSearchRes {
+            nonce: req.nonce,
+            correlation: req.correlation,
+            trace_id: req.trace_id,
+            hops: req.hops,
+            matched_identity: None,
+            fallback_direction: None,
+            target: req.target,
+            origin: req.origin,
+            termination_level: 5,
+            result: random_identifier(),
+        };
+
+        let next = IdSearchReq::continue_from(&req, &res);
+        assert_eq!(next.level, res.termination_level);
+        assert_eq!(next.hops, req.hops + 1);
+        assert_eq!(next.origin, req.origin);
+        assert_eq!(next.target, req.target);
+        assert_eq!(next.direction, req.direction);
+        assert_eq!(next.min_level, 2);
+        assert!(!next.inclusive);
+        assert_eq!(next.nonce, req.nonce);
+        assert_eq!(next.correlation, Some(7));
+        assert_eq!(next.trace_id, req.trace_id);
+    }
+
+    /// Verifies that a request built with only a target and direction gets the default level and
+    /// a fresh nonce, and that missing required fields or an out-of-range level fail the build.
+    #[test]
+    fn test_id_search_req_builder_defaults() {
+        let origin = random_identifier();
+        let target = random_identifier();
+
+        let req = IdSearchReq::builder(origin)
+            .target(target)
+            .direction(Direction::Left)
+            .build()
+            .unwrap();
+        assert_eq!(req.origin, origin);
+        assert_eq!(req.target, target);
+        assert_eq!(req.direction, Direction::Left);
+        assert_eq!(req.level, LOOKUP_TABLE_LEVELS - 1);
+        assert_eq!(req.min_level, 0);
+        assert!(req.inclusive);
+
+        let other = IdSearchReq::builder(origin)
+            .target(target)
+            .direction(Direction::Left)
+            .build()
+            .unwrap();
+        assert_ne!(
+            req.nonce, other.nonce,
+            "each request should get its own nonce"
+        );
+
+        let nonce = Nonce::random();
+        let relayed = IdSearchReq::builder(origin)
+            .target(target)
+            .direction(Direction::Right)
+            .level(3)
+            .nonce(nonce)
+            .build()
+            .unwrap();
+        assert_eq!(relayed.level, 3);
+        assert_eq!(relayed.nonce, nonce);
+
+        assert!(IdSearchReq::builder(origin)
+            .direction(Direction::Left)
+            .build()
+            .is_err());
+        assert!(IdSearchReq::builder(origin).target(target).build().is_err());
+        assert!(IdSearchReq::builder(origin)
+            .target(target)
+            .direction(Direction::Left)
+            .level(LOOKUP_TABLE_LEVELS)
+            .build()
+            .is_err());
+        assert!(IdSearchReq::builder(origin)
+            .target(target)
+            .direction(Direction::Left)
+            .level(2)
+            .min_level(3)
+            .build()
+            .is_err());
+    }
+
+    /// Verifies that `try_new` echoes the request into the result and rejects a termination level
+    /// beyond the lookup table.
+    #[test]
+    fn test_id_search_res_try_new() {
+        let req = IdSearchReq::builder(random_identifier())
+            .target(random_identifier())
+            .direction(Direction::Left)
+            .correlation(11)
+            .build()
+            .unwrap();
+        let matched = Identity::new(
+            random_identifier(),
+            random_membership_vector(),
+            random_address(),
+        );
+
+        let res = IdSearchRes::try_new(&req, LOOKUP_TABLE_LEVELS - 1, matched, None).unwrap();
+        assert_eq!(res.nonce, req.nonce);
+        assert_eq!(res.target, req.target);
+        assert_eq!(res.correlation, req.correlation);
+        assert_eq!(res.trace_id, req.trace_id);
+        assert_eq!(res.hops, req.hops);
+        assert_eq!(res.termination_level, LOOKUP_TABLE_LEVELS - 1);
+        assert_eq!(res.result, matched.id());
+        assert_eq!(res.matched_identity, Some(matched));
+        assert_eq!(res.fallback_direction, None);
+
+        assert!(IdSearchRes::try_new(&req, LOOKUP_TABLE_LEVELS, matched, None).is_err());
+        assert!(IdSearchRes::try_new(&req, usize::MAX, matched, Some(Direction::Left)).is_err());
+    }
+
+    /// Verifies that `try_new` creates the request it is given, and rejects a level beyond the
+    /// lookup table at construction.
+    #[test]
+    fn test_id_search_req_try_new() {
+        let origin = random_identifier();
+        let target = random_identifier();
+
+        let req = IdSearchReq::try_new(origin, target, 4, Direction::Right).unwrap();
+        assert_eq!(req.origin, origin);
+        assert_eq!(req.target, target);
+        assert_eq!(req.level, 4);
+        assert_eq!(req.min_level, 0);
+        assert_eq!(req.direction, Direction::Right);
+        assert_eq!(req.hops, 0);
+        assert!(req.inclusive);
+
+        assert!(
+            IdSearchReq::try_new(origin, target, LOOKUP_TABLE_LEVELS - 1, Direction::Left).is_ok()
+        );
+        assert!(
+            IdSearchReq::try_new(origin, target, LOOKUP_TABLE_LEVELS, Direction::Left).is_err()
+        );
+    }
+
+    /// Verifies that the pretty rendering shows every field, the status of exact, closest, and
+    /// fallback results, and the matched address only when there is a matched identity.
+    #[test]
+    fn test_id_search_res_to_pretty() {
+        let target = random_identifier();
+        let address = random_address();
+        let res = IdSearchRes {
+            nonce: Nonce::random(),
+            correlation: None,
+            trace_id: rand::random(),
+            hops: 0,
+            matched_identity: Some(Identity::new(target, random_membership_vector(), address)),
+            fallback_direction: None,
+            target,
+            origin: random_identifier(),
+            termination_level: 4,
+            result: target,
+        };
+
+        let pretty = res.to_pretty();
+        assert!(pretty.contains(&format!("target: {target}")));
+        assert!(pretty.contains(&format!("result: {target}")));
+        assert!(pretty.contains("termination level: 4"));
+        assert!(pretty.contains("status: exact"));
+        assert!(pretty.contains(&format!("address: {address}")));
+        assert_eq!(pretty.lines().count(), 5);
+
+        let result = random_identifier();
+        let closest = IdSearchRes {
+            result,
+            matched_identity: None,
+            ..res
+        }
+        .to_pretty();
+        assert!(closest.contains(&format!("result: {result}")));
+        assert!(closest.contains("status: closest"));
+        assert!(!closest.contains("address:"));
+
+        let fallback = IdSearchRes {
+            result,
+            fallback_direction: Some(Direction::Left),
+            ..res
+        }
+        .to_pretty();
+        assert!(fallback.contains("status: fallback (Left)"));
+    }
 }
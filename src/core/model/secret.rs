@@ -0,0 +1,107 @@
+//! Zeroize-on-drop wrappers for identifiers and membership vectors, enabled by the `zeroize` feature.
+//!
+//! `Identifier` and `MembershipVector` are `Copy`, so their bytes may be duplicated freely and
+//! cannot be reliably wiped; they therefore cannot implement `ZeroizeOnDrop` themselves. The
+//! wrappers here own their bytes, are deliberately not `Copy`, and zero them when dropped.
+//! Values obtained from a wrapper (e.g., through `identifier()`) are ordinary copies that are not
+//! wiped, so they should be kept short-lived.
+
+use crate::core::model::IDENTIFIER_SIZE_BYTES;
+use crate::core::{Identifier, MembershipVector};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// SecretIdentifier holds the bytes of an identifier and zeroes them on drop.
+pub struct SecretIdentifier([u8; IDENTIFIER_SIZE_BYTES]);
+
+impl SecretIdentifier {
+    /// Wraps a copy of the identifier's bytes.
+    pub fn new(id: &Identifier) -> Self {
+        let mut bytes = [0u8; IDENTIFIER_SIZE_BYTES];
+        bytes.copy_from_slice(id.as_bytes());
+        SecretIdentifier(bytes)
+    }
+
+    /// Returns a plain copy of the wrapped identifier; the copy is not zeroed on drop.
+    pub fn identifier(&self) -> Identifier {
+        Identifier::from_bytes(&self.0).expect("wrapped bytes have the identifier size")
+    }
+
+    /// Returns the wrapped bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Zeroize for SecretIdentifier {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretIdentifier {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretIdentifier {}
+
+/// SecretMembershipVector holds the bytes of a membership vector and zeroes them on drop.
+pub struct SecretMembershipVector([u8; IDENTIFIER_SIZE_BYTES]);
+
+impl SecretMembershipVector {
+    /// Wraps a copy of the membership vector's bytes.
+    pub fn new(mem_vec: &MembershipVector) -> Self {
+        let mut bytes = [0u8; IDENTIFIER_SIZE_BYTES];
+        bytes.copy_from_slice(mem_vec.as_bytes());
+        SecretMembershipVector(bytes)
+    }
+
+    /// Returns a plain copy of the wrapped membership vector; the copy is not zeroed on drop.
+    pub fn membership_vector(&self) -> MembershipVector {
+        MembershipVector::from_bytes(&self.0)
+            .expect("wrapped bytes have the membership vector size")
+    }
+
+    /// Returns the wrapped bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Zeroize for SecretMembershipVector {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SecretMembershipVector {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretMembershipVector {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testutil::fixtures::{random_identifier, random_membership_vector};
+
+    /// Verifies that the wrappers hold the original value and that an explicit `zeroize()`
+    /// clears every wrapped byte.
+    #[test]
+    fn test_secret_zeroize() {
+        let id = random_identifier();
+        let mut secret_id = SecretIdentifier::new(&id);
+        assert_eq!(secret_id.identifier(), id);
+        secret_id.zeroize();
+        assert!(secret_id.as_bytes().iter().all(|b| *b == 0));
+
+        let mem_vec = random_membership_vector();
+        let mut secret_mem_vec = SecretMembershipVector::new(&mem_vec);
+        assert_eq!(secret_mem_vec.membership_vector(), mem_vec);
+        secret_mem_vec.zeroize();
+        assert!(secret_mem_vec.as_bytes().iter().all(|b| *b == 0));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::core::Identifier;
+use anyhow::anyhow;
+
+/// Ring places a set of node identifiers on a consistent hashing ring over the identifier space,
+/// which wraps around from `MAX` to `ZERO`, to partition keys among the nodes: a key is owned by
+/// the first node at or clockwise after it, i.e., the node with the smallest identifier that is
+/// not less than the key, or the node with the smallest identifier overall if the key lies past
+/// every node.
+#[derive(Debug, Clone)]
+pub struct Ring {
+    // sorted in ascending order, without duplicates
+    nodes: Vec<Identifier>,
+}
+
+impl Ring {
+    /// Creates a ring of the given nodes, in any order; duplicates are dropped. Returns an error if
+    /// no node is given, as keys would have no owner.
+    pub fn new(mut nodes: Vec<Identifier>) -> anyhow::Result<Self> {
+        if nodes.is_empty() {
+            return Err(anyhow!("cannot create a ring without nodes"));
+        }
+        nodes.sort();
+        nodes.dedup();
+        Ok(Ring { nodes })
+    }
+
+    /// Returns the number of distinct nodes on the ring.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if the ring has no nodes, which never holds for a ring created by `new`.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the node owning `key`: the first node clockwise from it, where a node owns its own
+    /// identifier, wrapping around to the smallest node past the largest one.
+    pub fn owner(&self, key: &Identifier) -> Identifier {
+        let index = self.nodes.partition_point(|node| node < key);
+        self.nodes.get(index).copied().unwrap_or(self.nodes[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::model::identifier::{MAX, ZERO};
+    use crate::core::testutil::fixtures::random_sorted_identifiers;
+
+    fn id(byte: u8) -> Identifier {
+        Identifier::from_bytes(&[byte]).unwrap()
+    }
+
+    /// Tests that every key up to and including a node's identifier, and past the previous node's,
+    /// is owned by that node, and that keys past the largest node wrap around to the smallest one.
+    #[test]
+    fn test_ring_owner_boundaries_and_wrap_around() {
+        let ring = Ring::new(vec![id(200), id(10), id(100), id(100)]).unwrap();
+        assert_eq!(ring.len(), 3);
+
+        assert_eq!(ring.owner(&ZERO), id(10));
+        assert_eq!(ring.owner(&id(10)), id(10));
+        assert_eq!(ring.owner(&id(11)), id(100));
+        assert_eq!(ring.owner(&id(100)), id(100));
+        assert_eq!(ring.owner(&id(150)), id(200));
+        assert_eq!(ring.owner(&id(200)), id(200));
+
+        // past the largest node, ownership wraps around to the smallest one.
+        assert_eq!(ring.owner(&id(201)), id(10));
+        assert_eq!(ring.owner(&MAX), id(10));
+
+        let single = Ring::new(vec![id(42)]).unwrap();
+        for key in [ZERO, id(42), MAX] {
+            assert_eq!(single.owner(&key), id(42));
+        }
+
+        assert!(Ring::new(vec![]).is_err());
+    }
+
+    /// Tests that on a random ring every node owns its own identifier, and the key right after it
+    /// is owned by the next node clockwise.
+    #[test]
+    fn test_ring_owner_random_nodes() {
+        let nodes = random_sorted_identifiers(16);
+        let ring = Ring::new(nodes.clone()).unwrap();
+        for (i, node) in nodes.iter().enumerate() {
+            assert_eq!(ring.owner(node), *node);
+            let next = nodes[(i + 1) % nodes.len()];
+            let mut bytes = node.to_bytes();
+            // the successor of `node` in the identifier space; `MAX` has none, and wraps.
+            if let Some(last) = bytes.iter().rposition(|byte| *byte < 0xFF) {
+                bytes[last] += 1;
+                bytes[last + 1..].fill(0);
+                let key = Identifier::from_bytes(&bytes).unwrap();
+                assert_eq!(ring.owner(&key), next);
+            }
+        }
+    }
+}
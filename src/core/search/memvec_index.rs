@@ -0,0 +1,278 @@
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::model::IDENTIFIER_SIZE_BYTES;
+use crate::core::{Identifier, MembershipVector};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+/// The number of bits in a membership vector, i.e., the depth of a fully split trie.
+const MEM_VEC_BITS: usize = IDENTIFIER_SIZE_BYTES * 8;
+
+/// MemVecIndex indexes a set of candidate identities by the bits of their membership vectors, to
+/// find the candidate sharing the longest membership-vector prefix with a given one on one side
+/// of a pivot identifier, without scanning every candidate.
+///
+/// The index is a binary trie over membership-vector bits, most significant first. Each trie
+/// node holds the candidates of its subtree ordered by identifier, so that the candidates on
+/// either side of the pivot are found by a range query. A node with a single candidate is not
+/// split further, so the trie is only as deep as needed to tell its candidates apart.
+#[derive(Debug, Default)]
+pub struct MemVecIndex {
+    root: TrieNode,
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    // candidates whose membership vectors start with the path to this node, by identifier
+    entries: BTreeMap<Identifier, Identity>,
+    // subtrees for the next bit being 0 and 1; both are none while the node is not split
+    children: [Option<Box<TrieNode>>; 2],
+}
+
+impl MemVecIndex {
+    /// Creates an empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a candidate to the index, replacing any candidate with the same identifier.
+    pub fn insert(&mut self, identity: Identity) {
+        if let Some(existing) = self.root.entries.get(&identity.id()).copied() {
+            self.root.remove(0, &existing);
+        }
+        self.root.insert(0, identity);
+    }
+
+    /// Returns the number of candidates in the index.
+    pub fn len(&self) -> usize {
+        self.root.entries.len()
+    }
+
+    /// Returns true if the index has no candidates.
+    pub fn is_empty(&self) -> bool {
+        self.root.entries.is_empty()
+    }
+
+    /// Returns the candidate on the `direction` side of `pivot` (strictly smaller identifiers for
+    /// `Left`, strictly greater for `Right`) sharing the longest membership-vector prefix with
+    /// `mem_vec`. Among candidates sharing equally long prefixes, the one closest to `pivot` is
+    /// returned. Returns `None` if there is no candidate on that side.
+    pub fn longest_prefix_match(
+        &self,
+        mem_vec: MembershipVector,
+        pivot: Identifier,
+        direction: Direction,
+    ) -> Option<Identity> {
+        // descends along the bits of `mem_vec`; the deeper a subtree, the longer the prefix its
+        // candidates share with `mem_vec`, so the last subtree with a candidate on the requested
+        // side holds the answer.
+        let mut best = None;
+        let mut node = &self.root;
+        let mut depth = 0;
+        while let Some(candidate) = node.closest(pivot, direction) {
+            best = Some(candidate);
+            if depth == MEM_VEC_BITS {
+                break;
+            }
+            match &node.children[bit(&mem_vec, depth)] {
+                Some(child) => node = child,
+                None => break,
+            }
+            depth += 1;
+        }
+        best
+    }
+}
+
+impl TrieNode {
+    fn is_split(&self) -> bool {
+        self.children.iter().any(Option::is_some)
+    }
+
+    /// Inserts the identity into this node, which sits at `depth` bits, and its subtree.
+    fn insert(&mut self, depth: usize, identity: Identity) {
+        self.entries.insert(identity.id(), identity);
+        if depth == MEM_VEC_BITS {
+            // identical membership vectors cannot be told apart, so they share the leaf.
+            return;
+        }
+        if self.is_split() {
+            self.child(bit(&identity.mem_vec(), depth))
+                .insert(depth + 1, identity);
+        } else if self.entries.len() > 1 {
+            let entries: Vec<Identity> = self.entries.values().copied().collect();
+            for entry in entries {
+                self.child(bit(&entry.mem_vec(), depth))
+                    .insert(depth + 1, entry);
+            }
+        }
+    }
+
+    /// Removes the identity from this node, which sits at `depth` bits, and its subtree.
+    fn remove(&mut self, depth: usize, identity: &Identity) {
+        self.entries.remove(&identity.id());
+        if depth == MEM_VEC_BITS {
+            return;
+        }
+        let slot = bit(&identity.mem_vec(), depth);
+        if let Some(child) = self.children[slot].as_mut() {
+            child.remove(depth + 1, identity);
+            if child.entries.is_empty() {
+                self.children[slot] = None;
+            }
+        }
+    }
+
+    fn child(&mut self, slot: usize) -> &mut TrieNode {
+        self.children[slot].get_or_insert_with(Box::default)
+    }
+
+    /// Returns the candidate of this subtree closest to `pivot` on its `direction` side.
+    fn closest(&self, pivot: Identifier, direction: Direction) -> Option<Identity> {
+        match direction {
+            Direction::Left => self.entries.range(..pivot).next_back(),
+            Direction::Right => self
+                .entries
+                .range((Bound::Excluded(pivot), Bound::Unbounded))
+                .next(),
+        }
+        .map(|(_, identity)| *identity)
+    }
+}
+
+/// Returns the bit of `mem_vec` at `index`, counting from the most significant bit.
+fn bit(mem_vec: &MembershipVector, index: usize) -> usize {
+    usize::from((mem_vec.as_bytes()[index / 8] >> (7 - index % 8)) & 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::model::direction::DIRECTIONS;
+    use crate::core::model::identifier::MAX;
+    use crate::core::testutil::fixtures::{
+        random_address, random_identifier, random_identities, random_membership_vector,
+    };
+    use rand::Rng;
+
+    /// Returns the answer of `longest_prefix_match` by scanning every candidate.
+    fn brute_force(
+        candidates: &[Identity],
+        mem_vec: MembershipVector,
+        pivot: Identifier,
+        direction: Direction,
+    ) -> Option<Identity> {
+        candidates
+            .iter()
+            .filter(|c| match direction {
+                Direction::Left => c.id() < pivot,
+                Direction::Right => c.id() > pivot,
+            })
+            .max_by(|a, b| {
+                let prefix = |c: &Identity| c.mem_vec().common_prefix_bit(mem_vec);
+                // longer prefix first, then closer to the pivot.
+                prefix(a).cmp(&prefix(b)).then(match direction {
+                    Direction::Left => a.id().cmp(&b.id()),
+                    Direction::Right => b.id().cmp(&a.id()),
+                })
+            })
+            .copied()
+    }
+
+    /// Returns a membership vector whose first byte is one of a few values, so that random
+    /// vectors share long prefixes often.
+    fn clustered_membership_vector() -> MembershipVector {
+        let mut bytes = random_membership_vector().to_bytes();
+        bytes[0] = [0x00, 0x0f, 0xf0, 0xff][rand::rng().random_range(0..4)];
+        if rand::rng().random_bool(0.5) {
+            bytes[1] = 0xaa;
+        }
+        MembershipVector::from_bytes(&bytes).unwrap()
+    }
+
+    /// Verifies the index agrees with a brute-force scan on random candidates and queries, for
+    /// both uniformly random and clustered membership vectors.
+    #[test]
+    fn test_longest_prefix_match_matches_brute_force() {
+        let uniform = random_identities(200);
+        let clustered: Vec<Identity> = (0..200)
+            .map(|_| {
+                Identity::new(
+                    random_identifier(),
+                    clustered_membership_vector(),
+                    random_address(),
+                )
+            })
+            .collect();
+
+        for candidates in [uniform, clustered] {
+            let mut index = MemVecIndex::new();
+            for candidate in candidates.iter() {
+                index.insert(*candidate);
+            }
+            assert_eq!(index.len(), candidates.len());
+
+            for i in 0..200 {
+                // queries both with fresh membership vectors and with ones of the candidates.
+                let mem_vec = if i % 2 == 0 {
+                    clustered_membership_vector()
+                } else {
+                    candidates[i].mem_vec()
+                };
+                let pivot = random_identifier();
+                for direction in DIRECTIONS {
+                    assert_eq!(
+                        index.longest_prefix_match(mem_vec, pivot, direction),
+                        brute_force(&candidates, mem_vec, pivot, direction),
+                        "mismatch for query {i} in direction {direction}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Verifies the edge cases: an empty index, no candidate on the requested side, identical
+    /// membership vectors, and re-inserting a candidate with a new membership vector.
+    #[test]
+    fn test_longest_prefix_match_edge_cases() {
+        let mut index = MemVecIndex::new();
+        let pivot = random_identifier();
+        let mem_vec = random_membership_vector();
+        assert!(index.is_empty());
+        assert_eq!(
+            index.longest_prefix_match(mem_vec, pivot, Direction::Left),
+            None
+        );
+
+        // two candidates right of the pivot with the query's own membership vector; the closer
+        // one wins, and nothing lies to the left.
+        let near = Identity::new(pivot.midpoint(&MAX), mem_vec, random_address());
+        let far = Identity::new(MAX, mem_vec, random_address());
+        index.insert(far);
+        index.insert(near);
+        assert_eq!(
+            index.longest_prefix_match(mem_vec, pivot, Direction::Right),
+            Some(near)
+        );
+        assert_eq!(
+            index.longest_prefix_match(mem_vec, pivot, Direction::Left),
+            None
+        );
+
+        // re-inserting the closer candidate with a different membership vector moves it out of
+        // the longest-prefix subtree.
+        let mut other_bytes = mem_vec.to_bytes();
+        other_bytes[0] ^= 0x80;
+        let moved = Identity::new(
+            near.id(),
+            MembershipVector::from_bytes(&other_bytes).unwrap(),
+            near.address(),
+        );
+        index.insert(moved);
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.longest_prefix_match(mem_vec, pivot, Direction::Right),
+            Some(far)
+        );
+    }
+}
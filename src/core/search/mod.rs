@@ -0,0 +1 @@
+pub mod memvec_index;
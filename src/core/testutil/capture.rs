@@ -0,0 +1,158 @@
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// CapturedEvent is a tracing event recorded by `CaptureLayer`: its level, its fields (including the message,
+/// under `"message"`) formatted with `Debug`, and the fields of the spans it was emitted in, where a field of an
+/// inner span shadows a field of the same name of an outer one.
+#[derive(Debug, Clone)]
+pub struct CapturedEvent {
+    pub level: Level,
+    pub fields: HashMap<String, String>,
+    pub span_fields: HashMap<String, String>,
+}
+
+/// CapturedSpan is a tracing span recorded by `CaptureLayer` when it was created: its name, and
+/// the fields it was created with, formatted with `Debug`.
+#[derive(Debug, Clone)]
+pub struct CapturedSpan {
+    pub name: &'static str,
+    pub fields: HashMap<String, String>,
+}
+
+/// CaptureLayer is a tracing layer that records every event and span it sees, at every level.
+/// Cloning is shallow: clones record into the same lists.
+#[derive(Clone, Default)]
+pub struct CaptureLayer {
+    events: Arc<Mutex<Vec<CapturedEvent>>>,
+    spans: Arc<Mutex<Vec<CapturedSpan>>>,
+}
+
+impl CaptureLayer {
+    /// Returns a copy of the events recorded so far, in the order they were emitted.
+    pub fn events(&self) -> Vec<CapturedEvent> {
+        self.events.lock().clone()
+    }
+
+    /// Returns a copy of the spans recorded so far, in the order they were created.
+    pub fn spans(&self) -> Vec<CapturedSpan> {
+        self.spans.lock().clone()
+    }
+}
+
+/// SpanFields holds the fields a span was created with, in the span's extensions.
+struct SpanFields(HashMap<String, String>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CaptureLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut()
+                .insert(SpanFields(visitor.fields.clone()));
+        }
+        self.spans.lock().push(CapturedSpan {
+            name: attrs.metadata().name(),
+            fields: visitor.fields,
+        });
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        // walks the scope from the root span inwards, so that inner fields overwrite outer ones.
+        let mut span_fields = HashMap::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(fields) = span.extensions().get::<SpanFields>() {
+                    span_fields.extend(fields.0.clone());
+                }
+            }
+        }
+        self.events.lock().push(CapturedEvent {
+            level: *event.metadata().level(),
+            fields: visitor.fields,
+            span_fields,
+        });
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    fields: HashMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.fields
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+/// Runs `f` with a subscriber capturing every event emitted on the current thread, and returns the result of `f`
+/// together with the captured events. The subscriber is only the default for the duration of `f`, so events of
+/// other threads, and the global subscriber, are unaffected.
+pub fn capture_events<T>(f: impl FnOnce() -> T) -> (T, Vec<CapturedEvent>) {
+    let layer = CaptureLayer::default();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+    let result = tracing::subscriber::with_default(subscriber, f);
+    (result, layer.events())
+}
+
+/// Runs `f` like `capture_events`, but returns the spans created on the current thread instead.
+pub fn capture_spans<T>(f: impl FnOnce() -> T) -> (T, Vec<CapturedSpan>) {
+    let layer = CaptureLayer::default();
+    let subscriber = tracing_subscriber::registry().with(layer.clone());
+    let result = tracing::subscriber::with_default(subscriber, f);
+    (result, layer.spans())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies that events emitted inside `capture_events` are recorded with their level and fields, and that
+    /// events emitted afterwards are not.
+    #[test]
+    fn test_capture_events_records_fields() {
+        let (value, events) = capture_events(|| {
+            tracing::trace!(answer = 42u64, "inside");
+            7
+        });
+        tracing::trace!("outside");
+
+        assert_eq!(value, 7);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].level, Level::TRACE);
+        assert_eq!(
+            events[0].fields.get("answer").map(String::as_str),
+            Some("42")
+        );
+        assert_eq!(
+            events[0].fields.get("message").map(String::as_str),
+            Some("inside")
+        );
+    }
+
+    /// Verifies that spans created inside `capture_spans` are recorded with their name and fields.
+    #[test]
+    fn test_capture_spans_records_fields() {
+        let ((), spans) = capture_spans(|| {
+            let _span = tracing::trace_span!("outer", answer = 42u64).entered();
+        });
+
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].name, "outer");
+        assert_eq!(
+            spans[0].fields.get("answer").map(String::as_str),
+            Some("42")
+        );
+    }
+}
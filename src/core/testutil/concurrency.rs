@@ -0,0 +1,173 @@
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::{model, Address, ArrayLookupTable, Identifier, LookupTable, MembershipVector};
+use anyhow::anyhow;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+/// Number of levels the randomized operations are confined to, so that workers contend on a small
+/// set of (level, direction) slots and the mutually exclusive operations meaningfully interleave.
+const CONTENDED_LEVELS: usize = 10;
+
+/// Runs a randomized mix of read, write, and remove operations against `table` on a thread pool of
+/// `num_workers` threads, each worker performing `ops_per_worker` operations.
+///
+/// Every worker draws from its own RNG seeded with `seed + worker_index`, so the sequence of
+/// operations (and the identities written) is reproducible for a given seed; only the interleaving
+/// across workers is left to the scheduler. The workers call the table directly, so they contend on
+/// its own lock. As the interleaving is not known, reads are validated against invariants that hold
+/// under any interleaving: every identity written encodes the (level, direction) it is written to,
+/// so a read must return either nothing or an identity written to the slot it reads. Once all
+/// workers are done, the final state is checked the same way, and the neighbor lists and counts of
+/// each direction must agree with its entries.
+///
+/// Returns an error aggregating every validation failure observed across all workers.
+pub fn run_concurrent_ops(
+    table: ArrayLookupTable,
+    num_workers: usize,
+    ops_per_worker: usize,
+    seed: u64,
+) -> anyhow::Result<()> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_workers)
+        .build()
+        .map_err(|e| anyhow!("failed to build thread pool: {}", e))?;
+
+    let mut failures: Vec<String> = pool.install(|| {
+        (0..num_workers)
+            .into_par_iter()
+            .flat_map_iter(|worker| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(worker as u64));
+                let mut failures = Vec::new();
+
+                for _ in 0..ops_per_worker {
+                    let level = rng.random_range(0..CONTENDED_LEVELS);
+                    let direction = if rng.random_bool(0.5) {
+                        Direction::Left
+                    } else {
+                        Direction::Right
+                    };
+
+                    // Draw a random operation; 0: read, 1: write, 2: remove
+                    match rng.random_range(0..3) {
+                        0 => match table.get_entry(level, direction) {
+                            Ok(Some(read)) if !written_to(&read, level, direction) => {
+                                failures.push(format!(
+                                    "worker {worker}: read {read:?} at level {level} {direction} was not written there"
+                                ));
+                            }
+                            Ok(_) => {}
+                            Err(e) => failures.push(format!("worker {worker}: read failed: {e}")),
+                        },
+                        1 => {
+                            let identity = seeded_identity(&mut rng, level, direction);
+                            if let Err(e) = table.update_entry(identity, level, direction) {
+                                failures.push(format!("worker {worker}: write failed: {e}"));
+                            }
+                        }
+                        _ => {
+                            if let Err(e) = table.remove_entry(level, direction) {
+                                failures.push(format!("worker {worker}: remove failed: {e}"));
+                            }
+                        }
+                    }
+                }
+
+                failures
+            })
+            .collect()
+    });
+    failures.extend(final_state_failures(&table));
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} validation failures: {}",
+            failures.len(),
+            failures.join("; ")
+        ))
+    }
+}
+
+/// Checks the table once no worker touches it anymore: every entry was written to its own slot,
+/// and the neighbor list and count of each direction agree with the entries of that direction.
+fn final_state_failures(table: &ArrayLookupTable) -> Vec<String> {
+    let mut failures = Vec::new();
+    for direction in [Direction::Left, Direction::Right] {
+        let mut entries = Vec::new();
+        for level in 0..CONTENDED_LEVELS {
+            match table.get_entry(level, direction) {
+                Ok(Some(entry)) => {
+                    if !written_to(&entry, level, direction) {
+                        failures.push(format!(
+                            "final entry {entry:?} at level {level} {direction} was not written there"
+                        ));
+                    }
+                    entries.push((level, entry));
+                }
+                Ok(None) => {}
+                Err(e) => failures.push(format!("final read failed: {e}")),
+            }
+        }
+        let neighbors = match direction {
+            Direction::Left => table.left_neighbors(),
+            Direction::Right => table.right_neighbors(),
+        };
+        match neighbors {
+            Ok(neighbors) if neighbors != entries => failures.push(format!(
+                "{direction} neighbors {neighbors:?} do not match the entries {entries:?}"
+            )),
+            Ok(_) => {}
+            Err(e) => failures.push(format!("listing {direction} neighbors failed: {e}")),
+        }
+        match table.count_by_direction(direction) {
+            Ok(count) if count != entries.len() => failures.push(format!(
+                "{direction} count {count} does not match the {} entries",
+                entries.len()
+            )),
+            Ok(_) => {}
+            Err(e) => failures.push(format!("counting {direction} entries failed: {e}")),
+        }
+    }
+    failures
+}
+
+/// Returns true if `identity` encodes the given slot, i.e., it was written to that slot.
+fn written_to(identity: &Identity, level: usize, direction: Direction) -> bool {
+    let bytes = identity.id().to_bytes();
+    bytes[0] as usize == level && bytes[1] == direction_tag(direction)
+}
+
+fn direction_tag(direction: Direction) -> u8 {
+    match direction {
+        Direction::Left => 0,
+        Direction::Right => 1,
+    }
+}
+
+/// Draws an identity to write at the given slot from the given RNG, so that the written values are
+/// reproducible from a seed. The first two bytes of its identifier encode the slot, so that a read
+/// can tell where the identity was written.
+fn seeded_identity(rng: &mut StdRng, level: usize, direction: Direction) -> Identity {
+    let mut id_bytes = rng.random::<[u8; model::IDENTIFIER_SIZE_BYTES]>();
+    id_bytes[0] = level as u8;
+    id_bytes[1] = direction_tag(direction);
+    let id = Identifier::from_bytes(&id_bytes).expect("identifier of exact size must be valid");
+    let mem_vec = MembershipVector::from_bytes(&rng.random::<[u8; model::IDENTIFIER_SIZE_BYTES]>())
+        .expect("membership vector of exact size must be valid");
+    let port = rng.random_range(1024..=65535u16);
+    Identity::new(id, mem_vec, Address::new("localhost", &port.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    /// Runs the thread-pool stress helper with a small configuration and expects no validation
+    /// failures.
+    #[test]
+    fn test_run_concurrent_ops_small_configuration() {
+        let table = crate::core::ArrayLookupTable::new();
+        super::run_concurrent_ops(table, 4, 200, 42).expect("concurrent operations failed");
+    }
+}
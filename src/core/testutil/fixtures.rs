@@ -5,10 +5,13 @@ mod test_imports {
     pub use crate::core::{
         model, Address, ArrayLookupTable, Identifier, LookupTable, MembershipVector,
     };
+    pub use crate::network::mock::hub::NetworkHub;
+    pub use crate::network::Network;
+    pub(crate) use crate::node::base_node::BaseNode;
+    pub(crate) use crate::node::core::BaseCore;
     pub use rand::Rng;
 }
 
-use crate::core::model::identifier::{MAX, ZERO};
 use std::thread::JoinHandle;
 use std::time::Duration;
 use test_imports::*;
@@ -19,46 +22,40 @@ pub fn random_identifier() -> Identifier {
 
 /// Panics if `target` is `MAX`.
 pub fn random_identifier_greater_than(target: &Identifier) -> Identifier {
-    match *target {
-        ZERO => random_identifier(),
-        MAX => {
-            panic!("cannot generate a random identifier greater than the maximum identifier.");
-        }
-        _ => {
-            let mut bytes = target.to_bytes();
-            for byte in bytes.iter_mut().rev() {
-                if *byte < 0xFF {
-                    *byte += 1;
-                    break;
-                }
+    if target.is_zero() {
+        random_identifier()
+    } else if target.is_max() {
+        panic!("cannot generate a random identifier greater than the maximum identifier.");
+    } else {
+        let mut bytes = target.to_bytes();
+        for byte in bytes.iter_mut().rev() {
+            if *byte < 0xFF {
+                *byte += 1;
+                break;
             }
-            Identifier::from_bytes(&bytes).unwrap_or_else(|_| {
-                panic!("failed to create a valid identifier from bytes: {bytes:?}")
-            })
         }
+        Identifier::from_bytes(&bytes)
+            .unwrap_or_else(|_| panic!("failed to create a valid identifier from bytes: {bytes:?}"))
     }
 }
 
 /// Panics if `target` is `ZERO`.
 pub fn random_identifier_less_than(target: &Identifier) -> Identifier {
-    match *target {
-        ZERO => {
-            panic!("cannot generate a random identifier less than zero.");
-        }
-        MAX => random_identifier(),
-        _ => {
-            let mut bytes = target.to_bytes();
-            for byte in bytes.iter_mut() {
-                if *byte > 0x00 {
-                    *byte -= 1;
-                    break;
-                }
+    if target.is_zero() {
+        panic!("cannot generate a random identifier less than zero.");
+    } else if target.is_max() {
+        random_identifier()
+    } else {
+        let mut bytes = target.to_bytes();
+        for byte in bytes.iter_mut() {
+            if *byte > 0x00 {
+                *byte -= 1;
+                break;
             }
-
-            Identifier::from_bytes(&bytes).unwrap_or_else(|_| {
-                panic!("failed to create a valid identifier from bytes: {bytes:?}")
-            })
         }
+
+        Identifier::from_bytes(&bytes)
+            .unwrap_or_else(|_| panic!("failed to create a valid identifier from bytes: {bytes:?}"))
     }
 }
 
@@ -93,6 +90,37 @@ pub fn random_identities(n: usize) -> Vec<Identity> {
     (0..n).map(|_| random_identity()).collect()
 }
 
+/// Generates `n` identities clustered around `clusters` random anchor identities. Each identity
+/// copies the leading 1 to 4 bytes of both the identifier and the membership vector of a randomly
+/// chosen anchor, and randomizes the rest, so common prefixes are far more frequent than among
+/// uniformly random identities.
+/// Panics if `clusters` is zero while `n` is not.
+pub fn random_identities_clustered(n: usize, clusters: usize) -> Vec<Identity> {
+    assert!(
+        clusters > 0 || n == 0,
+        "cannot cluster identities around zero anchors."
+    );
+    let anchors = random_identities(clusters);
+    let mut rng = rand::rng();
+    (0..n)
+        .map(|_| {
+            let anchor = &anchors[rng.random_range(0..clusters)];
+            let prefix_len = rng.random_range(1..=4);
+
+            let mut id = random_identifier().to_bytes();
+            id[..prefix_len].copy_from_slice(&anchor.id().as_bytes()[..prefix_len]);
+            let mut mem_vec = random_membership_vector().to_bytes();
+            mem_vec[..prefix_len].copy_from_slice(&anchor.mem_vec().as_bytes()[..prefix_len]);
+
+            Identity::new(
+                Identifier::from_bytes(&id).unwrap(),
+                MembershipVector::from_bytes(&mem_vec).unwrap(),
+                random_address(),
+            )
+        })
+        .collect()
+}
+
 /// Inserts `2 * n` entries: at each level `i` in `0..n`, a `Left` and a `Right` neighbor.
 pub fn random_lookup_table(n: usize) -> ArrayLookupTable {
     let lt = ArrayLookupTable::new();
@@ -176,6 +204,60 @@ where
     }
 }
 
+/// Spawns a thread running `f` inside `span`, so that what `f` logs is correlated with the span, e.g., the node
+/// and search a test thread works on. The thread also runs under the subscriber that is the default on the calling
+/// thread, which owns the span, so that a subscriber set for the current thread only, e.g., by `capture_events`,
+/// sees the thread's events too.
+pub fn spawn_in_span<F, T>(span: &tracing::Span, f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let span = span.clone();
+    let dispatch = tracing::dispatcher::get_default(|dispatch| dispatch.clone());
+    std::thread::spawn(move || tracing::dispatcher::with_default(&dispatch, || span.in_scope(f)))
+}
+
+/// Creates the core of a node with the given identifier, membership vector, and lookup table, at a
+/// random address.
+pub(crate) fn new_core(
+    id: Identifier,
+    mem_vec: MembershipVector,
+    lt: Box<dyn LookupTable>,
+) -> Box<BaseCore> {
+    Box::new(BaseCore::new(
+        span_fixture(),
+        id,
+        mem_vec,
+        random_address(),
+        lt,
+    ))
+}
+
+/// Creates a node with the given membership vector and lookup table, reachable through `hub` under
+/// `id`, and registered as the event processor of its mock network.
+pub(crate) fn new_node_with(
+    hub: &NetworkHub,
+    id: Identifier,
+    mem_vec: MembershipVector,
+    lt: Box<dyn LookupTable>,
+) -> BaseNode {
+    let net = NetworkHub::new_mock_network(hub.clone(), id).expect("failed to create mock network");
+    BaseNode::new(span_fixture(), new_core(id, mem_vec, lt), net.clone_box())
+        .expect("failed to create BaseNode")
+}
+
+/// Creates an unjoined node with a random membership vector and an empty lookup table, reachable
+/// through `hub` under `id`.
+pub(crate) fn new_node(hub: &NetworkHub, id: Identifier) -> BaseNode {
+    new_node_with(
+        hub,
+        id,
+        random_membership_vector(),
+        Box::new(ArrayLookupTable::new()),
+    )
+}
+
 /// Initializes the global tracing subscriber at DEBUG level (idempotent via `try_init`) and returns
 /// a TRACE-level span.
 pub fn span_fixture() -> tracing::Span {
@@ -187,9 +269,86 @@ pub fn span_fixture() -> tracing::Span {
     tracing::span!(tracing::Level::TRACE, "test_span")
 }
 
+/// Polls `condition` on a blocking task (yielding between checks) until it is true or `timeout` elapses.
+pub async fn wait_until<F>(mut condition: F, timeout: Duration) -> Result<(), String>
+where
+    F: FnMut() -> bool + Send + 'static,
+{
+    let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
+
+    let condition_task = tokio::task::spawn_blocking(move || loop {
+        if condition() {
+            let _ = tx.send(Ok(()));
+            return;
+        }
+        std::thread::yield_now();
+    });
+
+    let result = match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err("channel closed unexpectedly".to_string()),
+        Err(_) => Err(format!("condition not met within timeout of {:?}", timeout)),
+    };
+
+    condition_task.abort();
+
+    result
+}
+
+#[cfg(test)]
 mod test {
     use crate::core::model::identifier::ComparisonResult::CompareLess;
     use crate::core::model::identifier::{MAX, ZERO};
+    use crate::core::testutil::capture::capture_events;
+
+    /// Verifies that an event logged by a thread spawned with `spawn_in_span` is seen by the
+    /// subscriber of the spawning thread, and carries the fields of the parent span.
+    #[test]
+    fn test_spawn_in_span_carries_parent_fields() {
+        let (value, events) = capture_events(|| {
+            let span = tracing::info_span!("search_thread", node = 7u64);
+            super::spawn_in_span(&span, || {
+                tracing::info!("inside");
+                42
+            })
+            .join()
+            .unwrap()
+        });
+
+        assert_eq!(value, 42);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].fields.get("message").map(String::as_str),
+            Some("inside")
+        );
+        assert_eq!(
+            events[0].span_fields.get("node").map(String::as_str),
+            Some("7")
+        );
+    }
+
+    /// Verifies that clustered identities share longer membership vector prefixes on average than
+    /// uniformly random ones.
+    #[test]
+    fn test_random_identities_clustered() {
+        let average_common_prefix = |identities: &[super::Identity]| {
+            let mut total = 0;
+            let mut pairs = 0;
+            for (i, a) in identities.iter().enumerate() {
+                for b in &identities[i + 1..] {
+                    total += a.mem_vec().common_prefix_bit(b.mem_vec());
+                    pairs += 1;
+                }
+            }
+            total as f64 / pairs as f64
+        };
+
+        let clustered = super::random_identities_clustered(64, 4);
+        assert_eq!(clustered.len(), 64);
+        let uniform = super::random_identities(64);
+
+        assert!(average_common_prefix(&clustered) > average_common_prefix(&uniform));
+    }
 
     #[test]
     fn test_random_identifiers() {
@@ -239,29 +398,3 @@ mod test {
         );
     }
 }
-
-/// Polls `condition` on a blocking task (yielding between checks) until it is true or `timeout` elapses.
-pub async fn wait_until<F>(mut condition: F, timeout: Duration) -> Result<(), String>
-where
-    F: FnMut() -> bool + Send + 'static,
-{
-    let (tx, rx) = tokio::sync::oneshot::channel::<Result<(), String>>();
-
-    let condition_task = tokio::task::spawn_blocking(move || loop {
-        if condition() {
-            let _ = tx.send(Ok(()));
-            return;
-        }
-        std::thread::yield_now();
-    });
-
-    let result = match tokio::time::timeout(timeout, rx).await {
-        Ok(Ok(result)) => result,
-        Ok(Err(_)) => Err("channel closed unexpectedly".to_string()),
-        Err(_) => Err(format!("condition not met within timeout of {:?}", timeout)),
-    };
-
-    condition_task.abort();
-
-    result
-}
@@ -1,4 +1,10 @@
 #[cfg(test)]
-pub(crate) mod fixtures;
+pub(crate) mod capture;
 #[cfg(test)]
+pub(crate) mod concurrency;
+#[cfg(any(test, feature = "bench"))]
+#[cfg_attr(not(test), allow(dead_code))] // only partly used by the benchmarks
+pub(crate) mod fixtures;
+#[cfg(any(test, feature = "bench"))]
+#[cfg_attr(not(test), allow(dead_code))] // only partly used by the benchmarks
 pub(crate) mod random;
@@ -1,3 +1,4 @@
+use crate::core::Identifier;
 use rand::Rng;
 
 /// Generate random bytes of the given size.
@@ -12,3 +13,62 @@ pub fn random_hex_str(size: usize) -> String {
     let bytes: Vec<u8> = (0..size).map(|_| rng.random::<u8>()).collect();
     hex::encode(bytes)
 }
+
+/// Counts how many identifiers fall into each of `buckets` equal-width buckets of the identifier
+/// space, placing each identifier by its top 8 bytes. Panics if `buckets` is zero.
+pub fn bucket_distribution(ids: &[Identifier], buckets: usize) -> Vec<usize> {
+    assert!(buckets > 0, "bucket count must be positive");
+    let mut counts = vec![0usize; buckets];
+    for id in ids {
+        let top = u64::from_be_bytes(
+            id.as_bytes()[..8]
+                .try_into()
+                .expect("identifier has 8 bytes"),
+        );
+        // scales the top 64 bits to a bucket index, so that buckets cover equal ranges.
+        let bucket = ((u128::from(top) * buckets as u128) >> 64) as usize;
+        counts[bucket] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::model::identifier::{MAX, ZERO};
+    use crate::core::testutil::fixtures::random_identifier;
+
+    /// Verifies that the extremes and the midpoint of the identifier space land in the first,
+    /// last, and middle buckets.
+    #[test]
+    fn test_bucket_distribution_boundaries() {
+        let mid = ZERO.midpoint(&MAX);
+        assert_eq!(bucket_distribution(&[ZERO], 4), vec![1, 0, 0, 0]);
+        assert_eq!(bucket_distribution(&[MAX], 4), vec![0, 0, 0, 1]);
+        assert_eq!(bucket_distribution(&[mid], 4), vec![0, 1, 0, 0]);
+        assert_eq!(bucket_distribution(&[], 3), vec![0, 0, 0]);
+    }
+
+    /// Statistical sanity check that `random_identifier` is uniform: across 100k identifiers and
+    /// 64 buckets, each bucket expects about 1563 identifiers with a standard deviation of about
+    /// 39, so a deviation of 15% (about six standard deviations) from the mean indicates a biased
+    /// generator rather than chance.
+    #[test]
+    fn test_random_identifier_is_uniform() {
+        let n = 100_000;
+        let buckets = 64;
+        let ids: Vec<Identifier> = (0..n).map(|_| random_identifier()).collect();
+
+        let counts = bucket_distribution(&ids, buckets);
+        assert_eq!(counts.iter().sum::<usize>(), n);
+        let mean = n as f64 / buckets as f64;
+        for (bucket, count) in counts.iter().enumerate() {
+            let deviation = (*count as f64 - mean).abs() / mean;
+            assert!(
+                deviation < 0.15,
+                "bucket {bucket} holds {count} identifiers, {:.1}% off the mean {mean}",
+                deviation * 100.0
+            );
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! `Arbitrary` implementation for `Event`, enabled by the `fuzzing` feature.
+
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::model::search::Nonce;
+use crate::core::{
+    IdSearchReq, IdSearchRes, Identifier, LinkReq, LookupTableLevel, NeighborReq, NeighborRes,
+    LOOKUP_TABLE_LEVELS,
+};
+use crate::network::Event;
+use arbitrary::{Arbitrary, Unstructured};
+
+/// Returns a level within the lookup table, so that generated events pass level validation.
+fn level(u: &mut Unstructured<'_>) -> arbitrary::Result<LookupTableLevel> {
+    u.int_in_range(0..=LOOKUP_TABLE_LEVELS - 1)
+}
+
+impl<'a> Arbitrary<'a> for Event {
+    /// Picks a variant from the first input byte and builds its payload from the rest.
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=12u8)? {
+            0 => Event::TestMessage(String::arbitrary(u)?),
+            1 => Event::SearchByIdRequest(IdSearchReq {
+                nonce: Nonce::arbitrary(u)?,
+                target: Identifier::arbitrary(u)?,
+                origin: Identifier::arbitrary(u)?,
+                level: level(u)?,
+                min_level: 0,
+                direction: Direction::arbitrary(u)?,
+                correlation: Option::arbitrary(u)?,
+                inclusive: bool::arbitrary(u)?,
+                trace_id: u128::arbitrary(u)?,
+                hops: usize::arbitrary(u)?,
+            }),
+            2 => Event::SearchByIdResponse(IdSearchRes {
+                nonce: Nonce::arbitrary(u)?,
+                target: Identifier::arbitrary(u)?,
+                origin: Identifier::arbitrary(u)?,
+                termination_level: level(u)?,
+                result: Identifier::arbitrary(u)?,
+                correlation: Option::arbitrary(u)?,
+                trace_id: u128::arbitrary(u)?,
+                hops: usize::arbitrary(u)?,
+                matched_identity: Option::arbitrary(u)?,
+                fallback_direction: Option::arbitrary(u)?,
+            }),
+            3 => Event::NeighborRequest(NeighborReq {
+                nonce: Nonce::arbitrary(u)?,
+                level: level(u)?,
+                direction: Direction::arbitrary(u)?,
+            }),
+            4 => Event::NeighborResponse(Box::new(NeighborRes {
+                nonce: Nonce::arbitrary(u)?,
+                responder: Identity::arbitrary(u)?,
+                neighbor: Option::arbitrary(u)?,
+            })),
+            5 => Event::LinkRequest(LinkReq {
+                identity: Identity::arbitrary(u)?,
+                level: level(u)?,
+                direction: Direction::arbitrary(u)?,
+            }),
+            6 => Event::Bootstrap {
+                new_node: Identity::arbitrary(u)?,
+                nonce: Nonce::arbitrary(u)?,
+            },
+            7 => Event::BootstrapResponse {
+                nonce: Nonce::arbitrary(u)?,
+                closest: Result::arbitrary(u)?,
+            },
+            8 => Event::NeighborGossip {
+                about: Identity::arbitrary(u)?,
+                level: level(u)?,
+                direction: Direction::arbitrary(u)?,
+            },
+            9 => Event::Leave(Identity::arbitrary(u)?),
+            10 => Event::Ping(Nonce::arbitrary(u)?),
+            11 => Event::Pong(Nonce::arbitrary(u)?),
+            _ => Event::Unknown {
+                tag: u8::arbitrary(u)?,
+                bytes: Vec::arbitrary(u)?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Verifies that every variant is built from some small buffer, and that generated levels
+    /// always fall within the lookup table.
+    #[test]
+    fn test_arbitrary_event_from_small_buffer() {
+        let mut kinds = HashSet::new();
+        for first in 0..=u8::MAX {
+            let mut data = vec![first];
+            data.extend((0..64).map(|i| first.wrapping_mul(31).wrapping_add(i)));
+            let event = Event::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            kinds.insert(event.kind());
+            match event {
+                Event::SearchByIdRequest(req) => assert!(req.level < LOOKUP_TABLE_LEVELS),
+                Event::NeighborGossip { level, .. } => assert!(level < LOOKUP_TABLE_LEVELS),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            kinds.len(),
+            13,
+            "not every variant was generated: {kinds:?}"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use crate::core::Identifier;
+use crate::network::{Envelope, Event, MessageProcessor, Network, NetworkError};
+use anyhow::{anyhow, Context};
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// Delivery is an event in transit over a channel, tagged with the identifier of its sender.
+type Delivery = (Identifier, Envelope);
+
+/// ChannelDirectory maps the identifier of every `ChannelNetwork` created on it to the sending half
+/// of that network's channel, so that networks on the same directory can reach each other.
+///
+/// Implements shallow cloning where cloned instances share the same underlying data.
+pub(crate) struct ChannelDirectory {
+    senders: Arc<RwLock<HashMap<Identifier, UnboundedSender<Delivery>>>>,
+}
+
+impl ChannelDirectory {
+    /// Creates a new empty directory.
+    pub(crate) fn new() -> Self {
+        ChannelDirectory {
+            senders: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+impl Clone for ChannelDirectory {
+    fn clone(&self) -> Self {
+        ChannelDirectory {
+            senders: Arc::clone(&self.senders),
+        }
+    }
+}
+
+/// ChannelNetwork is an in-process `Network` whose events travel over tokio channels instead of
+/// being dispatched synchronously on the sender's thread, as with `MockNetwork`. Each network owns
+/// the receiving half of an unbounded channel; sending an event pushes it onto the target's
+/// channel and returns at once, and a task spawned when the processor registers hands the events
+/// to the processor one at a time, in arrival order. Events arriving before a processor registers
+/// wait in the channel and are processed once it does.
+///
+/// Implements shallow cloning where cloned instances share the same underlying data.
+pub(crate) struct ChannelNetwork {
+    id: Identifier,
+    directory: ChannelDirectory,
+    // Taken by the delivery task once a processor registers.
+    receiver: Arc<Mutex<Option<UnboundedReceiver<Delivery>>>>,
+}
+
+impl ChannelNetwork {
+    /// Creates a new network for `id` and makes it reachable through `directory`. Returns an error
+    /// if the directory already has a network with the same identifier.
+    pub(crate) fn new(id: Identifier, directory: ChannelDirectory) -> anyhow::Result<Self> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        {
+            let mut senders = directory.senders.write();
+            if senders.contains_key(&id) {
+                return Err(anyhow!("network with identifier {} already exists", id));
+            }
+            senders.insert(id, sender);
+        }
+        Ok(ChannelNetwork {
+            id,
+            directory,
+            receiver: Arc::new(Mutex::new(Some(receiver))),
+        })
+    }
+}
+
+impl Clone for ChannelNetwork {
+    fn clone(&self) -> Self {
+        ChannelNetwork {
+            id: self.id,
+            directory: self.directory.clone(),
+            receiver: Arc::clone(&self.receiver),
+        }
+    }
+}
+
+impl Network for ChannelNetwork {
+    /// Pushes the event, stamped with the time it was sent, onto the target's channel. Returns a
+    /// `NetworkError::TargetNotFound` if the target is not in the directory, or a
+    /// `NetworkError::Dropped` if its channel is closed.
+    fn send_event(&self, target_id: Identifier, event: Event) -> anyhow::Result<()> {
+        let sender = self
+            .directory
+            .senders
+            .read()
+            .get(&target_id)
+            .cloned()
+            .ok_or(NetworkError::TargetNotFound(target_id))?;
+        sender.send((self.id, Envelope::new(event))).map_err(|_| {
+            anyhow::Error::new(NetworkError::Dropped(target_id))
+                .context(format!("channel of network {} is closed", target_id))
+        })
+    }
+
+    /// Registers an event processor, and spawns on the current tokio runtime the task delivering
+    /// the events of this network's channel to it. Only one processor can be registered at a
+    /// time; a `NetworkError::AlreadyRegistered` is returned if one already is, and an error if
+    /// called outside a tokio runtime.
+    /// Processors are synchronous and may block, e.g., on a response to a request they issued, so
+    /// each event is processed on the runtime's blocking thread pool rather than on the delivery
+    /// task; the task waits for one event to be processed before delivering the next, so events are
+    /// still processed one at a time in the order they arrived.
+    /// A failure to process an event is logged, and does not stop the delivery of later events.
+    fn register_processor(&self, processor: MessageProcessor) -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .context("channel network processor must be registered within a tokio runtime")?;
+        let mut receiver = self
+            .receiver
+            .lock()
+            .take()
+            .ok_or(NetworkError::AlreadyRegistered(self.id))?;
+
+        let id = self.id;
+        runtime.spawn(async move {
+            while let Some((origin_id, envelope)) = receiver.recv().await {
+                let processor = processor.clone();
+                let processed = tokio::task::spawn_blocking(move || {
+                    processor.process_incoming_envelope(origin_id, envelope)
+                })
+                .await;
+                match processed {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::warn!(
+                        "network {} failed to process event from {}: {}",
+                        id,
+                        origin_id,
+                        e
+                    ),
+                    Err(e) => tracing::warn!(
+                        "network {} panicked processing event from {}: {}",
+                        id,
+                        origin_id,
+                        e
+                    ),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn Network> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testutil::fixtures::random_identifier;
+    use crate::network::EventProcessorCore;
+    use std::time::Duration;
+
+    /// ForwardingProcessor forwards the origin and content of every test message it processes.
+    struct ForwardingProcessor {
+        forward: UnboundedSender<(Identifier, String)>,
+    }
+
+    impl EventProcessorCore for ForwardingProcessor {
+        fn process_incoming_event(
+            &self,
+            origin_id: Identifier,
+            event: Event,
+        ) -> anyhow::Result<()> {
+            match event {
+                Event::TestMessage(content) => {
+                    self.forward.send((origin_id, content))?;
+                    Ok(())
+                }
+                event => Err(anyhow!("unexpected event {:?}", event)),
+            }
+        }
+    }
+
+    /// Verifies that a test message sent from one channel network reaches the processor of
+    /// another on the same directory, tagged with the sender's identifier, that a message sent
+    /// before the receiver registered its processor is delivered first, and that an unknown target
+    /// is reported as not found.
+    #[tokio::test]
+    async fn test_channel_network_delivers_test_message() {
+        let directory = ChannelDirectory::new();
+        let (id_1, id_2) = (random_identifier(), random_identifier());
+        let net_1 = ChannelNetwork::new(id_1, directory.clone()).unwrap();
+        let net_2 = ChannelNetwork::new(id_2, directory.clone()).unwrap();
+        assert!(ChannelNetwork::new(id_2, directory.clone()).is_err());
+
+        net_1
+            .send_event(id_2, Event::TestMessage("early".to_string()))
+            .unwrap();
+        let (forward, mut delivered) = mpsc::unbounded_channel();
+        net_2
+            .register_processor(MessageProcessor::new(Box::new(ForwardingProcessor {
+                forward,
+            })))
+            .unwrap();
+        net_1
+            .send_event(id_2, Event::TestMessage("hello".to_string()))
+            .unwrap();
+
+        for expected in ["early", "hello"] {
+            let (origin_id, content) =
+                tokio::time::timeout(Duration::from_secs(1), delivered.recv())
+                    .await
+                    .expect("timed out waiting for delivery")
+                    .expect("processor dropped");
+            assert_eq!(origin_id, id_1);
+            assert_eq!(content, expected);
+        }
+
+        let unknown = random_identifier();
+        let err = net_1
+            .send_event(unknown, Event::TestMessage("lost".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            NetworkError::from_error(&err),
+            Some(NetworkError::TargetNotFound(unknown))
+        );
+    }
+
+    /// BlockingProcessor blocks on every test message until it is released, and forwards whether
+    /// it was released in time.
+    struct BlockingProcessor {
+        release: std::sync::Mutex<std::sync::mpsc::Receiver<()>>,
+        forward: UnboundedSender<bool>,
+    }
+
+    impl EventProcessorCore for BlockingProcessor {
+        fn process_incoming_event(&self, _: Identifier, event: Event) -> anyhow::Result<()> {
+            match event {
+                Event::TestMessage(_) => {
+                    let released = self
+                        .release
+                        .lock()
+                        .unwrap()
+                        .recv_timeout(Duration::from_secs(2))
+                        .is_ok();
+                    self.forward.send(released)?;
+                    Ok(())
+                }
+                event => Err(anyhow!("unexpected event {:?}", event)),
+            }
+        }
+    }
+
+    /// Verifies that a processor blocking on an event does not stall the runtime delivering it:
+    /// on a single-threaded runtime, the test task still gets to release the blocked processor.
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_channel_network_blocking_processor_does_not_stall_runtime() {
+        let directory = ChannelDirectory::new();
+        let (id_1, id_2) = (random_identifier(), random_identifier());
+        let net_1 = ChannelNetwork::new(id_1, directory.clone()).unwrap();
+        let net_2 = ChannelNetwork::new(id_2, directory.clone()).unwrap();
+        let (release_tx, release_rx) = std::sync::mpsc::channel();
+        let (forward, mut delivered) = mpsc::unbounded_channel();
+        net_2
+            .register_processor(MessageProcessor::new(Box::new(BlockingProcessor {
+                release: std::sync::Mutex::new(release_rx),
+                forward,
+            })))
+            .unwrap();
+
+        net_1
+            .send_event(id_2, Event::TestMessage("block".to_string()))
+            .unwrap();
+        // lets the delivery task hand the event to the processor before releasing it.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        release_tx.send(()).unwrap();
+
+        let released = tokio::time::timeout(Duration::from_secs(5), delivered.recv())
+            .await
+            .expect("timed out waiting for delivery")
+            .expect("processor dropped");
+        assert!(released, "processor was not released while blocked");
+    }
+}
@@ -0,0 +1,141 @@
+use crate::core::Identifier;
+use crate::network::{Event, MessageProcessor, Network, NetworkError};
+use parking_lot::{Mutex, RwLock};
+use std::sync::Arc;
+
+/// SentEvent is a single `send_event` call recorded by a `CountingNetwork`.
+#[derive(Debug, Clone)]
+pub(crate) struct SentEvent {
+    /// Identifier of the network the event was sent from.
+    pub(crate) origin_id: Identifier,
+    /// Identifier the event was addressed to.
+    pub(crate) target_id: Identifier,
+    /// The event that was sent.
+    pub(crate) event: Event,
+}
+
+/// CountingNetwork is a standalone `Network` for unit-testing a node without a `NetworkHub`.
+/// Nothing is delivered: every `send_event` call is recorded so tests can assert exactly what was
+/// sent, and the registered processor is kept so tests can feed events to the node directly.
+///
+/// Implements shallow cloning where cloned instances share the same recorded events and processor.
+pub(crate) struct CountingNetwork {
+    id: Identifier,
+    sent: Arc<Mutex<Vec<SentEvent>>>,
+    processor: Arc<RwLock<Option<MessageProcessor>>>,
+}
+
+impl CountingNetwork {
+    /// Creates a new network recording the events sent from `id`.
+    pub(crate) fn new(id: Identifier) -> Self {
+        CountingNetwork {
+            id,
+            sent: Arc::new(Mutex::new(Vec::new())),
+            processor: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Returns every event sent so far, in send order.
+    pub(crate) fn sent(&self) -> Vec<SentEvent> {
+        self.sent.lock().clone()
+    }
+
+    /// Returns the registered processor, if any.
+    pub(crate) fn processor(&self) -> Option<MessageProcessor> {
+        self.processor.read().clone()
+    }
+}
+
+impl Clone for CountingNetwork {
+    fn clone(&self) -> Self {
+        CountingNetwork {
+            id: self.id,
+            sent: Arc::clone(&self.sent),
+            processor: Arc::clone(&self.processor),
+        }
+    }
+}
+
+impl Network for CountingNetwork {
+    /// Records the event instead of sending it.
+    fn send_event(&self, target_id: Identifier, event: Event) -> anyhow::Result<()> {
+        self.sent.lock().push(SentEvent {
+            origin_id: self.id,
+            target_id,
+            event,
+        });
+        Ok(())
+    }
+
+    /// Registers an event processor to handle incoming events.
+    /// Only one processor can be registered at a time.
+    fn register_processor(&self, processor: MessageProcessor) -> anyhow::Result<()> {
+        let mut slot = self.processor.write();
+
+        match slot.as_ref() {
+            Some(_) => Err(NetworkError::AlreadyRegistered(self.id).into()),
+            None => {
+                *slot = Some(processor);
+                Ok(())
+            }
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn Network> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::model::direction::Direction;
+    use crate::core::testutil::fixtures::{
+        random_address, random_identifier, random_membership_vector, span_fixture,
+    };
+    use crate::core::{ArrayLookupTable, IdSearchReq};
+    use crate::node::base_node::BaseNode;
+    use crate::node::core::BaseCore;
+
+    /// Verifies that a node built on a `CountingNetwork` registers itself as the processor, and
+    /// that the search response it sends when it is the search target is recorded verbatim.
+    #[test]
+    fn test_counting_network_records_search_response() {
+        let node_id = random_identifier();
+        let origin_id = random_identifier();
+        let net = CountingNetwork::new(node_id);
+        let core = Box::new(BaseCore::new(
+            span_fixture(),
+            node_id,
+            random_membership_vector(),
+            random_address(),
+            Box::new(ArrayLookupTable::new()),
+        ));
+        let _node = BaseNode::new(span_fixture(), core, net.clone_box())
+            .expect("failed to create BaseNode");
+
+        let req = IdSearchReq::builder(origin_id)
+            .target(node_id)
+            .direction(Direction::Left)
+            .correlation(7)
+            .build()
+            .unwrap();
+        net.processor()
+            .expect("node should have registered as the processor")
+            .process_incoming_event(origin_id, Event::SearchByIdRequest(req))
+            .expect("failed to process request event");
+
+        let sent = net.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].origin_id, node_id);
+        assert_eq!(sent[0].target_id, origin_id);
+        match &sent[0].event {
+            Event::SearchByIdResponse(res) => {
+                assert_eq!(res.nonce, req.nonce);
+                assert_eq!(res.result, node_id);
+                assert_eq!(res.correlation, Some(7));
+            }
+            event => panic!("expected IdSearchResponse payload, got: {:?}", event),
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use crate::core::Identifier;
 use crate::network::mock::network::MockNetwork;
-use crate::network::Event;
-use anyhow::anyhow;
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use crate::network::{Envelope, Event, NetworkError};
+use anyhow::{anyhow, Context};
+use parking_lot::{Mutex, ReentrantMutex, RwLock};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Instant;
 
 /// NetworkHub is a central hub that manages multiple mock networks.
 /// It allows for the creation of new mock networks and routing events between them.
@@ -13,20 +14,223 @@ use std::sync::Arc;
 /// Thread-safety is handled internally using RwLock for the networks map, following a Go-like approach
 /// where the struct can be safely shared via Arc<NetworkHub> without external locking.
 ///
+/// Events are dispatched on the sender's thread, so by default a target's processor may be invoked
+/// concurrently for events from different senders, and their relative order is undefined. When
+/// per-target serialization is enabled, deliveries to the same target are serialized behind a
+/// per-target lock, so each target processes one event at a time and events from any single
+/// sender are processed in the order they were sent. The lock is reentrant, so a delivery that
+/// synchronously routes an event back to the same target does not deadlock. Serialization can
+/// also be enabled for a single target by creating its network with `new_ordered_mock_network`.
+///
+/// A deterministic hub does not dispatch events as they are routed, but appends them to a queue
+/// that `run_until_quiescent` drains on the calling thread, one event at a time in the order they
+/// were routed, until processing no longer routes new events. Test runs over such a hub process
+/// the same events in the same order every time. Nothing is delivered while the queue is not
+/// being drained, so a blocking node API that waits for a response (e.g., `search_by_id`, `join`,
+/// or `bootstrap`) never gets one when called on the thread that drains the queue: the search
+/// deadlocks, and join and bootstrap fail once their response timeout elapses. Over a deterministic
+/// hub, drive nodes by routing their events and draining the queue instead.
+///
 /// Implements shallow cloning where cloned instances share the same underlying data.
 pub struct NetworkHub {
-    networks: Arc<RwLock<HashMap<Identifier, Arc<MockNetwork>>>>,
+    networks: Arc<RwLock<HashMap<Identifier, Route>>>,
+    serialize_per_target: bool,
+    // Ring buffer of the most recently routed events; only present when logging is enabled.
+    event_log: Option<Arc<Mutex<EventLog>>>,
+    // Events routed but not yet dispatched; only present when the hub is deterministic.
+    queue: Option<Arc<Mutex<VecDeque<QueuedEvent>>>>,
+    // Maximum number of networks registered at once; unbounded when absent.
+    capacity: Option<usize>,
 }
 
-impl NetworkHub {
-    pub fn new() -> Self {
+/// NetworkHubBuilder builds a `NetworkHub`. Its options combine freely, e.g., a deterministic hub
+/// may also log the events it routes.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NetworkHubBuilder {
+    serialize_per_target: bool,
+    log_capacity: Option<usize>,
+    deterministic: bool,
+    capacity: Option<usize>,
+}
+
+impl NetworkHubBuilder {
+    /// Sets whether deliveries to the same target are serialized behind a per-target lock.
+    pub fn per_target_serialization(mut self, enabled: bool) -> Self {
+        self.serialize_per_target = enabled;
+        self
+    }
+
+    /// Makes the hub log the last `capacity` events it routes, oldest first; see `event_log`. An
+    /// event is logged when the hub routes it, whether or not its delivery succeeds. The log is
+    /// shared across clones of the hub.
+    pub fn logging(mut self, capacity: usize) -> Self {
+        self.log_capacity = Some(capacity);
+        self
+    }
+
+    /// Makes the hub deterministic, so that its events are only dispatched by
+    /// `run_until_quiescent`. Routing an event to a target that is not registered still fails at
+    /// once with `NetworkError::TargetNotFound`.
+    pub fn deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    /// Caps the hub at `max` registered mock networks at once, to simulate an overlay with a
+    /// membership cap. Creating a mock network on a full hub fails with
+    /// `NetworkError::AtCapacity`; removing one frees its slot.
+    pub fn capacity(mut self, max: usize) -> Self {
+        self.capacity = Some(max);
+        self
+    }
+
+    /// Builds the hub.
+    pub fn build(self) -> NetworkHub {
         NetworkHub {
             networks: Arc::new(RwLock::new(HashMap::new())),
+            serialize_per_target: self.serialize_per_target,
+            event_log: self.log_capacity.map(|capacity| {
+                Arc::new(Mutex::new(EventLog {
+                    entries: VecDeque::with_capacity(capacity),
+                    capacity,
+                }))
+            }),
+            queue: self
+                .deterministic
+                .then(|| Arc::new(Mutex::new(VecDeque::new()))),
+            capacity: self.capacity,
+        }
+    }
+}
+
+/// LoggedEvent is a record of one event routed by a `NetworkHub`: who sent it to whom, its kind,
+/// and when the hub routed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggedEvent {
+    pub origin: Identifier,
+    pub target: Identifier,
+    pub kind: &'static str,
+    pub timestamp: Instant,
+}
+
+struct EventLog {
+    entries: VecDeque<LoggedEvent>,
+    capacity: usize,
+}
+
+/// QueuedEvent is an event routed by a deterministic hub that awaits dispatch.
+struct QueuedEvent {
+    origin: Identifier,
+    target: Identifier,
+    envelope: Envelope,
+}
+
+/// Route is the hub's view of a registered mock network; cloning it is shallow.
+#[derive(Clone)]
+struct Route {
+    network: Arc<MockNetwork>,
+    // Serializes deliveries to this network; only present when per-target serialization is enabled.
+    delivery_lock: Option<Arc<ReentrantMutex<()>>>,
+}
+
+impl NetworkHub {
+    /// Creates a new hub that dispatches events on routing, with no per-target serialization,
+    /// logging, or capacity; see `builder` to enable them.
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Returns a builder for a hub, with every option disabled by default.
+    pub fn builder() -> NetworkHubBuilder {
+        NetworkHubBuilder::default()
+    }
+
+    /// Dispatches the queued events of a deterministic hub on the calling thread, oldest first,
+    /// including the events routed while processing them, until the queue is empty. Returns the
+    /// dispatched events in the order they were processed, each stamped with its dispatch time.
+    /// Stops at the first event whose delivery fails, leaving the events behind it queued.
+    pub fn run_until_quiescent(&self) -> anyhow::Result<Vec<LoggedEvent>> {
+        let queue = self
+            .queue
+            .as_ref()
+            .ok_or_else(|| anyhow!("hub is not deterministic, events are dispatched on routing"))?;
+        let mut processed = Vec::new();
+        loop {
+            // the queue lock is released before delivering, since processing an event may route
+            // further events onto the queue.
+            let Some(queued) = queue.lock().pop_front() else {
+                return Ok(processed);
+            };
+            let route = self
+                .resolve(&[queued.target])
+                .pop()
+                .expect("one route per target");
+            let kind = queued.envelope.event.kind();
+            Self::deliver(route, queued.origin, queued.target, queued.envelope).map_err(|e| {
+                anyhow!(
+                    "failed to dispatch {} event from {} to {}: {}",
+                    kind,
+                    queued.origin,
+                    queued.target,
+                    e
+                )
+            })?;
+            processed.push(LoggedEvent {
+                origin: queued.origin,
+                target: queued.target,
+                kind,
+                timestamp: Instant::now(),
+            });
         }
     }
 
+    /// Returns the logged events, in the order they were routed; empty if logging is disabled.
+    pub fn event_log(&self) -> Vec<LoggedEvent> {
+        self.event_log
+            .as_ref()
+            .map(|log| log.lock().entries.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
     /// Creates a new mock network with the given identifier and registers it in the hub.
     pub fn new_mock_network(hub: Self, identifier: Identifier) -> anyhow::Result<Arc<MockNetwork>> {
+        let network = MockNetwork::new(identifier, hub.clone());
+        let serialize = hub.serialize_per_target;
+        Self::add_mock_network(hub, identifier, network, serialize)
+    }
+
+    /// Creates a new mock network with the given identifier that buffers up to `capacity` events
+    /// arriving before its processor registers (see `MockNetwork::new_buffered`), and registers it
+    /// in the hub.
+    pub fn new_buffered_mock_network(
+        hub: Self,
+        identifier: Identifier,
+        capacity: usize,
+    ) -> anyhow::Result<Arc<MockNetwork>> {
+        let network = MockNetwork::new_buffered(identifier, hub.clone(), capacity);
+        let serialize = hub.serialize_per_target;
+        Self::add_mock_network(hub, identifier, network, serialize)
+    }
+
+    /// Creates a new mock network with the given identifier and registers it in the hub with its
+    /// deliveries serialized, as on a hub with per-target serialization enabled, whether or not
+    /// the hub serializes its other networks.
+    pub fn new_ordered_mock_network(
+        hub: Self,
+        identifier: Identifier,
+    ) -> anyhow::Result<Arc<MockNetwork>> {
+        let network = MockNetwork::new(identifier, hub.clone());
+        Self::add_mock_network(hub, identifier, network, true)
+    }
+
+    /// Registers `network` in the hub under `identifier`, behind a delivery lock if `serialize`
+    /// is true.
+    fn add_mock_network(
+        hub: Self,
+        identifier: Identifier,
+        network: MockNetwork,
+        serialize: bool,
+    ) -> anyhow::Result<Arc<MockNetwork>> {
         let mut networks = hub.networks.write();
 
         if networks.contains_key(&identifier) {
@@ -35,37 +239,164 @@ impl NetworkHub {
                 identifier
             ));
         }
+        if let Some(capacity) = hub.capacity {
+            if networks.len() >= capacity {
+                return Err(anyhow::Error::new(NetworkError::AtCapacity(capacity))
+                    .context(format!("cannot register network {}", identifier)));
+            }
+        }
 
-        let mock_network = Arc::new(MockNetwork::new(identifier, hub.clone()));
-        networks.insert(identifier, mock_network.clone());
+        let mock_network = Arc::new(network);
+        let delivery_lock = serialize.then(|| Arc::new(ReentrantMutex::new(())));
+        networks.insert(
+            identifier,
+            Route {
+                network: mock_network.clone(),
+                delivery_lock,
+            },
+        );
         Ok(mock_network)
     }
 
+    /// Unregisters the mock network with the given identifier, e.g., to simulate a node that went
+    /// away. Events routed to it afterwards fail with `NetworkError::TargetNotFound`.
+    pub fn remove_mock_network(&self, identifier: Identifier) -> anyhow::Result<()> {
+        self.networks
+            .write()
+            .remove(&identifier)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("network with identifier {} not found", identifier))
+    }
+
     // TODO: route_event should be a closure that embeds the origin_id.
     /// Routes an event to the appropriate mock network based on the target node identifier.
+    /// The event is stamped as sent at the time of this call.
     pub fn route_event(
         &self,
         origin_id: Identifier,
         target_id: Identifier,
         event: Event,
     ) -> anyhow::Result<()> {
+        self.route_envelope(origin_id, target_id, Envelope::new(event))
+    }
+
+    /// Routes an already stamped envelope to the appropriate mock network based on the target node identifier.
+    pub fn route_envelope(
+        &self,
+        origin_id: Identifier,
+        target_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        // Resolve the route and release the map lock before delivering, so that a delivery that
+        // routes further events does not re-acquire the map lock recursively.
+        let route = self
+            .resolve(&[target_id])
+            .pop()
+            .expect("one route per target");
+        self.log_event(origin_id, target_id, &envelope.event);
+        self.dispatch(route, origin_id, target_id, envelope)
+    }
+
+    /// Routes a copy of the event to each target, resolving all targets under a single read of
+    /// the networks map. Returns one result per target, in the order of `target_ids`; every copy
+    /// carries the same send time.
+    pub fn route_event_multi(
+        &self,
+        origin_id: Identifier,
+        target_ids: &[Identifier],
+        event: Event,
+    ) -> Vec<anyhow::Result<()>> {
+        let envelope = Envelope::new(event);
+        self.resolve(target_ids)
+            .into_iter()
+            .zip(target_ids)
+            .map(|(route, target_id)| {
+                self.log_event(origin_id, *target_id, &envelope.event);
+                self.dispatch(route, origin_id, *target_id, envelope.clone())
+            })
+            .collect()
+    }
+
+    /// Appends the event to the log if logging is enabled, evicting the oldest entry when full.
+    fn log_event(&self, origin: Identifier, target: Identifier, event: &Event) {
+        let Some(log) = self.event_log.as_ref() else {
+            return;
+        };
+        let mut log = log.lock();
+        if log.capacity == 0 {
+            return;
+        }
+        if log.entries.len() == log.capacity {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(LoggedEvent {
+            origin,
+            target,
+            kind: event.kind(),
+            timestamp: Instant::now(),
+        });
+    }
+
+    /// Looks up the network and delivery lock of each target, in order.
+    fn resolve(&self, target_ids: &[Identifier]) -> Vec<Option<Route>> {
         let networks = self.networks.read();
+        target_ids
+            .iter()
+            .map(|target_id| networks.get(target_id).cloned())
+            .collect()
+    }
 
-        if let Some(network) = networks.get(&target_id) {
-            network
-                .incoming_event(origin_id, event)
-                .map_err(|e| anyhow!("hub failed to process routing event: {}", e))?;
-            Ok(())
-        } else {
-            Err(anyhow!("network with identifier {} not found", target_id))
+    /// Queues the envelope for a resolved target if the hub is deterministic, and delivers it at
+    /// once otherwise. An unresolved target is reported as not found in either case.
+    fn dispatch(
+        &self,
+        route: Option<Route>,
+        origin_id: Identifier,
+        target_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        match (&self.queue, route) {
+            (Some(queue), Some(_)) => {
+                queue.lock().push_back(QueuedEvent {
+                    origin: origin_id,
+                    target: target_id,
+                    envelope,
+                });
+                Ok(())
+            }
+            (_, route) => Self::deliver(route, origin_id, target_id, envelope),
         }
     }
+
+    /// Delivers the envelope to a resolved target, serializing on its delivery lock if present.
+    fn deliver(
+        route: Option<Route>,
+        origin_id: Identifier,
+        target_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        let Some(route) = route else {
+            return Err(anyhow::Error::new(NetworkError::TargetNotFound(target_id)));
+        };
+
+        let _delivery_guard = route.delivery_lock.as_ref().map(|lock| lock.lock());
+        // keeps the original error in the chain, so that callers can still recover a
+        // `NetworkError` from it.
+        route
+            .network
+            .incoming_envelope(origin_id, envelope)
+            .context("hub failed to process routing event")
+    }
 }
 
 impl Clone for NetworkHub {
     fn clone(&self) -> Self {
         NetworkHub {
             networks: Arc::clone(&self.networks),
+            serialize_per_target: self.serialize_per_target,
+            event_log: self.event_log.clone(),
+            queue: self.queue.clone(),
+            capacity: self.capacity,
         }
     }
 }
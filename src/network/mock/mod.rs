@@ -1,6 +1,12 @@
 #[cfg(test)]
-pub(crate) mod hub;
+pub(crate) mod channel_network;
 #[cfg(test)]
+pub(crate) mod counting_network;
+#[cfg(any(test, feature = "bench"))]
+#[cfg_attr(not(test), allow(dead_code))] // only partly used by the benchmarks
+pub(crate) mod hub;
+#[cfg(any(test, feature = "bench"))]
+#[cfg_attr(not(test), allow(dead_code))] // only partly used by the benchmarks
 mod network;
 #[cfg(test)]
 mod network_test;
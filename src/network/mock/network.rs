@@ -1,9 +1,12 @@
 use crate::core::Identifier;
 use crate::network::mock::hub::NetworkHub;
-use crate::network::{Event, MessageProcessor, Network};
-use anyhow::{anyhow, Context};
-use parking_lot::RwLock;
+use crate::network::{Envelope, Event, MessageProcessor, Network, NetworkError};
+use anyhow::Context;
+use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
 /// MockNetwork is a mock implementation of the Network trait for testing purposes.
 /// It does not perform any real network operations but simulates event routing and processing through a `NetworkHub`.
@@ -11,12 +14,26 @@ use std::sync::Arc;
 /// MessageProcessor is inherently thread-safe, so we only need a simple Option wrapper.
 pub struct MockNetwork {
     core: Arc<RwLock<InnerMockNetwork>>,
+    // Kept apart from `core`, whose lock a node holds while it sends, so that delivering an
+    // event to a node that is itself sending does not deadlock.
+    delivery: Arc<Mutex<Delivery>>,
 }
 
 struct InnerMockNetwork {
     hub: NetworkHub,
     processor: Option<MessageProcessor>,
     id: Identifier, // Identifier of the mock network
+    // Events that arrived before a processor registered; only present for buffered networks.
+    buffer: Option<VecDeque<(Identifier, Envelope)>>,
+    buffer_capacity: usize,
+}
+
+#[derive(Default)]
+struct Delivery {
+    // How long every incoming event is held before it is handed to the processor.
+    delay: Duration,
+    // Number of delivered events per latency bucket, keyed by the latency rounded down to the millisecond.
+    latencies: BTreeMap<Duration, usize>,
 }
 
 impl MockNetwork {
@@ -27,25 +44,106 @@ impl MockNetwork {
                 hub,
                 processor: None,
                 id,
+                buffer: None,
+                buffer_capacity: 0,
             })),
+            delivery: Arc::new(Mutex::new(Delivery::default())),
         }
     }
 
+    /// Creates a new instance of MockNetwork that, instead of rejecting events arriving before a
+    /// processor registers, buffers up to `capacity` of them and delivers them, in arrival order,
+    /// once a processor registers. Events beyond the capacity are rejected.
+    pub fn new_buffered(id: Identifier, hub: NetworkHub, capacity: usize) -> Self {
+        MockNetwork {
+            core: Arc::new(RwLock::new(InnerMockNetwork {
+                hub,
+                processor: None,
+                id,
+                buffer: Some(VecDeque::with_capacity(capacity)),
+                buffer_capacity: capacity,
+            })),
+            delivery: Arc::new(Mutex::new(Delivery::default())),
+        }
+    }
+
+    /// Sets how long every incoming event is held before it is handed to the processor, to
+    /// simulate a slow link. The delay is zero by default.
+    pub fn set_delivery_delay(&self, delay: Duration) {
+        self.delivery.lock().delay = delay;
+    }
+
+    /// Returns the latencies of the events delivered to the processor so far, from their send
+    /// time to their hand-off, as `(bucket, count)` pairs in increasing bucket order. Each bucket
+    /// holds the latencies from its duration up to the next millisecond.
+    pub fn latency_histogram(&self) -> Vec<(Duration, usize)> {
+        self.delivery
+            .lock()
+            .latencies
+            .iter()
+            .map(|(bucket, count)| (*bucket, *count))
+            .collect()
+    }
+
+    /// Holds the envelope for the delivery delay, records its latency, and hands it to the
+    /// processor.
+    fn deliver(
+        &self,
+        processor: &MessageProcessor,
+        origin_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        let delay = self.delivery.lock().delay;
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        let latency = envelope.sent_at.elapsed();
+        let bucket = Duration::from_millis(latency.as_millis() as u64);
+        *self.delivery.lock().latencies.entry(bucket).or_insert(0) += 1;
+
+        processor.process_incoming_envelope(origin_id, envelope)
+    }
+
     /// This is the event handler for processing incoming events come through the mock network.
     /// Arguments:
     /// * `event`: The incoming event to be processed.
     ///   Returns:
     /// * `Result<(), anyhow::Error>`: Returns Ok if the event was processed successfully, or an error if processing failed.
     pub fn incoming_event(&self, origin_id: Identifier, event: Event) -> anyhow::Result<()> {
-        let core_guard = self.core.read();
+        self.incoming_envelope(origin_id, Envelope::new(event))
+    }
 
-        let processor = match core_guard.processor.as_ref() {
-            Some(p) => p,
-            None => return Err(anyhow!("no event processor registered")),
+    /// This is the event handler for processing incoming envelopes come through the mock network,
+    /// handing the processor the event along with the time it was sent.
+    pub fn incoming_envelope(
+        &self,
+        origin_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        let processor = {
+            let core_guard = self.core.upgradable_read();
+            match core_guard.processor.as_ref() {
+                Some(p) => p.clone(),
+                None => {
+                    let mut core_guard = RwLockUpgradableReadGuard::upgrade(core_guard);
+                    let capacity = core_guard.buffer_capacity;
+                    let id = core_guard.id;
+                    return match core_guard.buffer.as_mut() {
+                        None => Err(NetworkError::NoProcessor(id).into()),
+                        Some(buffer) if buffer.len() >= capacity => Err(anyhow::Error::new(
+                            NetworkError::Dropped(id),
+                        )
+                        .context("no event processor registered and the event buffer is full")),
+                        Some(buffer) => {
+                            buffer.push_back((origin_id, envelope));
+                            Ok(())
+                        }
+                    };
+                }
+            }
         };
 
-        processor
-            .process_incoming_event(origin_id, event)
+        self.deliver(&processor, origin_id, envelope)
             .context("failed to process incoming event")
     }
 }
@@ -54,37 +152,76 @@ impl Clone for MockNetwork {
     fn clone(&self) -> Self {
         MockNetwork {
             core: self.core.clone(),
+            delivery: self.delivery.clone(),
         }
     }
 }
 
 impl Network for MockNetwork {
-    /// Sends an event through the mock network by routing it through the NetworkHub.
+    /// Sends an event through the mock network by routing it through the NetworkHub,
+    /// stamped with the time it was sent.
     fn send_event(&self, target_id: Identifier, event: Event) -> anyhow::Result<()> {
         let core_guard = self.core.read();
 
         core_guard
             .hub
-            .route_event(core_guard.id, target_id, event)
-            .map_err(|e| anyhow!("failed to route event: {}", e))
+            .route_envelope(core_guard.id, target_id, Envelope::new(event))
+            .map_err(route_error)
+    }
+
+    /// Sends the event to all targets in one batch through the NetworkHub, which resolves every
+    /// target under a single lookup instead of one lookup per target.
+    fn send_event_multi(&self, targets: &[Identifier], event: Event) -> Vec<anyhow::Result<()>> {
+        let core_guard = self.core.read();
+
+        core_guard
+            .hub
+            .route_event_multi(core_guard.id, targets, event)
+            .into_iter()
+            .map(|result| result.map_err(route_error))
+            .collect()
     }
 
     /// Registers an event processor to handle incoming events.
     /// Only one processor can be registered at a time.
     /// If a processor is already registered, an error is returned.
+    /// On a buffered network, the events buffered so far are then delivered to the processor in
+    /// arrival order, outside the network's lock so that handlers may send events; an event
+    /// arriving meanwhile may be processed before them. The processor stays registered even if
+    /// a buffered event fails, and the first such failure is returned.
     fn register_processor(&self, processor: MessageProcessor) -> anyhow::Result<()> {
-        let mut core_guard = self.core.write();
+        let buffered = {
+            let mut core_guard = self.core.write();
+            if core_guard.processor.is_some() {
+                return Err(NetworkError::AlreadyRegistered(core_guard.id).into());
+            }
+            core_guard.processor = Some(processor.clone());
+            core_guard
+                .buffer
+                .as_mut()
+                .map(std::mem::take)
+                .unwrap_or_default()
+        };
 
-        match core_guard.processor.as_ref() {
-            Some(_) => Err(anyhow!("an event processor is already registered")),
-            None => {
-                core_guard.processor = Some(processor);
-                Ok(())
+        let mut first_error = None;
+        for (origin_id, envelope) in buffered {
+            if let Err(e) = self.deliver(&processor, origin_id, envelope) {
+                first_error.get_or_insert(e);
             }
         }
+        match first_error {
+            Some(e) => Err(e.context("failed to process buffered event")),
+            None => Ok(()),
+        }
     }
 
     fn clone_box(&self) -> Box<dyn Network> {
         Box::new(self.clone())
     }
 }
+
+/// Wraps a routing failure, keeping the original error in the chain so that callers can still
+/// downcast it, e.g., to `NetworkError`.
+fn route_error(e: anyhow::Error) -> anyhow::Error {
+    e.context("failed to route event")
+}
@@ -1,8 +1,11 @@
 use crate::core::testutil::fixtures::random_identifier;
 use crate::core::Identifier;
 use crate::network::mock::hub::NetworkHub;
+use crate::network::mock::network::MockNetwork;
 use crate::network::Event::TestMessage;
-use crate::network::{Event, EventProcessorCore, MessageProcessor, Network};
+use crate::network::{
+    Envelope, Event, EventProcessorCore, MessageProcessor, Network, NetworkError,
+};
 use std::collections::HashSet;
 use std::sync::{Arc, Barrier, RwLock};
 use std::thread;
@@ -276,3 +279,452 @@ fn test_event_processor_clone_functionality() {
     assert!(core_processor.has_seen("Processor clone test 1"));
     assert!(core_processor.has_seen("Processor clone test 2"));
 }
+
+/// Records the order in which `TestMessage` payloads of the form `"<sender>:<seq>"` are processed,
+/// and the maximum number of events observed in flight at once.
+struct OrderRecordingProcessor {
+    processed: Arc<RwLock<Vec<(String, usize)>>>,
+    in_flight: Arc<std::sync::atomic::AtomicUsize>,
+    max_in_flight: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+impl Clone for OrderRecordingProcessor {
+    fn clone(&self) -> Self {
+        OrderRecordingProcessor {
+            processed: Arc::clone(&self.processed),
+            in_flight: Arc::clone(&self.in_flight),
+            max_in_flight: Arc::clone(&self.max_in_flight),
+        }
+    }
+}
+
+impl EventProcessorCore for OrderRecordingProcessor {
+    fn process_incoming_event(&self, _origin_id: Identifier, event: Event) -> anyhow::Result<()> {
+        use std::sync::atomic::Ordering;
+
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        // widen the processing window so that unserialized deliveries would overlap.
+        thread::sleep(std::time::Duration::from_micros(200));
+
+        let result = match event {
+            TestMessage(content) => {
+                let (sender, seq) = content
+                    .split_once(':')
+                    .ok_or_else(|| anyhow::anyhow!("malformed test message: {}", content))?;
+                self.processed
+                    .write()
+                    .unwrap()
+                    .push((sender.to_string(), seq.parse()?));
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!(
+                "OrderRecordingProcessor only handles TestMessage payloads"
+            )),
+        };
+
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+}
+
+/// Sends `events_per_sender` numbered events from each of `senders`, concurrently, to
+/// `target_net`, and asserts that the target never processes two events at once and that each
+/// sender's events are processed in the order they were sent.
+fn assert_serialized_in_send_order(
+    hub: &NetworkHub,
+    target_id: Identifier,
+    target_net: Arc<MockNetwork>,
+    senders: &[&'static str],
+    events_per_sender: usize,
+) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let processor = OrderRecordingProcessor {
+        processed: Arc::new(RwLock::new(Vec::new())),
+        in_flight: Arc::new(AtomicUsize::new(0)),
+        max_in_flight: Arc::new(AtomicUsize::new(0)),
+    };
+    target_net
+        .register_processor(MessageProcessor::new(Box::new(processor.clone())))
+        .expect("failed to register event processor");
+
+    let barrier = Arc::new(Barrier::new(senders.len()));
+    let mut handles = vec![];
+    for &sender in senders {
+        let sender_net = NetworkHub::new_mock_network(hub.clone(), random_identifier()).unwrap();
+        let barrier_clone = barrier.clone();
+        handles.push(thread::spawn(move || {
+            barrier_clone.wait();
+            for seq in 0..events_per_sender {
+                sender_net
+                    .send_event(target_id, TestMessage(format!("{sender}:{seq}")))
+                    .unwrap();
+            }
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(
+        processor.max_in_flight.load(Ordering::SeqCst),
+        1,
+        "target processed events concurrently"
+    );
+
+    let processed = processor.processed.read().unwrap();
+    assert_eq!(processed.len(), senders.len() * events_per_sender);
+    for &sender in senders {
+        let seqs: Vec<usize> = processed
+            .iter()
+            .filter(|(s, _)| s == sender)
+            .map(|(_, seq)| *seq)
+            .collect();
+        assert_eq!(
+            seqs,
+            (0..events_per_sender).collect::<Vec<_>>(),
+            "events from sender {sender} were processed out of order"
+        );
+    }
+}
+
+/// This test sends numbered events from two concurrent senders to one target on a hub with
+/// per-target serialization enabled, and verifies that they are processed one at a time and in
+/// send order per sender.
+#[test]
+fn test_hub_per_target_serialization_orders_processing() {
+    let hub = NetworkHub::builder().per_target_serialization(true).build();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+    assert_serialized_in_send_order(&hub, target_id, target_net, &["a", "b"], 50);
+}
+
+/// This test sends 100 numbered events from each of two concurrent origins to an ordered mock
+/// network on a hub without per-target serialization, and verifies that the ordered network alone
+/// is serialized, processing the events one at a time and in send order per origin.
+#[test]
+fn test_ordered_mock_network_preserves_send_order() {
+    let hub = NetworkHub::new();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_ordered_mock_network(hub.clone(), target_id).unwrap();
+    assert_serialized_in_send_order(&hub, target_id, target_net, &["a", "b"], 100);
+}
+
+/// This test sends 100 numbered events from a single origin to an ordered mock network, and
+/// verifies that the network processes them one at a time and in the order they were sent.
+#[test]
+fn test_ordered_mock_network_single_origin_order() {
+    let hub = NetworkHub::new();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_ordered_mock_network(hub.clone(), target_id).unwrap();
+    assert_serialized_in_send_order(&hub, target_id, target_net, &["a"], 100);
+}
+
+/// This test sends one event to a mix of registered and unknown targets and verifies that each
+/// target gets its own result, in order, and that every registered target processes the event.
+#[test]
+fn test_send_event_multi_per_target_results() {
+    let hub = NetworkHub::new();
+    let sender = NetworkHub::new_mock_network(hub.clone(), random_identifier()).unwrap();
+
+    let mut processors = vec![];
+    let mut targets = vec![];
+    for _ in 0..3 {
+        let id = random_identifier();
+        let net = NetworkHub::new_mock_network(hub.clone(), id).unwrap();
+        let core_proc = MockEventProcessor::new();
+        net.register_processor(MessageProcessor::new(Box::new(core_proc.clone())))
+            .expect("failed to register event processor");
+        processors.push(core_proc);
+        targets.push(id);
+    }
+    // interleave unknown targets with the registered ones.
+    targets.insert(1, random_identifier());
+    targets.push(random_identifier());
+
+    let results = sender.send_event_multi(&targets, TestMessage("Multicast".to_string()));
+
+    assert_eq!(results.len(), targets.len());
+    let outcomes: Vec<bool> = results.iter().map(|r| r.is_ok()).collect();
+    assert_eq!(outcomes, vec![true, false, true, true, false]);
+    for (result, target) in results.iter().zip(&targets) {
+        if let Err(e) = result {
+            assert_eq!(
+                NetworkError::from_error(e),
+                Some(NetworkError::TargetNotFound(*target))
+            );
+        }
+    }
+    for core_proc in processors {
+        assert!(core_proc.has_seen("Multicast"));
+    }
+}
+
+/// Records the delivery latency of every envelope it processes, measured after an artificial
+/// processing delay.
+struct LatencyRecordingProcessor {
+    delay: std::time::Duration,
+    latencies: Arc<RwLock<Vec<std::time::Duration>>>,
+}
+
+impl EventProcessorCore for LatencyRecordingProcessor {
+    fn process_incoming_event(&self, _origin_id: Identifier, _event: Event) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "LatencyRecordingProcessor only handles envelopes"
+        ))
+    }
+
+    fn process_incoming_envelope(
+        &self,
+        _origin_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        thread::sleep(self.delay);
+        self.latencies
+            .write()
+            .unwrap()
+            .push(envelope.sent_at.elapsed());
+        Ok(())
+    }
+}
+
+/// This test verifies that events sent through a mock network arrive stamped with their send
+/// time, so that a processor measures a latency covering at least an artificial delay.
+#[test]
+fn test_envelope_latency_measurement() {
+    let hub = NetworkHub::new();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+    let delay = std::time::Duration::from_millis(20);
+    let latencies = Arc::new(RwLock::new(Vec::new()));
+    target_net
+        .register_processor(MessageProcessor::new(Box::new(LatencyRecordingProcessor {
+            delay,
+            latencies: latencies.clone(),
+        })))
+        .expect("failed to register event processor");
+
+    let sender = NetworkHub::new_mock_network(hub, random_identifier()).unwrap();
+    sender
+        .send_event(target_id, TestMessage("Latency".to_string()))
+        .unwrap();
+
+    let latencies = latencies.read().unwrap();
+    assert_eq!(latencies.len(), 1);
+    assert!(
+        latencies[0] >= delay,
+        "measured latency {:?} is shorter than the artificial delay {:?}",
+        latencies[0],
+        delay
+    );
+}
+
+/// This test verifies that a mock network with a delivery delay holds every event for at least
+/// that delay, and records each delivery in the latency histogram bucket of the delay.
+#[test]
+fn test_delivery_delay_latency_histogram() {
+    let hub = NetworkHub::new();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+    let processor = MockEventProcessor::new();
+    target_net
+        .register_processor(MessageProcessor::new(Box::new(processor.clone())))
+        .expect("failed to register event processor");
+    assert!(target_net.latency_histogram().is_empty());
+
+    let delay = std::time::Duration::from_millis(20);
+    target_net.set_delivery_delay(delay);
+    let sender = NetworkHub::new_mock_network(hub, random_identifier()).unwrap();
+    for content in ["first", "second", "third"] {
+        sender
+            .send_event(target_id, TestMessage(content.to_string()))
+            .unwrap();
+        assert!(processor.has_seen(content));
+    }
+
+    let histogram = target_net.latency_histogram();
+    assert_eq!(histogram.iter().map(|(_, count)| count).sum::<usize>(), 3);
+    for (bucket, _) in histogram {
+        assert!(
+            bucket >= delay && bucket < delay * 10,
+            "latency bucket {bucket:?} does not reflect the delivery delay {delay:?}"
+        );
+    }
+}
+
+/// This test verifies that a buffered mock network holds events routed to it before a processor registers, rejects
+/// events beyond its capacity, and delivers the held events once a processor registers.
+#[test]
+fn test_buffered_mock_network_delivers_after_registration() {
+    let hub = NetworkHub::new();
+    let target_id = random_identifier();
+    let mock_network = NetworkHub::new_buffered_mock_network(hub.clone(), target_id, 2).unwrap();
+    let origin_id = random_identifier();
+
+    for content in ["first", "second"] {
+        assert!(hub
+            .route_event(origin_id, target_id, TestMessage(content.to_string()))
+            .is_ok());
+    }
+    let err = hub
+        .route_event(origin_id, target_id, TestMessage("third".to_string()))
+        .expect_err("event beyond the buffer capacity should be rejected");
+    assert_eq!(
+        NetworkError::from_error(&err),
+        Some(NetworkError::Dropped(target_id))
+    );
+
+    let core_processor = MockEventProcessor::new();
+    assert!(!core_processor.has_seen("first"));
+    mock_network
+        .register_processor(MessageProcessor::new(Box::new(core_processor.clone())))
+        .expect("failed to register event processor");
+    assert!(core_processor.has_seen("first"));
+    assert!(core_processor.has_seen("second"));
+    assert!(!core_processor.has_seen("third"));
+
+    // once registered, events are delivered directly.
+    assert!(hub
+        .route_event(origin_id, target_id, TestMessage("fourth".to_string()))
+        .is_ok());
+    assert!(core_processor.has_seen("fourth"));
+}
+
+/// This test verifies that an unbuffered mock network still rejects events routed to it before a processor
+/// registers with `NetworkError::NoProcessor`, and rejects a second processor with `NetworkError::AlreadyRegistered`.
+#[test]
+fn test_unbuffered_mock_network_rejects_before_registration() {
+    let hub = NetworkHub::new();
+    let target_id = random_identifier();
+    let mock_network = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+
+    let err = hub
+        .route_event(
+            random_identifier(),
+            target_id,
+            TestMessage("early".to_string()),
+        )
+        .expect_err("event before registration should be rejected");
+    assert_eq!(
+        NetworkError::from_error(&err),
+        Some(NetworkError::NoProcessor(target_id))
+    );
+
+    // a second processor cannot replace the first one.
+    mock_network
+        .register_processor(MessageProcessor::new(Box::new(MockEventProcessor::new())))
+        .unwrap();
+    let err = mock_network
+        .register_processor(MessageProcessor::new(Box::new(MockEventProcessor::new())))
+        .expect_err("second registration should be rejected");
+    assert_eq!(
+        NetworkError::from_error(&err),
+        Some(NetworkError::AlreadyRegistered(target_id))
+    );
+}
+
+/// This test verifies that a logging hub records every routed event, including events to unknown targets and the
+/// copies of a multicast, in routing order with their origin, target, and kind, that clones share the log, and that
+/// only the most recent events are kept once the log is full.
+#[test]
+fn test_hub_event_log() {
+    let hub = NetworkHub::builder().logging(4).build();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+    target_net
+        .register_processor(MessageProcessor::new(Box::new(MockEventProcessor::new())))
+        .expect("failed to register event processor");
+    let origin_id = random_identifier();
+    let unknown_id = random_identifier();
+
+    hub.route_event(origin_id, target_id, TestMessage("first".to_string()))
+        .unwrap();
+    assert!(hub
+        .route_event(origin_id, unknown_id, TestMessage("lost".to_string()))
+        .is_err_and(
+            |e| NetworkError::from_error(&e) == Some(NetworkError::TargetNotFound(unknown_id))
+        ));
+    hub.clone()
+        .route_event_multi(origin_id, &[target_id], TestMessage("multi".to_string()));
+
+    let log = hub.event_log();
+    let routes: Vec<(Identifier, Identifier, &str)> =
+        log.iter().map(|e| (e.origin, e.target, e.kind)).collect();
+    assert_eq!(
+        routes,
+        vec![
+            (origin_id, target_id, "TestMessage"),
+            (origin_id, unknown_id, "TestMessage"),
+            (origin_id, target_id, "TestMessage"),
+        ]
+    );
+    assert!(log.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+
+    for _ in 0..3 {
+        hub.route_event(target_id, target_id, TestMessage("more".to_string()))
+            .unwrap();
+    }
+    let log = hub.event_log();
+    assert_eq!(log.len(), 4, "the log should keep only its capacity");
+    assert_eq!(
+        (log[0].origin, log[0].target),
+        (origin_id, target_id),
+        "the two oldest events should be evicted"
+    );
+    assert!(log[1..].iter().all(|e| e.origin == target_id));
+
+    assert!(NetworkHub::new().event_log().is_empty());
+}
+
+/// This test verifies that a hub with a capacity accepts mock networks up to its capacity, rejects
+/// the next one with `NetworkError::AtCapacity`, and accepts a new one once a slot is freed.
+#[test]
+fn test_hub_with_capacity_rejects_networks_beyond_cap() {
+    let hub = NetworkHub::builder().capacity(3).build();
+    let ids: Vec<Identifier> = (0..3).map(|_| random_identifier()).collect();
+    for id in &ids {
+        NetworkHub::new_mock_network(hub.clone(), *id).expect("network within capacity");
+    }
+
+    let Err(err) = NetworkHub::new_mock_network(hub.clone(), random_identifier()) else {
+        panic!("network beyond capacity should be rejected");
+    };
+    assert_eq!(
+        NetworkError::from_error(&err),
+        Some(NetworkError::AtCapacity(3)),
+        "unexpected error: {err:#}"
+    );
+
+    hub.remove_mock_network(ids[0]).unwrap();
+    NetworkHub::new_mock_network(hub, random_identifier()).expect("freed slot should be reusable");
+}
+
+/// This test verifies that hub options combine: a deterministic, logging hub with a capacity logs
+/// an event as it routes it but only delivers it once the queue is drained, and still rejects
+/// networks beyond its capacity.
+#[test]
+fn test_hub_builder_combines_options() {
+    let hub = NetworkHub::builder()
+        .deterministic()
+        .logging(8)
+        .capacity(2)
+        .build();
+    let target_id = random_identifier();
+    let target_net = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+    let core_proc = MockEventProcessor::new();
+    target_net
+        .register_processor(MessageProcessor::new(Box::new(core_proc.clone())))
+        .expect("failed to register event processor");
+    let origin_id = random_identifier();
+    NetworkHub::new_mock_network(hub.clone(), origin_id).unwrap();
+    assert!(NetworkHub::new_mock_network(hub.clone(), random_identifier()).is_err());
+
+    hub.route_event(origin_id, target_id, TestMessage("queued".to_string()))
+        .unwrap();
+    assert_eq!(hub.event_log().len(), 1);
+    assert!(!core_proc.has_seen("queued"));
+
+    assert_eq!(hub.run_until_quiescent().unwrap().len(), 1);
+    assert!(core_proc.has_seen("queued"));
+}
@@ -1,9 +1,17 @@
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
 pub mod mock;
 mod processor;
 
-use crate::core::{IdSearchReq, IdSearchRes, Identifier};
+use crate::core::model::direction::Direction;
+use crate::core::model::identity::Identity;
+use crate::core::model::search::Nonce;
+use crate::core::{
+    IdSearchReq, IdSearchRes, Identifier, LinkReq, LookupTableLevel, NeighborReq, NeighborRes,
+};
 #[allow(unused)]
-pub use processor::MessageProcessor;
+pub use processor::{ErrorHandler, MessageProcessor};
+use std::time::Instant;
 
 /// Event enum defines the semantics of the event payload that are processed by the Skip Graph event processor.
 /// Event is an application-layer semantic contrast to the lower-level transport-layer Message struct.
@@ -12,6 +20,187 @@ pub enum Event {
     TestMessage(String), // A payload for testing purposes, it is a simple string event, and is not used in production.
     SearchByIdRequest(IdSearchReq), // A payload representing an identifier search request.
     SearchByIdResponse(IdSearchRes), // A payload representing an identifier search response.
+    NeighborRequest(NeighborReq), // A payload asking for a lookup table entry of the receiver.
+    NeighborResponse(Box<NeighborRes>), // A payload answering a neighbor request, boxed as it carries two identities.
+    LinkRequest(LinkReq), // A payload asking the receiver to set one of its lookup table entries.
+    // A payload asking the receiver to search for a node joining through it; the response echoes the nonce.
+    Bootstrap {
+        new_node: Identity,
+        nonce: Nonce,
+    },
+    // A payload answering a bootstrap with the closest node found, or why the search failed.
+    BootstrapResponse {
+        nonce: Nonce,
+        closest: Result<Identifier, String>,
+    },
+    // A payload suggesting a neighbor to the receiver, applied only if it is closer than the current entry.
+    NeighborGossip {
+        about: Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    },
+    // A payload announcing that the sender leaves the overlay, so the receiver drops it from its lookup table.
+    Leave(Identity),
+    Ping(Nonce), // A payload probing the liveness of the receiver, carrying a nonce the answering pong echoes.
+    Pong(Nonce), // A payload answering a ping, carrying the nonce of the ping.
+    // A payload of a kind this node does not know, e.g., sent by a peer running a newer version;
+    // it carries the payload's tag and raw bytes, and is ignored by processors.
+    Unknown {
+        tag: u8,
+        bytes: Vec<u8>,
+    },
+}
+
+/// NetworkError enumerates network failures that callers may need to tell apart from other errors,
+/// e.g., to evict a neighbor that can no longer be reached. It is returned wrapped in
+/// `anyhow::Error`, and recovered with `NetworkError::from_error`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[allow(dead_code)] // TODO: remove once a production network reports these errors.
+pub enum NetworkError {
+    /// The target's identifier does not resolve to any known node, e.g., as the node went away.
+    TargetNotFound(Identifier),
+    /// The target network has no event processor registered to hand the event to.
+    NoProcessor(Identifier),
+    /// The network already has an event processor registered.
+    AlreadyRegistered(Identifier),
+    /// The event was dropped before reaching the target's processor, e.g., as the target could
+    /// not hold any more pending events.
+    Dropped(Identifier),
+    /// The network could not be registered, as the hub already holds as many networks as its
+    /// capacity, given here, allows.
+    AtCapacity(usize),
+}
+
+impl NetworkError {
+    /// Returns the network error carried by `e`, either as the error itself or anywhere in its
+    /// chain of causes, or `None` if the failure is of another kind.
+    pub fn from_error(e: &anyhow::Error) -> Option<NetworkError> {
+        e.chain()
+            .find_map(|cause| cause.downcast_ref::<NetworkError>())
+            .copied()
+    }
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::TargetNotFound(id) => {
+                write!(f, "network with identifier {} not found", id)
+            }
+            NetworkError::NoProcessor(id) => {
+                write!(f, "no event processor registered for network {}", id)
+            }
+            NetworkError::AlreadyRegistered(id) => write!(
+                f,
+                "an event processor is already registered for network {}",
+                id
+            ),
+            NetworkError::Dropped(id) => write!(f, "event to network {} was dropped", id),
+            NetworkError::AtCapacity(capacity) => {
+                write!(f, "hub is at its capacity of {} networks", capacity)
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// The kinds of events in the order of their wire tags: a frame is a tag byte, the index of its kind here,
+/// followed by the payload bytes. Kinds are only ever appended, so tags stay stable across versions.
+const WIRE_KINDS: [&str; 12] = [
+    "TestMessage",
+    "SearchByIdRequest",
+    "SearchByIdResponse",
+    "NeighborRequest",
+    "NeighborResponse",
+    "LinkRequest",
+    "Bootstrap",
+    "BootstrapResponse",
+    "NeighborGossip",
+    "Leave",
+    "Ping",
+    "Pong",
+];
+
+impl Event {
+    /// Decodes a frame of a tag byte followed by the payload bytes. A tag this version does not know, e.g., sent
+    /// by a peer running a newer version, decodes into `Event::Unknown` carrying the tag and the payload, rather
+    /// than failing, so that the event can be ignored. Only `TestMessage` (UTF-8 text) and `Ping` and `Pong` (a
+    /// big-endian 16-byte nonce) payloads are decoded so far; returns an error for the other known kinds, for an
+    /// empty frame, and for a malformed payload.
+    pub fn decode_lenient(frame: &[u8]) -> anyhow::Result<Event> {
+        let (&tag, payload) = frame
+            .split_first()
+            .ok_or_else(|| anyhow::anyhow!("empty event frame has no tag"))?;
+        let Some(&kind) = WIRE_KINDS.get(tag as usize) else {
+            return Ok(Event::Unknown {
+                tag,
+                bytes: payload.to_vec(),
+            });
+        };
+        let nonce = || -> anyhow::Result<Nonce> {
+            let bytes = payload.try_into().map_err(|_| {
+                anyhow::anyhow!(
+                    "{} payload must be 16 bytes long, got {}",
+                    kind,
+                    payload.len()
+                )
+            })?;
+            Ok(Nonce::from_bytes(bytes))
+        };
+        match kind {
+            "TestMessage" => Ok(Event::TestMessage(
+                String::from_utf8(payload.to_vec())
+                    .map_err(|e| anyhow::anyhow!("TestMessage payload is not UTF-8: {}", e))?,
+            )),
+            "Ping" => Ok(Event::Ping(nonce()?)),
+            "Pong" => Ok(Event::Pong(nonce()?)),
+            _ => Err(anyhow::anyhow!(
+                "decoding {} payloads (tag {}) is not supported yet",
+                kind,
+                tag
+            )),
+        }
+    }
+
+    /// Returns the name of the event variant, for use as a stable label in logs and metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::TestMessage(_) => "TestMessage",
+            Event::SearchByIdRequest(_) => "SearchByIdRequest",
+            Event::SearchByIdResponse(_) => "SearchByIdResponse",
+            Event::NeighborRequest(_) => "NeighborRequest",
+            Event::NeighborResponse(_) => "NeighborResponse",
+            Event::LinkRequest(_) => "LinkRequest",
+            Event::Bootstrap { .. } => "Bootstrap",
+            Event::BootstrapResponse { .. } => "BootstrapResponse",
+            Event::NeighborGossip { .. } => "NeighborGossip",
+            Event::Leave(_) => "Leave",
+            Event::Ping(_) => "Ping",
+            Event::Pong(_) => "Pong",
+            Event::Unknown { .. } => "Unknown",
+        }
+    }
+}
+
+/// Envelope wraps an event with the time it was sent, so that a processor can measure its delivery latency
+/// as `sent_at.elapsed()`.
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// The time at which the event was handed to the network for sending.
+    pub sent_at: Instant,
+    /// The event being delivered.
+    pub event: Event,
+}
+
+impl Envelope {
+    /// Wraps the event in an envelope stamped with the current time.
+    pub fn new(event: Event) -> Self {
+        Envelope {
+            sent_at: Instant::now(),
+            event,
+        }
+    }
 }
 
 /// Core event processing logic that implementations must provide.
@@ -25,6 +214,17 @@ pub trait EventProcessorCore: Send + Sync {
     ///   Returns:
     ///   * `Result<(), anyhow::Error>`: Returns Ok if the event was processed successfully, or an error if processing failed.
     fn process_incoming_event(&self, origin_id: Identifier, event: Event) -> anyhow::Result<()>;
+
+    /// Process an incoming event together with its send timestamp.
+    /// Implementations that measure delivery latency override this; by default the envelope is
+    /// unwrapped and the event is handed to `process_incoming_event`.
+    fn process_incoming_envelope(
+        &self,
+        origin_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        self.process_incoming_event(origin_id, envelope.event)
+    }
 }
 
 /// Network trait defines the interface for a network service that can send and receive events.
@@ -33,6 +233,16 @@ pub trait Network: Send + Sync {
     /// Sends an event to the network.
     fn send_event(&self, origin_id: Identifier, event: Event) -> anyhow::Result<()>;
 
+    /// Sends the same event to each of the given targets.
+    /// Returns one result per target, in the order of `targets`; a failure for one target does
+    /// not prevent delivery to the others.
+    fn send_event_multi(&self, targets: &[Identifier], event: Event) -> Vec<anyhow::Result<()>> {
+        targets
+            .iter()
+            .map(|target| self.send_event(*target, event.clone()))
+            .collect()
+    }
+
     /// Registers an event processor to handle incoming events.
     /// At any point in time, there can be only one processor registered.
     /// Registering a new processor is illegal if there is already a processor registered, and causes an error.
@@ -52,3 +262,173 @@ impl Clone for Box<dyn Network> {
         self.clone_box()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testutil::fixtures::{random_identifier, random_identity};
+
+    /// Verifies that each event variant reports its own name as its kind.
+    #[test]
+    fn test_event_kind() {
+        let target = random_identifier();
+        let nonce = Nonce::random();
+
+        assert_eq!(
+            Event::TestMessage("hello".to_string()).kind(),
+            "TestMessage"
+        );
+        assert_eq!(
+            Event::SearchByIdRequest(IdSearchReq {
+                nonce,
+                correlation: None,
+                inclusive: true,
+                trace_id: rand::random(),
+                hops: 0,
+                target,
+                origin: random_identifier(),
+                level: 0,
+                min_level: 0,
+                direction: Direction::Left,
+            })
+            .kind(),
+            "SearchByIdRequest"
+        );
+        assert_eq!(
+            Event::SearchByIdResponse(IdSearchRes {
+                nonce,
+                correlation: None,
+                trace_id: rand::random(),
+                hops: 0,
+                target,
+                origin: target,
+                termination_level: 0,
+                result: target,
+                matched_identity: None,
+                fallback_direction: None,
+            })
+            .kind(),
+            "SearchByIdResponse"
+        );
+
+        let identity = random_identity();
+        assert_eq!(
+            Event::NeighborRequest(NeighborReq {
+                nonce,
+                level: 0,
+                direction: Direction::Left,
+            })
+            .kind(),
+            "NeighborRequest"
+        );
+        assert_eq!(
+            Event::NeighborResponse(Box::new(NeighborRes {
+                nonce,
+                responder: identity,
+                neighbor: None,
+            }))
+            .kind(),
+            "NeighborResponse"
+        );
+        assert_eq!(
+            Event::LinkRequest(LinkReq {
+                identity,
+                level: 0,
+                direction: Direction::Right,
+            })
+            .kind(),
+            "LinkRequest"
+        );
+        assert_eq!(
+            Event::Bootstrap {
+                new_node: identity,
+                nonce
+            }
+            .kind(),
+            "Bootstrap"
+        );
+        assert_eq!(
+            Event::BootstrapResponse {
+                nonce,
+                closest: Ok(target)
+            }
+            .kind(),
+            "BootstrapResponse"
+        );
+        assert_eq!(
+            Event::NeighborGossip {
+                about: identity,
+                level: 0,
+                direction: Direction::Left,
+            }
+            .kind(),
+            "NeighborGossip"
+        );
+        assert_eq!(Event::Leave(identity).kind(), "Leave");
+        assert_eq!(Event::Ping(nonce).kind(), "Ping");
+        assert_eq!(Event::Pong(nonce).kind(), "Pong");
+        assert_eq!(
+            Event::Unknown {
+                tag: 0xff,
+                bytes: vec![1, 2, 3],
+            }
+            .kind(),
+            "Unknown"
+        );
+    }
+
+    /// Verifies that `decode_lenient` decodes a made-up tag into `Event::Unknown` with the payload intact,
+    /// decodes the supported known kinds, and fails on an empty frame, a malformed payload, and a known kind it
+    /// cannot decode yet.
+    #[test]
+    fn test_decode_lenient() {
+        match Event::decode_lenient(&[0xee, 0xde, 0xad, 0xbe, 0xef]).unwrap() {
+            Event::Unknown { tag, bytes } => {
+                assert_eq!(tag, 0xee);
+                assert_eq!(bytes, vec![0xde, 0xad, 0xbe, 0xef]);
+            }
+            other => panic!("expected an unknown event, got {:?}", other),
+        }
+        // the first tag past the known kinds is unknown, even with an empty payload.
+        assert!(matches!(
+            Event::decode_lenient(&[WIRE_KINDS.len() as u8]).unwrap(),
+            Event::Unknown { bytes, .. } if bytes.is_empty()
+        ));
+
+        let mut frame = vec![0];
+        frame.extend_from_slice(b"hello");
+        assert!(matches!(
+            Event::decode_lenient(&frame).unwrap(),
+            Event::TestMessage(text) if text == "hello"
+        ));
+        let mut frame = vec![11];
+        frame.extend_from_slice(&7u128.to_be_bytes());
+        assert!(matches!(
+            Event::decode_lenient(&frame).unwrap(),
+            Event::Pong(nonce) if nonce == Nonce::from_bytes(7u128.to_be_bytes())
+        ));
+
+        assert!(Event::decode_lenient(&[]).is_err());
+        assert!(Event::decode_lenient(&[10, 1, 2, 3]).is_err());
+        assert!(Event::decode_lenient(&[0, 0xff]).is_err());
+        assert!(Event::decode_lenient(&[1]).is_err());
+    }
+
+    /// Verifies that `NetworkError::from_error` recovers a network error wrapped in context, and
+    /// finds none in an error of another kind.
+    #[test]
+    fn test_network_error_from_error() {
+        let id = random_identifier();
+        let err = anyhow::Error::new(NetworkError::NoProcessor(id))
+            .context("failed to process incoming event")
+            .context("failed to route event");
+        assert_eq!(
+            NetworkError::from_error(&err),
+            Some(NetworkError::NoProcessor(id))
+        );
+        assert_eq!(
+            NetworkError::from_error(&anyhow::anyhow!("no processor")),
+            None
+        );
+    }
+}
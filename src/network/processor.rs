@@ -1,13 +1,22 @@
 use crate::core::Identifier;
-use crate::network::{Event, EventProcessorCore};
+use crate::network::{Envelope, Event, EventProcessorCore};
 use parking_lot::RwLock;
 use std::sync::Arc;
 
+/// ErrorHandler is invoked with every error returned by an event processor, e.g., for metrics or alerting.
+pub type ErrorHandler = Arc<dyn Fn(&anyhow::Error) + Send + Sync>;
+
 /// A thread-safe wrapper that enforces internal thread-safety for event processors.
 /// This type guarantees that all event processing is properly synchronized.
+///
+/// Processing is reentrant: a handler may, directly or through the network, dispatch another event to the same
+/// processor on the same thread, e.g., a node relaying a search that comes back to it. Each dispatch holds a
+/// recursive read lock on the core for its duration, so a nested dispatch never waits on the outer one. Any change
+/// to the locking here must preserve this, as an exclusive lock would deadlock such handlers.
 #[derive(Clone)]
 pub struct MessageProcessor {
     core: Arc<RwLock<Box<dyn EventProcessorCore>>>,
+    error_handler: Option<ErrorHandler>,
 }
 
 impl MessageProcessor {
@@ -15,6 +24,16 @@ impl MessageProcessor {
     pub fn new(core: Box<dyn EventProcessorCore>) -> Self {
         Self {
             core: Arc::new(RwLock::new(core)),
+            error_handler: None,
+        }
+    }
+
+    /// Creates a new thread-safe event processor from a core implementation, invoking `handler`
+    /// on every processing error before the error is returned to the caller.
+    pub fn with_error_handler(core: Box<dyn EventProcessorCore>, handler: ErrorHandler) -> Self {
+        Self {
+            core: Arc::new(RwLock::new(core)),
+            error_handler: Some(handler),
         }
     }
 
@@ -24,8 +43,26 @@ impl MessageProcessor {
         origin_id: Identifier,
         event: Event,
     ) -> anyhow::Result<()> {
-        let core = self.core.read();
-        core.process_incoming_event(origin_id, event)
+        let core = self.core.read_recursive();
+        self.handle_result(core.process_incoming_event(origin_id, event))
+    }
+
+    /// Process an incoming event together with its send timestamp, with guaranteed thread-safety.
+    pub fn process_incoming_envelope(
+        &self,
+        origin_id: Identifier,
+        envelope: Envelope,
+    ) -> anyhow::Result<()> {
+        let core = self.core.read_recursive();
+        self.handle_result(core.process_incoming_envelope(origin_id, envelope))
+    }
+
+    /// Invokes the error handler, if any, on a processing error, and passes the result through.
+    fn handle_result(&self, result: anyhow::Result<()>) -> anyhow::Result<()> {
+        if let (Err(e), Some(handler)) = (&result, &self.error_handler) {
+            handler(e);
+        }
+        result
     }
 }
 
@@ -91,4 +128,101 @@ mod tests {
             .unwrap();
         assert_eq!(counter_ref.load(Ordering::SeqCst), 2);
     }
+
+    /// This test verifies that a handler dispatching another event to its own processor, while the outer dispatch
+    /// is still in progress, completes instead of deadlocking on the processor's lock.
+    #[test]
+    fn test_event_processor_reentrant_dispatch() {
+        struct RedispatchingCore {
+            processor: Arc<std::sync::OnceLock<MessageProcessor>>,
+            handled: Arc<parking_lot::Mutex<Vec<String>>>,
+        }
+
+        impl EventProcessorCore for RedispatchingCore {
+            fn process_incoming_event(
+                &self,
+                origin_id: Identifier,
+                event: Event,
+            ) -> anyhow::Result<()> {
+                let Event::TestMessage(msg) = event else {
+                    return Err(anyhow::anyhow!("unexpected event"));
+                };
+                if msg == "outer" {
+                    self.processor
+                        .get()
+                        .expect("processor is set before dispatching")
+                        .process_incoming_event(
+                            origin_id,
+                            Event::TestMessage("inner".to_string()),
+                        )?;
+                }
+                self.handled.lock().push(msg);
+                Ok(())
+            }
+        }
+
+        let processor_slot = Arc::new(std::sync::OnceLock::new());
+        let handled = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let processor = MessageProcessor::new(Box::new(RedispatchingCore {
+            processor: processor_slot.clone(),
+            handled: handled.clone(),
+        }));
+        assert!(processor_slot.set(processor.clone()).is_ok());
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let result = processor.process_incoming_event(
+                random_identifier(),
+                Event::TestMessage("outer".to_string()),
+            );
+            done_tx.send(result.is_ok()).unwrap();
+        });
+        let succeeded = done_rx
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .expect("reentrant dispatch deadlocked");
+        assert!(succeeded);
+        // the inner event completes first, as it is handled within the outer one.
+        assert_eq!(
+            *handled.lock(),
+            vec!["inner".to_string(), "outer".to_string()]
+        );
+    }
+
+    /// This test verifies that the error handler of a `MessageProcessor` fires exactly once per
+    /// failed event, and that the error is still returned to the caller.
+    #[test]
+    fn test_event_processor_error_handler() {
+        struct ErroringCore;
+
+        impl EventProcessorCore for ErroringCore {
+            fn process_incoming_event(
+                &self,
+                _origin_id: Identifier,
+                _event: Event,
+            ) -> anyhow::Result<()> {
+                Err(anyhow::anyhow!("simulated processing error"))
+            }
+        }
+
+        let handled = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let handled_clone = handled.clone();
+        let processor = MessageProcessor::with_error_handler(
+            Box::new(ErroringCore),
+            Arc::new(move |e: &anyhow::Error| handled_clone.lock().push(e.to_string())),
+        );
+
+        for i in 0..3 {
+            let result = processor
+                .process_incoming_event(random_identifier(), Event::TestMessage(format!("{i}")));
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "simulated processing error"
+            );
+            assert_eq!(handled.lock().len(), i + 1);
+        }
+        assert!(handled
+            .lock()
+            .iter()
+            .all(|msg| msg == "simulated processing error"));
+    }
 }
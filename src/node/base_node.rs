@@ -1,17 +1,30 @@
+use crate::core::model::direction::{Direction, DIRECTIONS};
+use crate::core::model::identity::Identity;
 use crate::core::model::search::Nonce;
-use crate::core::{IdSearchReq, IdSearchRes, Identifier, IrrevocableContext, MembershipVector};
-use crate::network::Event::{SearchByIdRequest, SearchByIdResponse};
-#[cfg(test)] // TODO: Remove once BaseNode is used in production code.
+use crate::core::{
+    IdSearchReq, IdSearchRes, Identifier, IrrevocableContext, LinkReq, LookupTable,
+    LookupTableLevel, MembershipVector, NeighborReq, NeighborRes, LOOKUP_TABLE_LEVELS,
+};
+use crate::network::Event::{
+    Bootstrap, BootstrapResponse, LinkRequest, NeighborGossip, NeighborRequest, NeighborResponse,
+    Ping, Pong, SearchByIdRequest, SearchByIdResponse,
+};
+#[cfg(any(test, feature = "bench"))] // TODO: Remove once BaseNode is used in production code.
 use crate::network::MessageProcessor;
-use crate::network::{Event, EventProcessorCore, Network};
+use crate::network::{Event, EventProcessorCore, Network, NetworkError};
 use crate::node::core::Core;
-use anyhow::anyhow;
+use crate::node::retry::{RetryPolicy, SearchError};
+use crate::node::{Node, NodeView};
+use anyhow::{anyhow, Context};
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
-use std::sync::mpsc::sync_channel;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver};
 use std::sync::{mpsc::SyncSender, Arc, Mutex};
-use tracing::Span;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{Instrument, Span};
 
 // TODO: Remove #[allow(dead_code)] once BaseNode is used in production code.
 #[allow(dead_code)]
@@ -27,21 +40,88 @@ pub(crate) struct BaseNode {
     net: Box<dyn Network>,
     span: Span,
     ctx: IrrevocableContext,
-    // map from request id to the sender end of the channel for the response
-    request_id_map: Arc<Mutex<HashMap<Nonce, SyncSender<IdSearchRes>>>>,
+    // map from request id to the waiter of the response
+    request_id_map: Arc<Mutex<HashMap<Nonce, Waiter>>>,
+    // map from request id to the sender end of a pending neighbor request issued during join
+    neighbor_waiters: Arc<Mutex<HashMap<Nonce, SyncSender<NeighborRes>>>>,
+    // map from bootstrap nonce to the sender end of a pending bootstrap issued during join
+    bootstrap_waiters: Arc<Mutex<HashMap<Nonce, SyncSender<BootstrapResult>>>>,
+    // map from ping nonce to the waiter of its pong
+    pong_waiters: Arc<Mutex<HashMap<Nonce, PongWaiter>>>,
+    // set by a successful join and cleared by leave
+    joined: Arc<AtomicBool>,
+    // held by join and leave, so that a node links itself into the overlay at most once at a time
+    membership_lock: Arc<Mutex<()>>,
+}
+
+/// The answer to a bootstrap: the closest node the introducer found, or why its search failed.
+type BootstrapResult = Result<Identifier, String>;
+
+/// How long a node waits for the answer to a request it issued on its own behalf, e.g., while
+/// joining, before giving up on it.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Waiter is a search originated by this node that is pending a response.
+struct Waiter {
+    // where the response is handed over
+    reply: Reply,
+    // correlation the originating request was sent with; a response must echo it
+    correlation: u64,
+}
+
+/// PongWaiter is a ping sent by this node that is pending its pong.
+struct PongWaiter {
+    // the pinged node, the only one whose pong answers the ping
+    target: Identifier,
+    // sender end of the channel the pong is signalled on
+    tx: oneshot::Sender<()>,
+}
+
+/// Reply is where the response to a search originated by this node goes.
+enum Reply {
+    /// The sender end of the channel a caller waits on.
+    Caller(SyncSender<IdSearchRes>),
+    /// A node bootstrapping through this one, answered with a `BootstrapResponse` carrying the
+    /// nonce of its `Bootstrap`.
+    Bootstrap { new_node: Identifier, nonce: Nonce },
 }
 
 impl BaseNode {
     /// Create a new `BaseNode` from an already-constructed `Core` and a
     /// network handle. Registers the node as an event processor on the
     /// network before returning.
-    #[cfg(test)] // TODO: Remove once BaseNode is used in production code.
+    #[cfg(any(test, feature = "bench"))] // TODO: Remove once BaseNode is used in production code.
     pub(crate) fn new(
         parent_span: Span,
         core: Box<dyn Core>,
         net: Box<dyn Network>,
     ) -> anyhow::Result<Self> {
         let clone_net = net.clone();
+        let node = Self::new_unregistered(parent_span, core, net)?;
+        let span = node.span.clone();
+        let _enter = span.enter();
+
+        let processor = MessageProcessor::new(Box::new(node.clone()));
+
+        if let Err(e) = clone_net.register_processor(processor) {
+            let error = anyhow!("could not register node in network: {}", e);
+            node.ctx.throw_irrecoverable(error);
+        }
+
+        tracing::trace!("successfully created and registered node");
+
+        Ok(node)
+    }
+
+    /// Create a new `BaseNode` like `new`, but without registering it as an event processor on
+    /// the network. The caller registers the node, or a processor wrapping it, itself; until
+    /// then, no event reaches the node.
+    #[cfg(any(test, feature = "bench"))] // TODO: Remove once BaseNode is used in production code.
+    pub(crate) fn new_unregistered(
+        parent_span: Span,
+        core: Box<dyn Core>,
+        net: Box<dyn Network>,
+    ) -> anyhow::Result<Self> {
         let span = tracing::span!(parent: &parent_span, tracing::Level::TRACE, "base_node", id = ?core.id(), mem_vec = ?core.mem_vec());
         let _enter = span.enter();
 
@@ -53,90 +133,838 @@ impl BaseNode {
             span: span.clone(),
             ctx,
             request_id_map: Arc::new(Mutex::new(HashMap::new())),
+            neighbor_waiters: Arc::new(Mutex::new(HashMap::new())),
+            bootstrap_waiters: Arc::new(Mutex::new(HashMap::new())),
+            pong_waiters: Arc::new(Mutex::new(HashMap::new())),
+            joined: Arc::new(AtomicBool::new(false)),
+            membership_lock: Arc::new(Mutex::new(())),
         };
 
-        let processor = MessageProcessor::new(Box::new(node.clone()));
+        tracing::trace!("successfully created node");
 
-        if let Err(e) = clone_net.register_processor(processor) {
-            let error = anyhow!("could not register node in network: {}", e);
-            node.ctx.throw_irrecoverable(error);
-        }
+        Ok(node)
+    }
 
-        tracing::trace!("successfully created and registered node");
+    /// Returns the node's identity (delegated to core).
+    #[allow(dead_code)]
+    pub(crate) fn identity(&self) -> Identity {
+        self.core.identity()
+    }
 
-        Ok(node)
+    /// Returns a shallow copy of the node's lookup table (delegated to core).
+    #[allow(dead_code)]
+    pub(crate) fn lookup_table(&self) -> Box<dyn LookupTable> {
+        self.core.lookup_table()
     }
 
-    /// Returns the node's identifier (delegated to core).
+    /// Searches for `target` in `direction` over the whole lookup table, as `search_by_id` does,
+    /// but aborts with a cancellation error once `ctx` is cancelled; see `search_by_id_ctx`.
     #[allow(dead_code)]
-    pub(crate) fn id(&self) -> Identifier {
-        self.core.id()
+    pub(crate) async fn search_ctx(
+        &self,
+        ctx: &IrrevocableContext,
+        target: Identifier,
+        direction: Direction,
+    ) -> anyhow::Result<IdSearchRes> {
+        let req = IdSearchReq::try_new(self.core.id(), target, LOOKUP_TABLE_LEVELS - 1, direction)?;
+        self.search_by_id_ctx(ctx, &req).await
     }
 
-    /// Returns the node's membership vector (delegated to core).
+    /// Runs the search `req` as `search_by_id` does, but aborts with a cancellation error once
+    /// `ctx` is cancelled. On cancellation the pending waiter is removed, so a response arriving
+    /// later is dropped and no thread stays blocked on it.
+    ///
+    /// The local step of the search and the relay to the next hop run synchronously when the
+    /// returned future is first polled; only the wait for the response is off the polling task.
+    /// Over a network that processes events on the sender's thread, such as the mock hub, sending
+    /// the relay processes it at every later hop too, so the first poll blocks the polling task
+    /// until the search completes or fails.
     #[allow(dead_code)]
-    pub(crate) fn mem_vec(&self) -> MembershipVector {
-        self.core.mem_vec()
+    pub(crate) async fn search_by_id_ctx(
+        &self,
+        ctx: &IrrevocableContext,
+        req: &IdSearchReq,
+    ) -> anyhow::Result<IdSearchRes> {
+        let req = *req;
+        let span = tracing::trace_span!(
+            "search_by_id_ctx",
+            target = ?req.target,
+            level = ?req.level,
+            trace_id = req.trace_id
+        );
+
+        // the span is attached to the future rather than entered, as it must not stay entered
+        // on the polling thread across the await.
+        async {
+            let rx = match self.begin_search_by_id(req)? {
+                PendingSearch::Done(res) => return Ok(*res),
+                PendingSearch::Waiting(rx) => rx,
+            };
+
+            // the waiter is registered before waiting starts, so removing it on cancellation
+            // always disconnects the channel and releases the blocking receive below.
+            let wait = tokio::task::spawn_blocking(move || rx.recv());
+            let result = ctx
+                .run(async {
+                    match wait.await {
+                        Ok(Ok(net_result)) => Ok(net_result),
+                        Ok(Err(_)) => Err(anyhow!(
+                            "failed to receive network response for search by id"
+                        )),
+                        Err(e) => Err(anyhow!("search by id wait task failed: {}", e)),
+                    }
+                })
+                .await;
+            if result.is_err() {
+                self.remove_waiter(&req.nonce);
+            }
+            result
+        }
+        .instrument(span)
+        .await
     }
 
+    /// Runs the searches `reqs` concurrently, each as `search_by_id_ctx` does under the node's
+    /// context, and returns their results in the order of `reqs`. A failing search does not
+    /// affect the others.
     #[allow(dead_code)]
-    pub(crate) fn search_by_id(&self, req: IdSearchReq) -> anyhow::Result<IdSearchRes> {
-        let span = tracing::trace_span!("search_by_id", target = ?req.target, level = ?req.level);
-        let _enter = span.enter();
+    pub(crate) async fn search_many(
+        &self,
+        reqs: Vec<IdSearchReq>,
+    ) -> Vec<anyhow::Result<IdSearchRes>> {
+        let searches = reqs.iter().map(|req| self.search_by_id_ctx(&self.ctx, req));
+        futures::future::join_all(searches).await
+    }
 
-        tracing::trace!("searching for target {:?}", req.target);
+    /// Searches like `search_ctx`, retrying under `policy` when an attempt fails with a transient
+    /// `SearchError`: a request that could not be sent, or no response within the policy's
+    /// attempt timeout. Each attempt is a new search. Other failures, including cancellation of
+    /// `ctx`, end the search at once; a cancellation during the delay between attempts does too.
+    #[allow(dead_code)]
+    pub(crate) async fn search_with_retry(
+        &self,
+        ctx: &IrrevocableContext,
+        target: Identifier,
+        direction: Direction,
+        policy: RetryPolicy,
+    ) -> anyhow::Result<IdSearchRes> {
+        let mut attempt = 1;
+        loop {
+            let attempt_ctx = ctx.child("search_attempt");
+            let timed_out = Arc::new(AtomicBool::new(false));
+            {
+                let attempt_ctx = attempt_ctx.clone();
+                let timed_out = timed_out.clone();
+                tokio::spawn(async move {
+                    tokio::select! {
+                        _ = tokio::time::sleep(policy.attempt_timeout) => {
+                            timed_out.store(true, Ordering::SeqCst);
+                            attempt_ctx.cancel();
+                        }
+                        // the attempt finished first, nothing left to do
+                        _ = attempt_ctx.cancelled() => {}
+                    }
+                });
+            }
+            let result = self.search_ctx(&attempt_ctx, target, direction).await;
+            attempt_ctx.cancel();
+
+            let err = match result {
+                Ok(res) => return Ok(res),
+                Err(e) if timed_out.load(Ordering::SeqCst) => e.context(SearchError::Timeout),
+                Err(e) => e,
+            };
+            let transient = err
+                .downcast_ref::<SearchError>()
+                .is_some_and(SearchError::is_transient);
+            if !transient {
+                return Err(err);
+            }
+            if attempt >= policy.max_attempts {
+                return Err(err.context(format!("search gave up after {} attempts", attempt)));
+            }
+
+            let delay = policy.delay_after(attempt);
+            tracing::warn!(
+                "search attempt {} failed, retrying in {:?}: {}",
+                attempt,
+                delay,
+                err
+            );
+            ctx.run(async {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            })
+            .await?;
+            attempt += 1;
+        }
+    }
+
+    /// Runs the local step of a search and, unless it terminates here, registers a waiter for the
+    /// response and relays the request to the next hop.
+    fn begin_search_by_id(&self, req: IdSearchReq) -> anyhow::Result<PendingSearch> {
+        tracing::trace!("searching for target {}", req.target.short());
+        let correlation = req.correlation.unwrap_or_else(rand::random);
+        let req = IdSearchReq {
+            correlation: Some(correlation),
+            ..req
+        };
         let local_res = self
             .core
             .search_by_id(req)
-            .map_err(|e| anyhow!("failed to perform search by id {}", e))?;
+            .context("failed to perform search by id")?;
         if local_res.result == self.core.id() {
             tracing::trace!("found self in search by id, terminating the search result");
-            return Ok(local_res);
+            return Ok(PendingSearch::Done(Box::new(local_res)));
         }
 
+        let rx = self.relay_search(
+            local_res.result,
+            IdSearchReq::continue_from(&req, &local_res),
+            correlation,
+        )?;
+        tracing::info!("relayed search by id request to the next node, pending response");
+        Ok(PendingSearch::Waiting(rx))
+    }
+
+    /// Registers a waiter for the response to `req` and sends `req` to `next_hop`, returning the
+    /// receiver the response is handed over on. The waiter is removed again if the request cannot
+    /// be sent.
+    fn relay_search(
+        &self,
+        next_hop: Identifier,
+        req: IdSearchReq,
+        correlation: u64,
+    ) -> anyhow::Result<Receiver<IdSearchRes>> {
         let (tx, rx) = sync_channel::<IdSearchRes>(1);
+        self.relay_search_to(next_hop, req, Reply::Caller(tx), correlation)?;
+        Ok(rx)
+    }
+
+    /// Registers a waiter handing the response to `req` over to `reply`, and sends `req` to
+    /// `next_hop`. The waiter is removed again if the request cannot be sent.
+    fn relay_search_to(
+        &self,
+        next_hop: Identifier,
+        req: IdSearchReq,
+        reply: Reply,
+        correlation: u64,
+    ) -> anyhow::Result<()> {
         {
             let mut request_id_map = self
                 .request_id_map
                 .lock()
                 .expect("mutex was poisoned by a previous panic");
-            request_id_map.insert(req.nonce, tx);
-        }
-        let relay_request = SearchByIdRequest(IdSearchReq {
-            nonce: req.nonce,
-            target: req.target,
-            origin: self.core.id(),
-            level: local_res.termination_level,
-            direction: req.direction,
+            request_id_map.insert(req.nonce, Waiter { reply, correlation });
+        }
+
+        if let Err(e) = self.net.send_event(next_hop, SearchByIdRequest(req)) {
+            self.remove_waiter(&req.nonce);
+            // keeps the typed error in the chain so callers can tell a send failure apart.
+            return Err(anyhow::Error::new(SearchError::NetworkSend)
+                .context(format!("failed to perform search by id: {}", e)));
+        }
+        Ok(())
+    }
+
+    /// Finds this node's neighbors at every level through `introducer` and links them to it, in
+    /// the manner of the Aspnes & Shah insert: a search for the node's own identifier yields its
+    /// level-0 neighbors, and the neighbors at each higher level are found by walking the level
+    /// below away from the node until a node sharing enough membership-vector prefix is reached.
+    /// The overlay is assumed not to change while the walk is in progress. Every neighbor asked to
+    /// link to this node is appended to `linked`, so that a failed join can undo the links.
+    fn link_via(&self, introducer: Identifier, linked: &mut Vec<Identifier>) -> anyhow::Result<()> {
+        let id = self.core.id();
+        let closest = self.bootstrap(introducer)?;
+        if closest == id {
+            return Err(anyhow!("identifier {} is already in the overlay", id));
+        }
+
+        // the search terminates at the closest node on the introducer's side of this node; the
+        // other level-0 neighbor is that node's current neighbor towards this node.
+        let (mut left, mut right) = if closest > id {
+            let res = self.query_neighbor(closest, 0, Direction::Left)?;
+            (res.neighbor, Some(res.responder))
+        } else {
+            let res = self.query_neighbor(closest, 0, Direction::Right)?;
+            (Some(res.responder), res.neighbor)
+        };
+
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            if level > 0 {
+                left = self.walk_to_prefix_match(left, level, Direction::Left)?;
+                right = match left {
+                    // any node sharing the prefix with the left neighbor shares it with this
+                    // node too, so the left neighbor's right neighbor is this node's.
+                    Some(left) => {
+                        self.query_neighbor(left.id(), level, Direction::Right)?
+                            .neighbor
+                    }
+                    None => self.walk_to_prefix_match(right, level, Direction::Right)?,
+                };
+            }
+            if left.is_none() && right.is_none() {
+                // no node shares this many prefix bits, so none shares more either.
+                break;
+            }
+            if let Some(left) = left {
+                linked.push(left.id());
+                self.link(left, level, Direction::Left)?;
+            }
+            if let Some(right) = right {
+                linked.push(right.id());
+                self.link(right, level, Direction::Right)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks `introducer` to search for this node's identifier on its behalf, blocking until it
+    /// answers with the closest node it found on its own side of this node, or `RESPONSE_TIMEOUT`
+    /// elapses. The introducer does not need to know this node beforehand, and this node needs no
+    /// lookup table to ask.
+    pub(crate) fn bootstrap(&self, introducer: Identifier) -> anyhow::Result<Identifier> {
+        let nonce = Nonce::random();
+        let (tx, rx) = sync_channel::<BootstrapResult>(1);
+        self.bootstrap_waiters
+            .lock()
+            .expect("mutex was poisoned by a previous panic")
+            .insert(nonce, tx);
+
+        let bootstrap = Bootstrap {
+            new_node: self.core.identity(),
+            nonce,
+        };
+        let result = self
+            .net
+            .send_event(introducer, bootstrap)
+            .map_err(|e| anyhow!("failed to send bootstrap to {}: {}", introducer, e))
+            .and_then(|_| {
+                rx.recv_timeout(RESPONSE_TIMEOUT).map_err(|_| {
+                    anyhow!(
+                        "no bootstrap response from {} within {:?}",
+                        introducer,
+                        RESPONSE_TIMEOUT
+                    )
+                })
+            });
+        if result.is_err() {
+            self.bootstrap_waiters
+                .lock()
+                .expect("mutex was poisoned by a previous panic")
+                .remove(&nonce);
+        }
+        result?.map_err(|e| anyhow!("introducer {} failed to bootstrap: {}", introducer, e))
+    }
+
+    /// Starts the search an introducer runs on behalf of `new_node` for its `Bootstrap` with the
+    /// given nonce, without waiting for it: a search terminating at this node is answered at once,
+    /// and a relayed one once its response arrives.
+    fn start_bootstrap_search(&self, new_node: Identifier, nonce: Nonce) -> anyhow::Result<()> {
+        // searches towards the new node, so the result is its closest node on this side.
+        let direction = if self.core.id() > new_node {
+            Direction::Left
+        } else {
+            Direction::Right
+        };
+        let correlation = rand::random();
+        let req = IdSearchReq {
+            correlation: Some(correlation),
+            ..IdSearchReq::try_new(self.core.id(), new_node, LOOKUP_TABLE_LEVELS - 1, direction)?
+        };
+        let local_res = self
+            .core
+            .search_by_id(req)
+            .context("failed to search for bootstrapping node")?;
+        if local_res.result == self.core.id() {
+            return self.answer_bootstrap(new_node, nonce, Ok(local_res.result));
+        }
+        self.relay_search_to(
+            local_res.result,
+            IdSearchReq::continue_from(&req, &local_res),
+            Reply::Bootstrap { new_node, nonce },
+            correlation,
+        )
+    }
+
+    /// Sends `new_node` the `BootstrapResponse` to its `Bootstrap` with the given nonce.
+    fn answer_bootstrap(
+        &self,
+        new_node: Identifier,
+        nonce: Nonce,
+        closest: BootstrapResult,
+    ) -> anyhow::Result<()> {
+        tracing::info!("answering bootstrap of {:?} with {:?}", new_node, closest);
+        self.net
+            .send_event(new_node, BootstrapResponse { nonce, closest })
+            .context("failed to send bootstrap response")
+    }
+
+    /// Joins the overlay like `join`, trying each of `introducers` in order until one succeeds, so
+    /// that a single unreachable introducer does not prevent joining. If every attempt fails, the
+    /// returned error lists the failure of each introducer.
+    #[allow(dead_code)] // TODO: remove once nodes are started with a list of introducers.
+    pub(crate) fn join_via(&self, introducers: &[Identifier]) -> anyhow::Result<()> {
+        if introducers.is_empty() {
+            return Err(anyhow!("no introducer to join via"));
+        }
+        if self.is_joined() {
+            return Err(anyhow!("node is already joined"));
+        }
+
+        let mut failures = Vec::with_capacity(introducers.len());
+        for introducer in introducers {
+            match self.join(*introducer) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    // the error already names the introducer.
+                    tracing::warn!("{}", e);
+                    failures.push(e.to_string());
+                }
+            }
+        }
+        Err(anyhow!(
+            "failed to join via any of {} introducers: {}",
+            introducers.len(),
+            failures.join("; ")
+        ))
+    }
+
+    /// Fills this node's entry at `level` in `direction`, e.g., after detecting a gap there, by
+    /// walking the level below from its neighbor in `direction` until a node sharing at least
+    /// `level` bits of membership-vector prefix is reached, and linking to it. The entry is left
+    /// empty if no such node exists. Level 0 has no level below to walk, so it cannot be repaired
+    /// this way.
+    #[allow(dead_code)] // TODO: remove once a maintenance task repairs levels.
+    pub(crate) fn repair_level(
+        &self,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        let span = tracing::trace_span!("repair_level", level = level, direction = ?direction);
+        let _enter = span.enter();
+
+        if level == 0 || level >= LOOKUP_TABLE_LEVELS {
+            return Err(anyhow!(
+                "level {} cannot be repaired, it must be within 1..{}",
+                level,
+                LOOKUP_TABLE_LEVELS
+            ));
+        }
+        let start = self.core.lookup_table().get_entry(level - 1, direction)?;
+        match self.walk_to_prefix_match(start, level, direction)? {
+            Some(neighbor) => {
+                self.link(neighbor, level, direction)?;
+                tracing::info!("repaired level with neighbor {:?}", neighbor.id());
+            }
+            None => tracing::info!("no node to repair the level with"),
+        }
+        Ok(())
+    }
+
+    /// Removes the failed node `failed`, e.g., one that did not answer a `ping`, from every level of
+    /// this node's lookup table, and refills the lowest level vacated in each direction by promoting
+    /// the next-best neighbor the table still holds: the closest remaining neighbor in that
+    /// direction, at any level, sharing at least that level's number of membership-vector prefix
+    /// bits with this node. No other node is queried, so a level with no such neighbor is left
+    /// empty, for `repair_level` to fill over the network; so are the higher vacated levels.
+    #[allow(dead_code)] // TODO: remove once failure detection repairs neighbors.
+    pub(crate) fn repair_neighbor(&self, failed: &Identifier) -> anyhow::Result<()> {
+        let span = tracing::trace_span!("repair_neighbor", failed = ?failed);
+        let _enter = span.enter();
+
+        let lt = self.core.lookup_table();
+        let vacated = lt.remove_matching(failed)?;
+        tracing::info!(
+            "removed failed neighbor from {} lookup table entries",
+            vacated.len()
+        );
+
+        for direction in DIRECTIONS {
+            // positions are ordered by level, so the first one in a direction is its lowest.
+            let Some(level) = vacated
+                .iter()
+                .find(|(_, d)| *d == direction)
+                .map(|(level, _)| *level)
+            else {
+                continue;
+            };
+            let remaining = match direction {
+                Direction::Left => lt.left_neighbors()?,
+                Direction::Right => lt.right_neighbors()?,
+            };
+            let candidates =
+                remaining
+                    .into_iter()
+                    .map(|(_, identity)| identity)
+                    .filter(|identity| {
+                        identity.mem_vec().common_prefix_bit(self.core.mem_vec()) >= level
+                    });
+            // left neighbors lie below this node and right ones above, so the closest is the
+            // greatest on the left and the smallest on the right.
+            let promoted = match direction {
+                Direction::Left => candidates.max_by_key(|identity| identity.id()),
+                Direction::Right => candidates.min_by_key(|identity| identity.id()),
+            };
+            match promoted {
+                Some(neighbor) => {
+                    lt.replace_if_closer(self.core.id(), neighbor, level, direction)?;
+                    tracing::info!(
+                        "promoted neighbor {:?} to level {} in direction {:?}",
+                        neighbor.id(),
+                        level,
+                        direction
+                    );
+                }
+                None => tracing::info!(
+                    "no neighbor to promote to level {} in direction {:?}",
+                    level,
+                    direction
+                ),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns up to `k` of this node's neighbors in `direction`, at levels `0..=max_level`, that
+    /// do not overshoot `target`, sorted from the closest to `target` to the farthest, each with
+    /// the level it was found at. A neighbor present at several levels is listed once, at its
+    /// highest level, matching the tie-break of the search.
+    #[allow(dead_code)] // TODO: remove once searches fan out to several candidates.
+    pub(crate) fn k_closest(
+        &self,
+        target: &Identifier,
+        max_level: LookupTableLevel,
+        direction: Direction,
+        k: usize,
+    ) -> anyhow::Result<Vec<(LookupTableLevel, Identity)>> {
+        if max_level >= LOOKUP_TABLE_LEVELS {
+            return Err(anyhow!(
+                "max level {} out of range, it must be below {}",
+                max_level,
+                LOOKUP_TABLE_LEVELS
+            ));
+        }
+        let lt = self.core.lookup_table();
+        let neighbors = match direction {
+            Direction::Left => lt.left_neighbors()?,
+            Direction::Right => lt.right_neighbors()?,
+        };
+
+        let mut highest: HashMap<Identifier, (LookupTableLevel, Identity)> = HashMap::new();
+        for (level, identity) in neighbors {
+            let in_range = match direction {
+                Direction::Left => identity.id() >= *target,
+                Direction::Right => identity.id() <= *target,
+            };
+            if level > max_level || !in_range {
+                continue;
+            }
+            let entry = highest.entry(identity.id()).or_insert((level, identity));
+            if level > entry.0 {
+                *entry = (level, identity);
+            }
+        }
+
+        let mut candidates: Vec<(LookupTableLevel, Identity)> = highest.into_values().collect();
+        match direction {
+            Direction::Left => candidates.sort_by_key(|(_, identity)| identity.id()),
+            Direction::Right => {
+                candidates.sort_by_key(|(_, identity)| std::cmp::Reverse(identity.id()))
+            }
+        }
+        candidates.truncate(k);
+        Ok(candidates)
+    }
+
+    /// Starting at `start` and moving in `direction` along the level below `level`, returns the
+    /// first node sharing at least `level` bits of membership-vector prefix with this node.
+    fn walk_to_prefix_match(
+        &self,
+        start: Option<Identity>,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<Option<Identity>> {
+        let mut candidate = start;
+        while let Some(current) = candidate {
+            if current.mem_vec().common_prefix_bit(self.core.mem_vec()) >= level {
+                return Ok(Some(current));
+            }
+            candidate = self
+                .query_neighbor(current.id(), level - 1, direction)?
+                .neighbor;
+        }
+        Ok(None)
+    }
+
+    /// Installs `neighbor` in this node's lookup table and asks it to install this node in the
+    /// opposite direction of its own, at the same level.
+    fn link(
+        &self,
+        neighbor: Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        self.core
+            .lookup_table()
+            .update_entry(neighbor, level, direction)?;
+        let link_request = LinkRequest(LinkReq {
+            identity: self.core.identity(),
+            level,
+            direction: direction.opposite(),
         });
+        self.net
+            .send_event(neighbor.id(), link_request)
+            .map_err(|e| {
+                anyhow!(
+                    "failed to link to {} at level {}: {}",
+                    neighbor.id(),
+                    level,
+                    e
+                )
+            })
+    }
 
-        if let Err(e) = self.net.send_event(local_res.result, relay_request) {
-            self.request_id_map
+    /// Asks `target` for its neighbor at `level` in `direction`, blocking until it answers or
+    /// `RESPONSE_TIMEOUT` elapses.
+    fn query_neighbor(
+        &self,
+        target: Identifier,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<NeighborRes> {
+        let req = NeighborReq {
+            nonce: Nonce::random(),
+            level,
+            direction,
+        };
+        let (tx, rx) = sync_channel::<NeighborRes>(1);
+        self.neighbor_waiters
+            .lock()
+            .expect("mutex was poisoned by a previous panic")
+            .insert(req.nonce, tx);
+
+        let result = self
+            .net
+            .send_event(target, NeighborRequest(req))
+            .map_err(|e| anyhow!("failed to send neighbor request to {}: {}", target, e))
+            .and_then(|_| {
+                rx.recv_timeout(RESPONSE_TIMEOUT).map_err(|_| {
+                    anyhow!(
+                        "no neighbor response from {} within {:?}",
+                        target,
+                        RESPONSE_TIMEOUT
+                    )
+                })
+            });
+        if result.is_err() {
+            self.neighbor_waiters
                 .lock()
                 .expect("mutex was poisoned by a previous panic")
                 .remove(&req.nonce);
-            return Err(anyhow!("failed to perform search by id {}", e));
         }
-        tracing::info!("relayed search by id request to the next node, pending response");
-        match rx.recv() {
-            Ok(net_result) => {
-                tracing::info!(
-                    "received network response for search by id {:?}: {:?}",
-                    req.target,
-                    net_result.result
-                );
-                Ok(net_result)
+        result
+    }
+
+    /// Marks the node as joined without running the join protocol, for overlays whose lookup
+    /// tables are wired directly.
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn mark_joined(&self) {
+        self.joined.store(true, Ordering::SeqCst);
+    }
+
+    /// Sends a `Leave` to each of `neighbors`, so that they drop this node from their lookup tables,
+    /// and returns the number of distinct neighbors notified. A neighbor listed several times is
+    /// notified once. Notifying goes ahead even if some neighbors cannot be told; they keep a stale
+    /// entry until they notice the node is gone.
+    fn announce_leave(&self, mut neighbors: Vec<Identifier>) -> usize {
+        neighbors.sort();
+        neighbors.dedup();
+        let results = self
+            .net
+            .send_event_multi(&neighbors, Event::Leave(self.core.identity()));
+        for (neighbor, result) in neighbors.iter().zip(results) {
+            if let Err(e) = result {
+                tracing::warn!("failed to notify neighbor {:?} of leave: {}", neighbor, e);
             }
-            Err(_) => {
-                self.request_id_map
-                    .lock()
-                    .expect("mutex was poisoned by a previous panic")
-                    .remove(&req.nonce);
-                Err(anyhow!(
-                    "failed to receive network response for search by id"
-                ))
+        }
+        neighbors.len()
+    }
+
+    /// Removes every lookup table entry pointing at the given node, e.g., once it can no longer
+    /// be reached.
+    fn evict_neighbor(&self, id: Identifier) -> anyhow::Result<()> {
+        let removed = self.core.lookup_table().remove_matching(&id)?;
+        tracing::warn!(
+            "evicted unreachable neighbor {:?} from {} entries",
+            id,
+            removed.len()
+        );
+        Ok(())
+    }
+
+    /// Drops the waiter of the given search, if still pending.
+    fn remove_waiter(&self, nonce: &Nonce) {
+        self.request_id_map
+            .lock()
+            .expect("mutex was poisoned by a previous panic")
+            .remove(nonce);
+    }
+
+    /// Probes the liveness of `target` by sending it a `Ping`, and returns whether its `Pong` arrived
+    /// within `timeout`. Returns an error if the ping cannot be sent. Every ping carries a nonce of
+    /// its own that the pong echoes, so concurrent pings of the same target are answered by their
+    /// own pongs, and a late pong to an earlier ping does not answer a later one.
+    #[allow(dead_code)] // TODO: remove once failure detection pings neighbors.
+    pub(crate) async fn ping(&self, target: Identifier, timeout: Duration) -> anyhow::Result<bool> {
+        let span = tracing::trace_span!("ping", target = ?target);
+
+        async {
+            let nonce = Nonce::random();
+            let (tx, rx) = oneshot::channel();
+            self.pong_waiters
+                .lock()
+                .expect("mutex was poisoned by a previous panic")
+                .insert(nonce, PongWaiter { target, tx });
+
+            let result = match self.net.send_event(target, Ping(nonce)) {
+                Ok(()) => Ok(tokio::time::timeout(timeout, rx)
+                    .await
+                    .is_ok_and(|r| r.is_ok())),
+                Err(e) => Err(e.context(format!("failed to send ping to {}", target))),
+            };
+
+            // drops the waiter of this ping if no pong took it.
+            self.pong_waiters
+                .lock()
+                .expect("mutex was poisoned by a previous panic")
+                .remove(&nonce);
+            if let Ok(false) = result {
+                tracing::debug!("no pong from {:?} within {:?}", target, timeout);
             }
+            result
         }
+        .instrument(span)
+        .await
+    }
+
+    /// Returns the number of searches originated by this node that are still waiting for a response.
+    #[cfg(test)]
+    pub(crate) fn pending_search_count(&self) -> usize {
+        self.request_id_map
+            .lock()
+            .expect("mutex was poisoned by a previous panic")
+            .len()
+    }
+}
+
+/// Outcome of the local step of a search originated by this node.
+enum PendingSearch {
+    /// The search terminated locally.
+    Done(Box<IdSearchRes>),
+    /// The search was relayed; the response arrives on the receiver.
+    Waiting(Receiver<IdSearchRes>),
+}
+
+impl NodeView for BaseNode {
+    fn id(&self) -> Identifier {
+        self.core.id()
+    }
+
+    fn mem_vec(&self) -> MembershipVector {
+        self.core.mem_vec()
+    }
+
+    fn search_by_id(&self, req: IdSearchReq) -> anyhow::Result<IdSearchRes> {
+        let span = tracing::trace_span!(
+            "search_by_id",
+            target = ?req.target,
+            level = ?req.level,
+            trace_id = req.trace_id
+        );
+        let _enter = span.enter();
+
+        match self.begin_search_by_id(req)? {
+            PendingSearch::Done(res) => Ok(*res),
+            PendingSearch::Waiting(rx) => match rx.recv() {
+                Ok(net_result) => {
+                    tracing::info!(
+                        "received network response for search by id {}: {}",
+                        req.target.short(),
+                        net_result.result.short()
+                    );
+                    Ok(net_result)
+                }
+                Err(_) => {
+                    self.remove_waiter(&req.nonce);
+                    Err(anyhow!(
+                        "failed to receive network response for search by id"
+                    ))
+                }
+            },
+        }
+    }
+}
+
+impl Node for BaseNode {
+    fn join(&self, introducer: Identifier) -> anyhow::Result<()> {
+        let span = tracing::trace_span!("join", introducer = ?introducer);
+        let _enter = span.enter();
+
+        let _membership = self
+            .membership_lock
+            .lock()
+            .expect("mutex was poisoned by a previous panic");
+        if self.is_joined() {
+            return Err(anyhow!("node is already joined"));
+        }
+        if introducer == self.core.id() {
+            tracing::info!("introduced by self, starting a new overlay");
+            self.joined.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+
+        // on failure the neighbors linked so far are asked to drop this node again, and the node
+        // is left unjoined with an empty lookup table.
+        let mut linked = Vec::new();
+        if let Err(e) = self.link_via(introducer, &mut linked) {
+            self.announce_leave(linked);
+            self.core.lookup_table().clear()?;
+            return Err(anyhow!("failed to join via {}: {}", introducer, e));
+        }
+        self.joined.store(true, Ordering::SeqCst);
+        tracing::info!("joined the overlay");
+        Ok(())
+    }
+
+    fn leave(&self) -> anyhow::Result<()> {
+        let span = tracing::trace_span!("leave");
+        let _enter = span.enter();
+
+        let _membership = self
+            .membership_lock
+            .lock()
+            .expect("mutex was poisoned by a previous panic");
+        if !self.joined.swap(false, Ordering::SeqCst) {
+            return Err(anyhow!("node is not joined"));
+        }
+
+        let lt = self.core.lookup_table();
+        let neighbors: Vec<Identifier> = lt
+            .left_neighbors()?
+            .into_iter()
+            .chain(lt.right_neighbors()?)
+            .map(|(_, identity)| identity.id())
+            .collect();
+        let notified = self.announce_leave(neighbors);
+
+        lt.clear()?;
+        tracing::info!("left the overlay, notified {} neighbors", notified);
+        Ok(())
+    }
+
+    fn is_joined(&self) -> bool {
+        self.joined.load(Ordering::SeqCst)
     }
 }
 
@@ -148,10 +976,12 @@ impl EventProcessorCore for BaseNode {
             SearchByIdRequest(req) => {
                 let span = tracing::trace_span!(
                     "search_by_id_request",
+                    node = ?self.core.id(),
                     origin = ?origin_id,
                     target = ?req.target,
                     direction = ?req.direction,
-                    level = ?req.level
+                    level = ?req.level,
+                    trace_id = req.trace_id
                 );
                 let _enter = span.enter();
                 tracing::trace!("received request");
@@ -159,12 +989,13 @@ impl EventProcessorCore for BaseNode {
                 let res = self
                     .core
                     .search_by_id(req)
-                    .map_err(|e| anyhow!("failed to perform search by id {}", e))?;
+                    .context("failed to perform search by id")?;
 
                 let span = tracing::trace_span!(
                     "terminating",
                     result = ?res.result,
-                    termination_level = ?res.termination_level
+                    termination_level = ?res.termination_level,
+                    fallback_direction = ?res.fallback_direction
                 );
                 let _enter = span.enter();
 
@@ -178,19 +1009,20 @@ impl EventProcessorCore for BaseNode {
                     return Ok(());
                 }
 
-                let relay_request = SearchByIdRequest(IdSearchReq {
-                    level: res.termination_level,
-                    ..req
-                });
+                let relay_request = SearchByIdRequest(IdSearchReq::continue_from(&req, &res));
 
-                self.net
-                    .send_event(res.result, relay_request)
-                    .map_err(|e| {
-                        anyhow!(
-                            "failed to send relay response event for search by id: {}",
-                            e
-                        )
-                    })?;
+                if let Err(e) = self.net.send_event(res.result, relay_request) {
+                    // a neighbor that no longer resolves is dropped, so later searches route
+                    // around it; the typed error stays in the chain for the caller. A failure
+                    // reported from further down the relay is about another node's neighbor, which
+                    // this node did not try to reach, so it is left to that node.
+                    if let Some(NetworkError::TargetNotFound(dead)) = NetworkError::from_error(&e) {
+                        if dead == res.result {
+                            self.evict_neighbor(dead)?;
+                        }
+                    }
+                    return Err(e.context("failed to send relay request event for search by id"));
+                }
                 tracing::info!("relayed search by id request to the next node");
                 Ok(())
             }
@@ -200,21 +1032,236 @@ impl EventProcessorCore for BaseNode {
                     origin = ?origin_id,
                     target = ?res.target,
                     result = ?res.result,
-                    termination_level = ?res.termination_level
+                    termination_level = ?res.termination_level,
+                    trace_id = res.trace_id
                 );
                 let _enter = span.enter();
 
+                let waiter = {
+                    let mut request_id_map = self
+                        .request_id_map
+                        .lock()
+                        .expect("mutex was poisoned by a previous panic");
+                    // a response that does not echo the request's correlation is not an answer
+                    // to it, so the waiter is kept for the genuine response.
+                    if let Some(waiter) = request_id_map.get(&res.nonce) {
+                        if res.correlation != Some(waiter.correlation) {
+                            tracing::warn!(
+                                "dropping search by id response with mismatched correlation {:?}",
+                                res.correlation
+                            );
+                            return Err(anyhow!(
+                                "search by id response correlation {:?} does not match request correlation {}",
+                                res.correlation,
+                                waiter.correlation
+                            ));
+                        }
+                    }
+                    request_id_map.remove(&res.nonce)
+                };
+                match waiter.map(|waiter| waiter.reply) {
+                    Some(Reply::Caller(tx)) => {
+                        if let Err(e) = tx.send(res) {
+                            tracing::warn!(
+                                "failed to send the response to the receiver end: {:?}",
+                                e
+                            )
+                        }
+                    }
+                    Some(Reply::Bootstrap { new_node, nonce }) => {
+                        self.answer_bootstrap(new_node, nonce, Ok(res.result))?
+                    }
+                    None => {}
+                }
+
+                Ok(())
+            }
+            NeighborRequest(req) => {
+                let neighbor = self
+                    .core
+                    .lookup_table()
+                    .get_entry(req.level, req.direction)?;
+                let res = NeighborResponse(Box::new(NeighborRes {
+                    nonce: req.nonce,
+                    responder: self.core.identity(),
+                    neighbor,
+                }));
+                self.net
+                    .send_event(origin_id, res)
+                    .map_err(|e| anyhow!("failed to send neighbor response: {}", e))
+            }
+            NeighborResponse(res) => {
                 let waiter = self
-                    .request_id_map
+                    .neighbor_waiters
                     .lock()
                     .expect("mutex was poisoned by a previous panic")
                     .remove(&res.nonce);
                 if let Some(tx) = waiter {
-                    if let Err(e) = tx.send(res) {
+                    if let Err(e) = tx.send(*res) {
                         tracing::warn!("failed to send the response to the receiver end: {:?}", e)
                     }
                 }
+                Ok(())
+            }
+            Bootstrap { new_node, nonce } => {
+                let span = tracing::trace_span!("bootstrap", new_node = ?new_node.id());
+                let _enter = span.enter();
+
+                // the handler does not wait for a relayed search, so that it never blocks event
+                // processing; a search that cannot even start is reported back to the new node.
+                if let Err(e) = self.start_bootstrap_search(new_node.id(), nonce) {
+                    tracing::warn!("failed to bootstrap {:?}: {:#}", new_node.id(), e);
+                    self.answer_bootstrap(new_node.id(), nonce, Err(format!("{:#}", e)))?;
+                }
+                Ok(())
+            }
+            BootstrapResponse { nonce, closest } => {
+                let waiter = self
+                    .bootstrap_waiters
+                    .lock()
+                    .expect("mutex was poisoned by a previous panic")
+                    .remove(&nonce);
+                if let Some(tx) = waiter {
+                    if let Err(e) = tx.send(closest) {
+                        tracing::warn!("failed to send the response to the receiver end: {:?}", e)
+                    }
+                }
+                Ok(())
+            }
+            LinkRequest(req) => {
+                // only a node itself asks to be linked, at a level its membership vector
+                // qualifies it for.
+                if req.identity.id() != origin_id {
+                    return Err(anyhow!(
+                        "link of {} requested by another node {}",
+                        req.identity.id(),
+                        origin_id
+                    ));
+                }
+                if req.level >= LOOKUP_TABLE_LEVELS {
+                    return Err(anyhow!(
+                        "link level {} is beyond the lookup table levels {}",
+                        req.level,
+                        LOOKUP_TABLE_LEVELS
+                    ));
+                }
+                let prefix = req
+                    .identity
+                    .mem_vec()
+                    .common_prefix_bit(self.core.mem_vec());
+                if prefix < req.level {
+                    return Err(anyhow!(
+                        "node {} asking to be linked shares {} prefix bits, fewer than its level {}",
+                        req.identity.id(),
+                        prefix,
+                        req.level
+                    ));
+                }
+                tracing::trace!(
+                    "linking {:?} at level {} in direction {:?}",
+                    req.identity.id(),
+                    req.level,
+                    req.direction
+                );
+                self.core
+                    .lookup_table()
+                    .update_entry(req.identity, req.level, req.direction)
+            }
+            NeighborGossip {
+                about,
+                level,
+                direction,
+            } => {
+                let span = tracing::trace_span!(
+                    "neighbor_gossip",
+                    origin = ?origin_id,
+                    about = ?about.id(),
+                    level = level,
+                    direction = ?direction
+                );
+                let _enter = span.enter();
+
+                if level >= LOOKUP_TABLE_LEVELS {
+                    return Err(anyhow!(
+                        "gossiped level {} is beyond the lookup table levels {}",
+                        level,
+                        LOOKUP_TABLE_LEVELS
+                    ));
+                }
+                // a level-l neighbor must share at least l membership-vector prefix bits.
+                let prefix = about.mem_vec().common_prefix_bit(self.core.mem_vec());
+                if prefix < level {
+                    return Err(anyhow!(
+                        "gossiped neighbor {} shares {} prefix bits, fewer than its level {}",
+                        about.id(),
+                        prefix,
+                        level
+                    ));
+                }
 
+                let replaced = self.core.lookup_table().replace_if_closer(
+                    self.core.id(),
+                    about,
+                    level,
+                    direction,
+                )?;
+                tracing::trace!("gossiped neighbor applied: {}", replaced);
+                Ok(())
+            }
+            Event::Leave(leaver) => {
+                let span = tracing::trace_span!(
+                    "leave_notification",
+                    origin = ?origin_id,
+                    leaver = ?leaver.id()
+                );
+                let _enter = span.enter();
+
+                // only a node itself announces its departure.
+                if leaver.id() != origin_id {
+                    return Err(anyhow!(
+                        "leave of {} announced by another node {}",
+                        leaver.id(),
+                        origin_id
+                    ));
+                }
+                let removed = self.core.lookup_table().remove_matching(&leaver.id())?;
+                tracing::trace!("removed departed neighbor from {} entries", removed.len());
+                Ok(())
+            }
+            Ping(nonce) => self
+                .net
+                .send_event(origin_id, Pong(nonce))
+                .map_err(|e| e.context(format!("failed to send pong to {}", origin_id))),
+            Pong(nonce) => {
+                let mut waiters = self
+                    .pong_waiters
+                    .lock()
+                    .expect("mutex was poisoned by a previous panic");
+                // only the pinged node answers a ping; a waiter whose ping already timed out is
+                // gone, and its pong is dropped.
+                if let Some(waiter) = waiters.get(&nonce) {
+                    if waiter.target != origin_id {
+                        return Err(anyhow!(
+                            "pong to the ping of {} sent by another node {}",
+                            waiter.target,
+                            origin_id
+                        ));
+                    }
+                    if let Some(waiter) = waiters.remove(&nonce) {
+                        let _ = waiter.tx.send(());
+                    }
+                }
+                Ok(())
+            }
+            Event::Unknown { tag, bytes } => {
+                // a peer running a newer version may send payloads this node does not know;
+                // dropping them keeps the node working with it during a rolling upgrade.
+                tracing::debug!(
+                    origin = ?origin_id,
+                    tag = tag,
+                    len = bytes.len(),
+                    "ignoring event payload of unknown kind"
+                );
                 Ok(())
             }
             _ => {
@@ -252,6 +1299,11 @@ impl Clone for BaseNode {
             span: self.span.clone(),
             ctx: self.ctx.clone(),
             request_id_map: self.request_id_map.clone(),
+            neighbor_waiters: self.neighbor_waiters.clone(),
+            bootstrap_waiters: self.bootstrap_waiters.clone(),
+            pong_waiters: self.pong_waiters.clone(),
+            joined: self.joined.clone(),
+            membership_lock: self.membership_lock.clone(),
         }
     }
 }
@@ -259,12 +1311,17 @@ impl Clone for BaseNode {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::model::identifier::{MAX, ZERO};
     use crate::core::testutil::fixtures::{
-        random_identifier, random_membership_vector, span_fixture,
+        new_core, new_node, new_node_with, random_address, random_identifier,
+        random_lookup_table_with_extremes, random_membership_vector, random_sorted_identifiers,
+        span_fixture,
     };
     use crate::core::ArrayLookupTable;
+    use crate::network::mock::hub::NetworkHub;
     use crate::network::NetworkMock;
     use crate::node::core::BaseCore;
+    use std::sync::atomic::AtomicUsize;
     use unimock::*;
 
     #[test]
@@ -286,6 +1343,7 @@ mod tests {
             span.clone(),
             id,
             mem_vec,
+            random_address(),
             Box::new(ArrayLookupTable::new()),
         ));
 
@@ -293,4 +1351,451 @@ mod tests {
         assert_eq!(node.id(), id);
         assert_eq!(node.mem_vec(), mem_vec);
     }
+
+    /// Verifies that an unregistered node receives no events until the caller registers a
+    /// processor wrapping it, and that a search request routed through the wrapper is then
+    /// handled by the node, which answers the originator.
+    #[test]
+    fn test_new_unregistered_behind_wrapping_processor() {
+        /// Forwards every event to the wrapped node, counting them.
+        struct Wrapper {
+            node: BaseNode,
+            forwarded: Arc<AtomicUsize>,
+        }
+
+        impl EventProcessorCore for Wrapper {
+            fn process_incoming_event(
+                &self,
+                origin_id: Identifier,
+                event: Event,
+            ) -> anyhow::Result<()> {
+                self.forwarded.fetch_add(1, Ordering::SeqCst);
+                self.node.process_incoming_event(origin_id, event)
+            }
+        }
+
+        /// Hands every search response it receives over on a channel.
+        struct ResponseCollector {
+            tx: SyncSender<IdSearchRes>,
+        }
+
+        impl EventProcessorCore for ResponseCollector {
+            fn process_incoming_event(&self, _: Identifier, event: Event) -> anyhow::Result<()> {
+                match event {
+                    SearchByIdResponse(res) => Ok(self.tx.send(res)?),
+                    _ => Err(anyhow!("unexpected event {}", event.kind())),
+                }
+            }
+        }
+
+        let hub = NetworkHub::new();
+        let id = random_identifier();
+        let net = NetworkHub::new_mock_network(hub.clone(), id).unwrap();
+        let core = new_core(
+            id,
+            random_membership_vector(),
+            Box::new(ArrayLookupTable::new()),
+        );
+        let node = BaseNode::new_unregistered(span_fixture(), core, net.clone_box()).unwrap();
+
+        let origin_id = random_identifier();
+        let origin_net = NetworkHub::new_mock_network(hub.clone(), origin_id).unwrap();
+        let (tx, rx) = sync_channel(1);
+        origin_net
+            .register_processor(MessageProcessor::new(Box::new(ResponseCollector { tx })))
+            .unwrap();
+
+        let req = IdSearchReq::builder(origin_id)
+            .target(random_identifier())
+            .direction(Direction::Left)
+            .build()
+            .unwrap();
+        assert!(
+            hub.route_event(origin_id, id, SearchByIdRequest(req))
+                .is_err(),
+            "an unregistered node should receive no events"
+        );
+
+        let forwarded = Arc::new(AtomicUsize::new(0));
+        net.register_processor(MessageProcessor::new(Box::new(Wrapper {
+            node: node.clone(),
+            forwarded: forwarded.clone(),
+        })))
+        .unwrap();
+        hub.route_event(origin_id, id, SearchByIdRequest(req))
+            .expect("failed to route search request");
+
+        assert_eq!(forwarded.load(Ordering::SeqCst), 1);
+        let res = rx
+            .try_recv()
+            .expect("originator should have received a response");
+        assert_eq!(res.nonce, req.nonce);
+        assert_eq!(res.result, id);
+    }
+
+    /// Verifies that gossip about a neighbor updates the receiver's lookup table only when the
+    /// gossiped node is closer than the current entry and on the right side of the receiver, and
+    /// that gossip with an out-of-range level or a too-short membership-vector prefix is rejected.
+    #[test]
+    fn test_neighbor_gossip_replaces_only_closer() {
+        let hub = NetworkHub::new();
+        let ids = random_sorted_identifiers(3);
+        let (id, near_id, far_id) = (ids[0], ids[1], ids[2]);
+        let mem_vec = random_membership_vector();
+        let node = new_node_with(&hub, id, mem_vec, Box::new(ArrayLookupTable::new()));
+        let lt = node.lookup_table();
+
+        let near = Identity::new(near_id, mem_vec, random_address());
+        let far = Identity::new(far_id, mem_vec, random_address());
+        let gossip = |about, level, direction| {
+            hub.route_event(
+                random_identifier(),
+                id,
+                NeighborGossip {
+                    about,
+                    level,
+                    direction,
+                },
+            )
+        };
+
+        gossip(far, 2, Direction::Right).unwrap();
+        assert_eq!(lt.get_entry(2, Direction::Right).unwrap(), Some(far));
+        gossip(near, 2, Direction::Right).unwrap();
+        assert_eq!(lt.get_entry(2, Direction::Right).unwrap(), Some(near));
+        gossip(far, 2, Direction::Right).unwrap();
+        assert_eq!(
+            lt.get_entry(2, Direction::Right).unwrap(),
+            Some(near),
+            "a farther neighbor should not replace a closer one"
+        );
+        gossip(near, 2, Direction::Left).unwrap();
+        assert_eq!(
+            lt.get_entry(2, Direction::Left).unwrap(),
+            None,
+            "a node on the right is not a left neighbor"
+        );
+
+        assert!(gossip(near, LOOKUP_TABLE_LEVELS, Direction::Right).is_err());
+        let mut other_bytes = mem_vec.to_bytes();
+        other_bytes[0] ^= 0x80;
+        let unrelated = Identity::new(
+            near_id,
+            MembershipVector::from_bytes(&other_bytes).unwrap(),
+            random_address(),
+        );
+        assert!(gossip(unrelated, 1, Direction::Right).is_err());
+        assert_eq!(lt.get_entry(1, Direction::Right).unwrap(), None);
+    }
+
+    /// Verifies that relaying a search to a neighbor that no longer resolves fails with
+    /// `NetworkError::TargetNotFound` and evicts that neighbor from every lookup table entry.
+    #[test]
+    fn test_unreachable_neighbor_is_evicted() {
+        let hub = NetworkHub::new();
+        let ids = random_sorted_identifiers(3);
+        let (id, dead_id, target) = (ids[0], ids[1], ids[2]);
+        let node = new_node(&hub, id);
+        // the neighbor has no network in the hub, so it cannot be reached.
+        let dead = Identity::new(dead_id, random_membership_vector(), random_address());
+        for level in 0..3 {
+            node.lookup_table()
+                .update_entry(dead, level, Direction::Right)
+                .unwrap();
+        }
+
+        let req = IdSearchReq::builder(random_identifier())
+            .target(target)
+            .direction(Direction::Right)
+            .build()
+            .unwrap();
+        let err = node
+            .process_incoming_event(random_identifier(), SearchByIdRequest(req))
+            .expect_err("relaying to an unreachable neighbor should fail");
+        assert_eq!(
+            NetworkError::from_error(&err),
+            Some(NetworkError::TargetNotFound(dead_id))
+        );
+        assert!(node
+            .lookup_table()
+            .find_entry_by_id(&dead_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Verifies that a node relaying a search evicts only the neighbor it failed to reach itself:
+    /// when its next hop fails to reach a neighbor of its own, the failure comes back in the error
+    /// chain, but the relaying node keeps that neighbor.
+    #[test]
+    fn test_downstream_unreachable_neighbor_is_not_evicted() {
+        let hub = NetworkHub::new();
+        let ids = random_sorted_identifiers(4);
+        let (a_id, b_id, dead_id, target) = (ids[0], ids[1], ids[2], ids[3]);
+        let a = new_node(&hub, a_id);
+        let b = new_node(&hub, b_id);
+        // the dead node has no network in the hub; `a` holds it above the searched level only.
+        let dead = Identity::new(dead_id, random_membership_vector(), random_address());
+        a.lookup_table()
+            .update_entry(b.identity(), 0, Direction::Right)
+            .unwrap();
+        a.lookup_table()
+            .update_entry(dead, 5, Direction::Right)
+            .unwrap();
+        b.lookup_table()
+            .update_entry(dead, 0, Direction::Right)
+            .unwrap();
+
+        let req = IdSearchReq::builder(random_identifier())
+            .target(target)
+            .direction(Direction::Right)
+            .level(0)
+            .build()
+            .unwrap();
+        let err = a
+            .process_incoming_event(random_identifier(), SearchByIdRequest(req))
+            .expect_err("relaying past an unreachable neighbor should fail");
+        assert_eq!(
+            NetworkError::from_error(&err),
+            Some(NetworkError::TargetNotFound(dead_id))
+        );
+
+        assert!(b
+            .lookup_table()
+            .find_entry_by_id(&dead_id)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            a.lookup_table().find_entry_by_id(&dead_id).unwrap(),
+            vec![(5, Direction::Right)],
+            "the relaying node never tried to reach the dead node"
+        );
+    }
+
+    /// Verifies that a node ignores an event decoded from a made-up tag, as sent by a newer peer,
+    /// instead of failing, and keeps processing events afterwards.
+    #[test]
+    fn test_unknown_event_is_ignored() {
+        let hub = NetworkHub::new();
+        let id = random_identifier();
+        let node = new_node(&hub, id);
+
+        let unknown = Event::decode_lenient(&[0xee, 0xde, 0xad, 0xbe, 0xef]).unwrap();
+        assert!(matches!(unknown, Event::Unknown { tag: 0xee, .. }));
+        hub.route_event(random_identifier(), id, unknown)
+            .expect("an unknown event should be ignored");
+
+        let identity = Identity::new(
+            random_identifier(),
+            random_membership_vector(),
+            random_address(),
+        );
+        hub.route_event(
+            identity.id(),
+            id,
+            LinkRequest(LinkReq {
+                identity,
+                level: 0,
+                direction: Direction::Left,
+            }),
+        )
+        .expect("node should keep processing events");
+        assert_eq!(
+            node.lookup_table().get_entry(0, Direction::Left).unwrap(),
+            Some(identity)
+        );
+    }
+
+    /// Verifies that a link request is applied only when it comes from the node to be linked, at
+    /// a level within the lookup table that its membership-vector prefix qualifies it for.
+    #[test]
+    fn test_link_request_validates_origin_and_level() {
+        let hub = NetworkHub::new();
+        let id = random_identifier();
+        let mem_vec = random_membership_vector();
+        let node = new_node_with(&hub, id, mem_vec, Box::new(ArrayLookupTable::new()));
+        let link = |origin, identity, level| {
+            hub.route_event(
+                origin,
+                id,
+                LinkRequest(LinkReq {
+                    identity,
+                    level,
+                    direction: Direction::Right,
+                }),
+            )
+        };
+
+        let peer = Identity::new(random_identifier(), mem_vec, random_address());
+        assert!(
+            link(random_identifier(), peer, 0).is_err(),
+            "a node cannot link another one"
+        );
+        assert!(link(peer.id(), peer, LOOKUP_TABLE_LEVELS).is_err());
+        let mut other_bytes = mem_vec.to_bytes();
+        other_bytes[0] ^= 0x80;
+        let unrelated = Identity::new(
+            random_identifier(),
+            MembershipVector::from_bytes(&other_bytes).unwrap(),
+            random_address(),
+        );
+        assert!(link(unrelated.id(), unrelated, 1).is_err());
+        assert!(node.lookup_table().right_neighbors().unwrap().is_empty());
+
+        link(peer.id(), peer, 3).expect("a node should link itself");
+        assert_eq!(
+            node.lookup_table().get_entry(3, Direction::Right).unwrap(),
+            Some(peer)
+        );
+    }
+
+    /// Verifies that `k_closest` returns at most `k` distinct neighbors sorted from the closest
+    /// to the target, starting with the extreme entry of the table in each direction, and that
+    /// levels beyond the lookup table are refused.
+    #[test]
+    fn test_k_closest_sorted_and_limited() {
+        let hub = NetworkHub::new();
+        let node = new_node_with(
+            &hub,
+            random_identifier(),
+            random_membership_vector(),
+            Box::new(random_lookup_table_with_extremes(LOOKUP_TABLE_LEVELS)),
+        );
+        let k = 5;
+
+        let left = node
+            .k_closest(&ZERO, LOOKUP_TABLE_LEVELS - 1, Direction::Left, k)
+            .unwrap();
+        assert_eq!(left.len(), k);
+        assert_eq!(left[0].1.id(), ZERO);
+        assert!(left.windows(2).all(|w| w[0].1.id() < w[1].1.id()));
+
+        let right = node
+            .k_closest(&MAX, LOOKUP_TABLE_LEVELS - 1, Direction::Right, k)
+            .unwrap();
+        assert_eq!(right.len(), k);
+        assert_eq!(right[0].1.id(), MAX);
+        assert!(right.windows(2).all(|w| w[0].1.id() > w[1].1.id()));
+
+        // only level 0 is scanned, and it holds the extreme alone.
+        let level_zero = node.k_closest(&ZERO, 0, Direction::Left, k).unwrap();
+        assert_eq!(level_zero.len(), 1);
+        assert_eq!(level_zero[0], (0, left[0].1));
+
+        assert!(node
+            .k_closest(&ZERO, LOOKUP_TABLE_LEVELS, Direction::Left, k)
+            .is_err());
+    }
+
+    /// Verifies that a ping over mock networks is answered by the target's pong in time, and that
+    /// pinging a node that is not in the hub fails to send, leaving no waiter behind either way.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ping_pong_round_trip() {
+        let hub = NetworkHub::new();
+        let pinger = new_node(&hub, random_identifier());
+        let target = new_node(&hub, random_identifier());
+
+        let alive = pinger
+            .ping(target.id(), Duration::from_secs(1))
+            .await
+            .expect("ping failed");
+        assert!(alive, "target should answer the ping in time");
+
+        let unknown = random_identifier();
+        let err = pinger
+            .ping(unknown, Duration::from_millis(50))
+            .await
+            .expect_err("pinging an unknown node should fail to send");
+        assert_eq!(
+            NetworkError::from_error(&err),
+            Some(NetworkError::TargetNotFound(unknown))
+        );
+        assert!(pinger.pong_waiters.lock().unwrap().is_empty());
+    }
+
+    /// Verifies that concurrent pings of the same target are each answered by their own pong, and
+    /// that a pong carrying the nonce of a pending ping answers it only if the pinged node sent it.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ping_correlates_pongs_by_nonce() {
+        let hub = NetworkHub::new();
+        let pinger = new_node(&hub, random_identifier());
+        let target = new_node(&hub, random_identifier());
+
+        let (first, second) = tokio::join!(
+            pinger.ping(target.id(), Duration::from_secs(1)),
+            pinger.ping(target.id(), Duration::from_secs(1))
+        );
+        assert!(first.expect("first ping failed"));
+        assert!(second.expect("second ping failed"));
+        assert!(pinger.pong_waiters.lock().unwrap().is_empty());
+
+        let nonce = Nonce::random();
+        let (tx, mut rx) = oneshot::channel();
+        pinger.pong_waiters.lock().unwrap().insert(
+            nonce,
+            PongWaiter {
+                target: target.id(),
+                tx,
+            },
+        );
+        assert!(pinger
+            .process_incoming_event(random_identifier(), Pong(nonce))
+            .is_err());
+        assert!(
+            rx.try_recv().is_err(),
+            "pong of another node answered the ping"
+        );
+        pinger
+            .process_incoming_event(target.id(), Pong(nonce))
+            .expect("failed to process pong");
+        assert!(rx.try_recv().is_ok(), "pong of the pinged node was dropped");
+        assert!(pinger.pong_waiters.lock().unwrap().is_empty());
+    }
+
+    /// Verifies that repairing a failed mid-level neighbor removes it from every level, promotes
+    /// the lower-level neighbor sharing enough membership-vector prefix bits to the lowest vacated
+    /// level, and leaves a higher vacated level that it does not qualify for empty.
+    #[test]
+    fn test_repair_neighbor_promotes_lower_level_neighbor() {
+        let hub = NetworkHub::new();
+        let ids = random_sorted_identifiers(3);
+        let (id, near_id, failed_id) = (ids[0], ids[1], ids[2]);
+        let mem_vec = MembershipVector::from_bytes(&[0u8; 32]).unwrap();
+        let node = new_node_with(&hub, id, mem_vec, Box::new(ArrayLookupTable::new()));
+
+        // `near` shares exactly 1 prefix bit with the node, `failed` shares 255.
+        let mut near_bytes = [0u8; 32];
+        near_bytes[0] = 0x40;
+        let near = Identity::new(
+            near_id,
+            MembershipVector::from_bytes(&near_bytes).unwrap(),
+            random_address(),
+        );
+        let mut failed_bytes = [0u8; 32];
+        failed_bytes[31] = 0x01;
+        let failed = Identity::new(
+            failed_id,
+            MembershipVector::from_bytes(&failed_bytes).unwrap(),
+            random_address(),
+        );
+        let lt = node.lookup_table();
+        lt.update_entry(near, 0, Direction::Right).unwrap();
+        lt.update_entry(failed, 1, Direction::Right).unwrap();
+        lt.update_entry(failed, 2, Direction::Right).unwrap();
+
+        node.repair_neighbor(&failed_id).unwrap();
+
+        assert!(lt.find_entry_by_id(&failed_id).unwrap().is_empty());
+        assert_eq!(lt.get_entry(0, Direction::Right).unwrap(), Some(near));
+        assert_eq!(
+            lt.get_entry(1, Direction::Right).unwrap(),
+            Some(near),
+            "the level-0 neighbor should be promoted to the lowest vacated level"
+        );
+        assert_eq!(
+            lt.get_entry(2, Direction::Right).unwrap(),
+            None,
+            "the promoted neighbor does not share enough prefix bits for level 2"
+        );
+        assert!(lt.left_neighbors().unwrap().is_empty());
+    }
 }
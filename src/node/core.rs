@@ -1,6 +1,11 @@
 use crate::core::model::direction::Direction;
-use crate::core::{IdSearchReq, IdSearchRes, Identifier, LookupTable, MembershipVector};
-use anyhow::anyhow;
+use crate::core::model::identity::Identity;
+use crate::core::{
+    Address, IdSearchReq, IdSearchRes, Identifier, LookupTable, LookupTableError, LookupTableLevel,
+    MembershipVector, LOOKUP_TABLE_LEVELS,
+};
+use anyhow::{anyhow, Context};
+use std::collections::HashSet;
 use tracing::Span;
 
 /// Core is the pure-local interface for a skip-graph node's algorithms.
@@ -20,13 +25,90 @@ pub trait Core: Send + Sync {
     /// Returns the membership vector of the node this core belongs to.
     fn mem_vec(&self) -> MembershipVector;
 
+    /// Returns the network address of the node this core belongs to.
+    fn address(&self) -> Address;
+
+    /// Returns the identity (identifier, membership vector, and address) of the node this core
+    /// belongs to, as it is stored in the lookup tables of its neighbors.
+    fn identity(&self) -> Identity {
+        Identity::new(self.id(), self.mem_vec(), self.address())
+    }
+
+    /// Returns a shallow copy of the lookup table backing this core. Changes
+    /// made through the returned handle are visible to the core.
+    fn lookup_table(&self) -> Box<dyn LookupTable>;
+
     /// Performs a local search for the given identifier in the lookup table
     /// in the direction and up to the level specified by the request. The
     /// result is the closest neighbor satisfying the directional constraint,
     /// or — if no such neighbor exists at any level — the caller's own
-    /// identifier at level 0 (the Aspnes & Shah fallback).
+    /// identifier at level 0 (the Aspnes & Shah fallback), with the request's
+    /// direction recorded as the fallback direction.
     fn search_by_id(&self, req: IdSearchReq) -> anyhow::Result<IdSearchRes>;
 
+    /// Returns the neighbors in the lookup table whose identifiers lie within `low..=high`, sorted
+    /// by identifier, each with the level it was found at. A neighbor held at several levels is
+    /// listed once, at the lowest of them. Returns an error if `low` is greater than `high`.
+    fn search_by_range(
+        &self,
+        low: Identifier,
+        high: Identifier,
+    ) -> anyhow::Result<Vec<(LookupTableLevel, Identity)>> {
+        if low > high {
+            return Err(anyhow!(
+                "invalid search range, low {} is greater than high {}",
+                low,
+                high
+            ));
+        }
+        let lt = self.lookup_table();
+        // neighbors come ordered by level, so the first occurrence of an identifier is its lowest.
+        let mut seen = HashSet::new();
+        let mut found: Vec<(LookupTableLevel, Identity)> = lt
+            .left_neighbors()?
+            .into_iter()
+            .chain(lt.right_neighbors()?)
+            .filter(|(_, identity)| low <= identity.id() && identity.id() <= high)
+            .filter(|(_, identity)| seen.insert(identity.id()))
+            .collect();
+        found.sort_by_key(|(_, identity)| identity.id());
+        Ok(found)
+    }
+
+    /// Merges the `discovered` identities into the lookup table, then searches as `search_by_id`
+    /// does. A discovered identity sharing `p` membership-vector prefix bits with this node is a
+    /// valid neighbor at levels `0..=p`; at each of them, on its side of this node, it replaces
+    /// the entry if it is closer to this node (see `LookupTable::replace_if_closer`). This node
+    /// itself is skipped.
+    fn search_and_learn(
+        &self,
+        req: &IdSearchReq,
+        discovered: &[Identity],
+    ) -> anyhow::Result<IdSearchRes> {
+        let lt = self.lookup_table();
+        for identity in discovered {
+            let direction = if identity.id() < self.id() {
+                Direction::Left
+            } else if identity.id() > self.id() {
+                Direction::Right
+            } else {
+                continue;
+            };
+            let prefix = identity.mem_vec().common_prefix_bit(self.mem_vec());
+            for level in 0..=prefix.min(LOOKUP_TABLE_LEVELS - 1) {
+                if lt.replace_if_closer(self.id(), *identity, level, direction)? {
+                    tracing::trace!(
+                        "learned neighbor {:?} at level {} in direction {:?}",
+                        identity.id(),
+                        level,
+                        direction
+                    );
+                }
+            }
+        }
+        self.search_by_id(*req)
+    }
+
     /// Performs a local search for the given membership vector.
     #[allow(dead_code)]
     fn search_by_mem_vec(&self, req: IdSearchReq) -> anyhow::Result<IdSearchRes>;
@@ -42,35 +124,59 @@ impl Clone for Box<dyn Core> {
     }
 }
 
+/// PoisonPolicy decides how `BaseCore` reacts when its lookup table reports a poisoned lock
+/// (`LookupTableError::Poisoned`) during a search, as opposed to any other lookup failure.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) enum PoisonPolicy {
+    /// Abort the search with an error that still downcasts to `LookupTableError`.
+    #[default]
+    FailFast,
+    /// Continue the search using the last entry written before the lock was poisoned.
+    #[allow(dead_code)] // TODO: remove once a production caller opts in.
+    Recover,
+}
+
 /// `BaseCore` is the concrete `Core` implementation backed by an
 /// `ArrayLookupTable`-style lookup table. It owns the node's identifier,
-/// membership vector, and lookup table. All state is shallow-cloneable via
+/// membership vector, address, and lookup table. All state is shallow-cloneable via
 /// the Arc-backed lookup table; cloned instances share the same LT.
 // TODO: Remove #[allow(dead_code)] once BaseCore is used in production code.
 #[allow(dead_code)]
 pub struct BaseCore {
     id: Identifier,
     mem_vec: MembershipVector,
+    address: Address,
     lt: Box<dyn LookupTable>,
+    poison_policy: PoisonPolicy,
     span: Span,
 }
 
 impl BaseCore {
-    #[cfg(test)] // TODO: remove once BaseCore is used in production code.
+    #[cfg(any(test, feature = "bench"))] // TODO: remove once BaseCore is used in production code.
     pub(crate) fn new(
         parent_span: Span,
         id: Identifier,
         mem_vec: MembershipVector,
+        address: Address,
         lt: Box<dyn LookupTable>,
     ) -> Self {
         let span = tracing::span!(parent: &parent_span, tracing::Level::TRACE, "base_core", id = ?id, mem_vec = ?mem_vec);
         BaseCore {
             id,
             mem_vec,
+            address,
             lt,
+            poison_policy: PoisonPolicy::default(),
             span,
         }
     }
+
+    /// Sets how searches react to a poisoned lookup table; defaults to `PoisonPolicy::FailFast`.
+    #[cfg(test)] // TODO: remove once BaseCore is used in production code.
+    pub(crate) fn with_poison_policy(mut self, poison_policy: PoisonPolicy) -> Self {
+        self.poison_policy = poison_policy;
+        self
+    }
 }
 
 impl Clone for BaseCore {
@@ -80,7 +186,9 @@ impl Clone for BaseCore {
         BaseCore {
             id: self.id,
             mem_vec: self.mem_vec,
+            address: self.address,
             lt: self.lt.clone(),
+            poison_policy: self.poison_policy,
             span: self.span.clone(),
         }
     }
@@ -95,79 +203,137 @@ impl Core for BaseCore {
         self.mem_vec
     }
 
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn lookup_table(&self) -> Box<dyn LookupTable> {
+        self.lt.clone()
+    }
+
     fn search_by_id(&self, req: IdSearchReq) -> anyhow::Result<IdSearchRes> {
         let span = tracing::trace_span!(
             parent: &self.span,
             "search_by_id_req",
             target = ?req.target,
             dir = ?req.direction,
-            level = ?req.level
+            level = ?req.level,
+            trace_id = req.trace_id
         );
         let _enter = span.enter();
 
-        // Collect neighbors from levels <= req.level in req.direction
-        let candidates: Result<Vec<_>, _> = (0..=req.level)
-            .filter_map(|lvl| match self.lt.get_entry(lvl, req.direction) {
-                Ok(Some(identity)) => Some(Ok((identity.id(), lvl))),
-                Ok(None) => None,
-                Err(e) => Some(Err(anyhow!(
-                    "error while searching by id in level {}: {}",
-                    lvl,
-                    e
-                ))),
-            })
-            .collect();
+        // Collect neighbors from levels min_level..=level in req.direction. Levels are scanned in
+        // ascending order, so the first entry matching the target exactly is the lowest-level
+        // exact match; no other candidate can beat it, so the scan stops there.
+        let mut candidates = Vec::new();
+        for lvl in req.min_level..=req.level {
+            let entry = match self.lt.get_entry(lvl, req.direction) {
+                Ok(entry) => entry,
+                Err(e) => match (e.downcast_ref::<LookupTableError>(), self.poison_policy) {
+                    (Some(LookupTableError::Poisoned { recovered }), PoisonPolicy::Recover) => {
+                        tracing::warn!(
+                            "lookup table poisoned at level {}, recovering last written entry",
+                            lvl
+                        );
+                        *recovered
+                    }
+                    // keeps the typed error in the chain so callers can tell poisoning apart.
+                    (Some(LookupTableError::Poisoned { .. }), PoisonPolicy::FailFast) => {
+                        return Err(e.context(format!(
+                            "lookup table poisoned while searching by id in level {lvl}"
+                        )));
+                    }
+                    _ => {
+                        return Err(anyhow!(
+                            "error while searching by id in level {}: {}",
+                            lvl,
+                            e
+                        ))
+                    }
+                },
+            };
+            let Some(identity) = entry else {
+                continue;
+            };
 
-        let candidates = candidates?;
+            if identity.id() == req.target && req.inclusive {
+                tracing::trace!(
+                    "search successful: found exact match {:?} at level {}",
+                    identity.id(),
+                    lvl
+                );
+                return IdSearchRes::try_new(&req, lvl, identity, None)
+                    .context("internal error: search by id produced a corrupt result");
+            }
+            candidates.push((identity, lvl));
+        }
 
+        let candidate_count = candidates.len();
         tracing::trace!(
-            "found {} candidates across levels 0-{}",
-            candidates.len(),
+            "found {} candidates across levels {}-{}",
+            candidate_count,
+            req.min_level,
             req.level
         );
 
-        // Filter candidates based on the direction
+        // Filter candidates based on the direction; an exclusive search also drops the target itself
         let result = match req.direction {
             Direction::Left => {
-                // smallest identifier that is >= target
+                // smallest identifier that is >= target, or > target if exclusive
                 candidates
                     .into_iter()
-                    .filter(|(id, _)| id >= &req.target)
-                    .min_by_key(|(id, _)| *id)
+                    .filter(|(identity, _)| {
+                        identity.id() > req.target || (req.inclusive && identity.id() == req.target)
+                    })
+                    // on ties the highest level wins, as `max_by_key` picks for the right
+                    // direction, so that the next hop does not descend below the entry's level.
+                    .min_by_key(|(identity, level)| (identity.id(), std::cmp::Reverse(*level)))
             }
             Direction::Right => {
-                // greatest identifier that is <= target
+                // greatest identifier that is <= target, or < target if exclusive
                 candidates
                     .into_iter()
-                    .filter(|(id, _)| id <= &req.target)
-                    .max_by_key(|(id, _)| *id)
+                    .filter(|(identity, _)| {
+                        identity.id() < req.target || (req.inclusive && identity.id() == req.target)
+                    })
+                    .max_by_key(|(identity, _)| identity.id())
             }
         };
 
         match result {
-            Some((id, level)) => {
-                let search_result = IdSearchRes {
-                    nonce: req.nonce,
-                    target: req.target,
-                    termination_level: level,
-                    result: id,
-                };
-                tracing::trace!("search successful: found match {:?} at level {}", id, level);
+            Some((identity, level)) => {
+                let search_result = IdSearchRes::try_new(&req, level, identity, None)
+                    .context("internal error: search by id produced a corrupt result")?;
+                tracing::trace!(
+                    "search successful: found match {:?} at level {}",
+                    identity.id(),
+                    level
+                );
                 Ok(search_result)
             }
             None => {
                 // No valid neighbors at any level: Aspnes & Shah fallback —
-                // return caller's own identifier at level 0.
-                tracing::trace!(
-                    "search fallback: no valid candidates found, returning own identifier {:?}",
-                    self.id
-                );
-                Ok(IdSearchRes {
-                    nonce: req.nonce,
-                    target: req.target,
-                    termination_level: 0,
-                    result: self.id,
-                })
+                // return caller's own identifier at level 0. An empty table in this direction and
+                // neighbors that are all on the wrong side of the target are logged apart, as they
+                // point at different topology issues.
+                if candidate_count == 0 {
+                    tracing::trace!(
+                        "search fallback: table empty at direction {:?} across levels {}-{}, returning own identifier {:?}",
+                        req.direction,
+                        req.min_level,
+                        req.level,
+                        self.id
+                    );
+                } else {
+                    tracing::trace!(
+                        "search fallback: no in-range candidate among {} neighbors at direction {:?}, returning own identifier {:?}",
+                        candidate_count,
+                        req.direction,
+                        self.id
+                    );
+                }
+                IdSearchRes::try_new(&req, 0, self.identity(), Some(req.direction))
+                    .context("internal error: search by id produced a corrupt result")
             }
         }
     }
@@ -1,21 +1,149 @@
 use crate::core::model::direction::Direction;
 use crate::core::model::identity::Identity;
-use crate::core::model::search::Nonce;
+use crate::core::testutil::capture::capture_events;
 use crate::core::testutil::fixtures::{
     join_all_with_timeout, random_address, random_identifier, random_identifier_greater_than,
-    random_identifier_less_than, random_lookup_table_with_extremes, random_membership_vector,
-    span_fixture,
+    random_identifier_less_than, random_identity, random_lookup_table_with_extremes,
+    random_membership_vector, span_fixture, spawn_in_span,
 };
 use crate::core::{
-    ArrayLookupTable, IdSearchReq, Identifier, LookupTable, LookupTableLevel, LOOKUP_TABLE_LEVELS,
+    ArrayLookupTable, IdSearchReq, Identifier, LookupDiff, LookupTable, LookupTableError,
+    LookupTableLevel, LOOKUP_TABLE_LEVELS,
 };
-use crate::node::core::{BaseCore, Core};
+use crate::node::core::{BaseCore, Core, PoisonPolicy};
 use anyhow::anyhow;
 use rand::Rng;
 use std::sync::Arc;
 
 fn make_core(id: Identifier, lt: Box<dyn LookupTable>) -> BaseCore {
-    BaseCore::new(span_fixture(), id, random_membership_vector(), lt)
+    BaseCore::new(
+        span_fixture(),
+        id,
+        random_membership_vector(),
+        random_address(),
+        lt,
+    )
+}
+
+/// Hook run by `HookedLookupTable` on every `get_entry`, given the level, the direction, and the
+/// entry the wrapped table holds there; what it returns is returned to the caller.
+type GetEntryHook = Arc<
+    dyn Fn(LookupTableLevel, Direction, Option<Identity>) -> anyhow::Result<Option<Identity>>
+        + Send
+        + Sync,
+>;
+
+/// Lookup table that delegates to an `ArrayLookupTable`, except that reads through `get_entry`
+/// pass through a hook, e.g., to record them or to fail them. Searches only read entries through
+/// `get_entry`, so this is the one method tests of `search_by_id` need to intercept.
+#[derive(Clone)]
+struct HookedLookupTable {
+    inner: ArrayLookupTable,
+    hook: GetEntryHook,
+}
+
+impl HookedLookupTable {
+    fn new(
+        inner: ArrayLookupTable,
+        hook: impl Fn(LookupTableLevel, Direction, Option<Identity>) -> anyhow::Result<Option<Identity>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        HookedLookupTable {
+            inner,
+            hook: Arc::new(hook),
+        }
+    }
+}
+
+impl LookupTable for HookedLookupTable {
+    fn update_entry(
+        &self,
+        identity: Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        self.inner.update_entry(identity, level, direction)
+    }
+
+    fn remove_entry(&self, level: LookupTableLevel, direction: Direction) -> anyhow::Result<()> {
+        self.inner.remove_entry(level, direction)
+    }
+
+    fn get_entry(
+        &self,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<Option<Identity>> {
+        (self.hook)(level, direction, self.inner.get_entry(level, direction)?)
+    }
+
+    fn equal(&self, other: &dyn LookupTable) -> bool {
+        self.inner.equal(other)
+    }
+
+    fn left_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
+        self.inner.left_neighbors()
+    }
+
+    fn right_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
+        self.inner.right_neighbors()
+    }
+
+    fn count_by_direction(&self, direction: Direction) -> anyhow::Result<usize> {
+        self.inner.count_by_direction(direction)
+    }
+
+    fn find_entry_by_id(&self, id: &Identifier) -> anyhow::Result<Vec<(usize, Direction)>> {
+        self.inner.find_entry_by_id(id)
+    }
+
+    fn swap_directions(&self) -> anyhow::Result<()> {
+        self.inner.swap_directions()
+    }
+
+    fn successor_predecessor(&self) -> anyhow::Result<(Option<Identity>, Option<Identity>)> {
+        self.inner.successor_predecessor()
+    }
+
+    fn replace_if_closer(
+        &self,
+        owner: Identifier,
+        identity: Identity,
+        level: LookupTableLevel,
+        direction: Direction,
+    ) -> anyhow::Result<bool> {
+        self.inner
+            .replace_if_closer(owner, identity, level, direction)
+    }
+
+    fn clear(&self) -> anyhow::Result<()> {
+        self.inner.clear()
+    }
+
+    fn remove_matching(
+        &self,
+        id: &Identifier,
+    ) -> anyhow::Result<Vec<(LookupTableLevel, Direction)>> {
+        self.inner.remove_matching(id)
+    }
+
+    fn diff(&self, other: &dyn LookupTable) -> anyhow::Result<Vec<LookupDiff>> {
+        self.inner.diff(other)
+    }
+
+    fn apply_diff(&self, diff: &[LookupDiff]) -> anyhow::Result<()> {
+        self.inner.apply_diff(diff)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn LookupTable> {
+        Box::new(self.clone())
+    }
 }
 
 /// Verifies `search_by_id` returns the core's own identifier when the lookup
@@ -33,19 +161,51 @@ fn test_search_by_id_singleton_fallback() {
     ];
 
     for (target, direction) in cases {
-        let req = IdSearchReq {
-            nonce: Nonce::random(),
-            origin: origin_id,
-            target,
-            level: 3,
-            direction,
-        };
+        let req = IdSearchReq::try_new(origin_id, target, 3, direction).unwrap();
         let res = core.search_by_id(req).expect("search failed");
         assert_eq!(res.termination_level, 0);
         assert_eq!(res.result, origin_id);
+        assert_eq!(res.fallback_direction, Some(direction));
     }
 }
 
+/// Verifies that a search result carries the full identity of the neighbor it selected, with
+/// the address stored in the lookup table, and the core's own identity on fallback.
+#[test]
+fn test_search_by_id_returns_matched_identity() {
+    let lt = ArrayLookupTable::new();
+    let core = make_core(random_identifier(), Box::new(lt.clone()));
+    let neighbor = Identity::new(
+        random_identifier_less_than(&core.id()),
+        random_membership_vector(),
+        random_address(),
+    );
+    lt.update_entry(neighbor, 2, Direction::Right)
+        .expect("failed to update entry in lookup table");
+
+    let req = IdSearchReq::builder(core.id())
+        .target(core.id())
+        .direction(Direction::Right)
+        .build()
+        .unwrap();
+    let res = core.search_by_id(req).unwrap();
+    assert_eq!(res.result, neighbor.id());
+    let matched = res
+        .matched_identity
+        .expect("result should carry the matched identity");
+    assert_eq!(matched.address(), neighbor.address());
+    assert_eq!(matched, neighbor);
+
+    // nothing lies to the left, so the search falls back to the core itself.
+    let res = core
+        .search_by_id(IdSearchReq {
+            direction: Direction::Left,
+            ..req
+        })
+        .unwrap();
+    assert_eq!(res.matched_identity, Some(core.identity()));
+}
+
 /// Verifies left-direction search returns the smallest neighbor with identifier >= target.
 #[test]
 fn test_search_by_id_found_left_direction() {
@@ -62,13 +222,7 @@ fn test_search_by_id_found_left_direction() {
         .expect("failed to update entry in lookup table");
 
         let core = make_core(random_identifier(), Box::new(lt.clone()));
-        let req = IdSearchReq {
-            nonce: Nonce::random(),
-            origin: core.id(),
-            target,
-            level: lvl,
-            direction: Direction::Left,
-        };
+        let req = IdSearchReq::try_new(core.id(), target, lvl, Direction::Left).unwrap();
         let actual = core.search_by_id(req).unwrap();
 
         let (expected_lvl, expected_identity) = lt
@@ -100,13 +254,7 @@ fn test_search_by_id_found_right_direction() {
         .expect("failed to update entry in lookup table");
 
         let core = make_core(random_identifier(), Box::new(lt.clone()));
-        let req = IdSearchReq {
-            nonce: Nonce::random(),
-            origin: core.id(),
-            target,
-            level: lvl,
-            direction: Direction::Right,
-        };
+        let req = IdSearchReq::try_new(core.id(), target, lvl, Direction::Right).unwrap();
         let actual = core.search_by_id(req).unwrap();
 
         let (expected_lvl, expected_identity) = lt
@@ -144,13 +292,7 @@ fn test_search_by_id_not_found_left_direction() {
         }
 
         let core = make_core(random_identifier(), Box::new(lt.clone()));
-        let req = IdSearchReq {
-            nonce: Nonce::random(),
-            origin: core.id(),
-            target,
-            level: lvl,
-            direction: Direction::Left,
-        };
+        let req = IdSearchReq::try_new(core.id(), target, lvl, Direction::Left).unwrap();
         let actual = core.search_by_id(req).unwrap();
 
         assert_eq!(actual.termination_level, 0);
@@ -180,13 +322,7 @@ fn test_search_by_id_not_found_right_direction() {
         }
 
         let core = make_core(random_identifier(), Box::new(lt.clone()));
-        let req = IdSearchReq {
-            nonce: Nonce::random(),
-            origin: core.id(),
-            target,
-            level: lvl,
-            direction: Direction::Right,
-        };
+        let req = IdSearchReq::try_new(core.id(), target, lvl, Direction::Right).unwrap();
         let actual = core.search_by_id(req).unwrap();
 
         assert_eq!(actual.termination_level, 0);
@@ -205,13 +341,7 @@ fn test_search_by_id_exact_result() {
         for direction in [Direction::Left, Direction::Right] {
             let target_identity = lt.get_entry(lvl, direction).unwrap().unwrap();
             let target = target_identity.id();
-            let req = IdSearchReq {
-                nonce: Nonce::random(),
-                origin: core.id(),
-                target,
-                level: lvl,
-                direction,
-            };
+            let req = IdSearchReq::try_new(core.id(), target, lvl, direction).unwrap();
             let actual = core.search_by_id(req).unwrap();
 
             assert_eq!(actual.termination_level, lvl);
@@ -220,6 +350,74 @@ fn test_search_by_id_exact_result() {
     }
 }
 
+/// Verifies that an inclusive search settles on an entry equal to the target,
+/// while an exclusive search skips it for the next entry strictly beyond it,
+/// falling back to the core's own identifier when there is none.
+#[test]
+fn test_search_by_id_inclusive_vs_exclusive() {
+    let id = |b: u8| Identifier::from_bytes(&[b]).unwrap();
+    let lt = ArrayLookupTable::new();
+    for (b, lvl, direction) in [
+        (60, 0, Direction::Left),
+        (70, 1, Direction::Left),
+        (40, 0, Direction::Right),
+        (30, 1, Direction::Right),
+    ] {
+        let identity = Identity::new(id(b), random_membership_vector(), random_address());
+        lt.update_entry(identity, lvl, direction).unwrap();
+    }
+    let core = make_core(id(50), Box::new(lt));
+
+    let cases = [
+        (60, Direction::Left, true, 60),
+        (60, Direction::Left, false, 70),
+        (40, Direction::Right, true, 40),
+        (40, Direction::Right, false, 30),
+        (70, Direction::Left, false, 50),
+        (30, Direction::Right, false, 50),
+    ];
+    for (target, direction, inclusive, expected) in cases {
+        let req = IdSearchReq::builder(core.id())
+            .target(id(target))
+            .direction(direction)
+            .inclusive(inclusive)
+            .build()
+            .unwrap();
+        let actual = core.search_by_id(req).unwrap();
+        assert_eq!(
+            actual.result,
+            id(expected),
+            "target {target} in direction {direction}, inclusive {inclusive}"
+        );
+    }
+}
+
+/// Verifies that when the best candidate is held at several levels, both directions settle on
+/// its highest level, so the next hop does not descend below it.
+#[test]
+fn test_search_by_id_level_ties_pick_highest_level() {
+    let id = |b: u8| Identifier::from_bytes(&[b]).unwrap();
+    let lt = ArrayLookupTable::new();
+    let left = Identity::new(id(60), random_membership_vector(), random_address());
+    let right = Identity::new(id(40), random_membership_vector(), random_address());
+    for lvl in [0, 2] {
+        lt.update_entry(left, lvl, Direction::Left).unwrap();
+        lt.update_entry(right, lvl, Direction::Right).unwrap();
+    }
+    let core = make_core(id(50), Box::new(lt));
+
+    for (target, direction, expected) in [(55, Direction::Left, 60), (45, Direction::Right, 40)] {
+        let req = IdSearchReq::builder(core.id())
+            .target(id(target))
+            .direction(direction)
+            .build()
+            .unwrap();
+        let res = core.search_by_id(req).unwrap();
+        assert_eq!(res.result, id(expected), "direction {direction}");
+        assert_eq!(res.termination_level, 2, "direction {direction}");
+    }
+}
+
 /// Verifies left-direction `search_by_id` returns correct results under
 /// concurrent access from 20 threads.
 #[test]
@@ -233,20 +431,15 @@ fn test_search_by_id_concurrent_found_left_direction() {
     let num_threads = 20;
     let barrier = Arc::new(std::sync::Barrier::new(num_threads + 1));
     let mut handles: Vec<std::thread::JoinHandle<()>> = Vec::new();
-    for _ in 0..num_threads {
+    for thread in 0..num_threads {
         let handle_barrier = barrier.clone();
         let core_ref = core.clone();
         let lt_clone = lt.clone();
-        let handle = std::thread::spawn(move || {
+        let span = tracing::trace_span!(parent: &span_fixture(), "search_thread", thread);
+        let handle = spawn_in_span(&span, move || {
             handle_barrier.wait();
             let lvl = rand::rng().random_range(0..LOOKUP_TABLE_LEVELS);
-            let req = IdSearchReq {
-                nonce: Nonce::random(),
-                origin: core_ref.id(),
-                target,
-                level: lvl,
-                direction: Direction::Left,
-            };
+            let req = IdSearchReq::try_new(core_ref.id(), target, lvl, Direction::Left).unwrap();
             let actual = core_ref.search_by_id(req).unwrap();
 
             let expected = lt_clone
@@ -288,20 +481,15 @@ fn test_search_by_id_concurrent_right_direction() {
     let num_threads = 20;
     let barrier = Arc::new(std::sync::Barrier::new(num_threads + 1));
     let mut handles: Vec<std::thread::JoinHandle<()>> = Vec::new();
-    for _ in 0..num_threads {
+    for thread in 0..num_threads {
         let handle_barrier = barrier.clone();
         let core_ref = core.clone();
         let lt_clone = lt.clone();
-        let handle = std::thread::spawn(move || {
+        let span = tracing::trace_span!(parent: &span_fixture(), "search_thread", thread);
+        let handle = spawn_in_span(&span, move || {
             handle_barrier.wait();
             let lvl = rand::rng().random_range(0..LOOKUP_TABLE_LEVELS);
-            let req = IdSearchReq {
-                nonce: Nonce::random(),
-                origin: core_ref.id(),
-                target,
-                level: lvl,
-                direction: Direction::Right,
-            };
+            let req = IdSearchReq::try_new(core_ref.id(), target, lvl, Direction::Right).unwrap();
             let actual = core_ref.search_by_id(req).unwrap();
 
             let expected = lt_clone
@@ -333,71 +521,290 @@ fn test_search_by_id_concurrent_right_direction() {
 /// table.
 #[test]
 fn test_search_by_id_error_propagation() {
-    struct MockErrorLookupTable;
-
-    impl Clone for MockErrorLookupTable {
-        fn clone(&self) -> Self {
-            MockErrorLookupTable
-        }
-    }
+    let lt = HookedLookupTable::new(ArrayLookupTable::new(), |_, _, _| {
+        Err(anyhow!("simulated lookup table error"))
+    });
+    let core = make_core(random_identifier(), Box::new(lt));
+    let req = IdSearchReq::try_new(core.id(), random_identifier(), 3, Direction::Left).unwrap();
+    let result = core.search_by_id(req);
 
-    impl LookupTable for MockErrorLookupTable {
-        fn update_entry(
-            &self,
-            _identity: Identity,
-            _level: usize,
-            _direction: Direction,
-        ) -> anyhow::Result<()> {
-            Ok(())
-        }
+    assert!(
+        result.is_err(),
+        "expected an error but got a success result"
+    );
 
-        fn remove_entry(&self, _: LookupTableLevel, _: Direction) -> anyhow::Result<()> {
-            todo!()
-        }
+    let error_msg = result.unwrap_err().to_string();
+    assert!(
+        error_msg.contains("error while searching by id in level"),
+        "error message '{error_msg}' doesn't contain expected text"
+    );
+    assert!(
+        error_msg.contains("simulated lookup table error"),
+        "error message '{error_msg}' doesn't contain expected text"
+    );
+}
 
-        fn get_entry(&self, _: usize, _: Direction) -> anyhow::Result<Option<Identity>> {
-            Err(anyhow!("simulated lookup table error"))
-        }
+/// Verifies `search_by_id` stops scanning as soon as it finds the exact
+/// target: with the target at level 1 and non-matching entries at every
+/// higher level, the search terminates at level 1 and only levels 0 and 1
+/// are read from the lookup table.
+#[test]
+fn test_search_by_id_exact_match_early_exit() {
+    let target = random_identifier();
+    let lt = ArrayLookupTable::new();
+    // level 0 holds a valid but non-exact candidate, level 1 the exact target.
+    lt.update_entry(
+        Identity::new(
+            random_identifier_less_than(&target),
+            random_membership_vector(),
+            random_address(),
+        ),
+        0,
+        Direction::Right,
+    )
+    .unwrap();
+    lt.update_entry(
+        Identity::new(target, random_membership_vector(), random_address()),
+        1,
+        Direction::Right,
+    )
+    .unwrap();
+    // garbage at every higher level.
+    for lvl in 2..LOOKUP_TABLE_LEVELS {
+        lt.update_entry(random_identity(), lvl, Direction::Right)
+            .unwrap();
+    }
 
-        fn equal(&self, _: &dyn LookupTable) -> bool {
-            todo!()
-        }
+    let reads = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let recorded = reads.clone();
+    let lt = HookedLookupTable::new(lt, move |level, _, entry| {
+        recorded.lock().push(level);
+        Ok(entry)
+    });
+    let core = make_core(random_identifier(), Box::new(lt));
+    let req = IdSearchReq::builder(core.id())
+        .target(target)
+        .direction(Direction::Right)
+        .build()
+        .unwrap();
+    let actual = core.search_by_id(req).unwrap();
+
+    assert_eq!(actual.result, target);
+    assert_eq!(actual.termination_level, 1);
+    assert_eq!(
+        *reads.lock(),
+        vec![0, 1],
+        "search scanned past the exact match"
+    );
+}
 
-        fn left_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
-            Ok(Vec::new())
+/// Verifies `search_by_id` distinguishes a poisoned lookup table from other
+/// failures: under `FailFast` the search errors with a `LookupTableError`
+/// that callers can downcast, and under `Recover` it uses the last entry
+/// written before the poisoning.
+#[test]
+fn test_search_by_id_poisoned_lookup_table() {
+    let target = random_identifier();
+    let lt = ArrayLookupTable::new();
+    // the only candidate sits behind the poisoned level.
+    lt.update_entry(
+        Identity::new(
+            random_identifier_less_than(&target),
+            random_membership_vector(),
+            random_address(),
+        ),
+        2,
+        Direction::Right,
+    )
+    .unwrap();
+    let expected = lt.get_entry(2, Direction::Right).unwrap().unwrap().id();
+    let recovered = lt.get_entry(2, Direction::Right).unwrap();
+    let poisoned = HookedLookupTable::new(lt, |level, _, entry| {
+        if level == 2 {
+            return Err(LookupTableError::Poisoned { recovered: entry }.into());
         }
+        Ok(entry)
+    });
+    let req = IdSearchReq::try_new(random_identifier(), target, 5, Direction::Right).unwrap();
+
+    // fail fast is the default policy.
+    let core = make_core(random_identifier(), poisoned.clone_box());
+    let err = core
+        .search_by_id(req)
+        .expect_err("poisoned lookup table should fail the search");
+    assert_eq!(
+        err.downcast_ref::<LookupTableError>(),
+        Some(&LookupTableError::Poisoned { recovered })
+    );
 
-        fn right_neighbors(&self) -> anyhow::Result<Vec<(usize, Identity)>> {
-            Ok(Vec::new())
-        }
+    let core = make_core(random_identifier(), poisoned.clone_box())
+        .with_poison_policy(PoisonPolicy::Recover);
+    let res = core
+        .search_by_id(req)
+        .expect("search should recover from a poisoned lookup table");
+    assert_eq!(res.result, expected);
+    assert_eq!(res.termination_level, 2);
+}
 
-        fn clone_box(&self) -> Box<dyn LookupTable> {
-            Box::new(self.clone())
-        }
+/// Verifies that a search with a minimum level ignores the entries below it: with the target
+/// itself at level 0 and a closer candidate at level 1, a search from level 2 settles on the
+/// level-2 entry, while a search from level 0 finds the target.
+#[test]
+fn test_search_by_id_respects_min_level() {
+    let lt = ArrayLookupTable::new();
+    let core = make_core(
+        Identifier::from_bytes(&[100u8]).unwrap(),
+        Box::new(lt.clone()),
+    );
+    let target = Identifier::from_bytes(&[50u8]).unwrap();
+    for (id, level) in [(50u8, 0), (45u8, 1), (30u8, 2)] {
+        lt.update_entry(
+            Identity::new(
+                Identifier::from_bytes(&[id]).unwrap(),
+                random_membership_vector(),
+                random_address(),
+            ),
+            level,
+            Direction::Right,
+        )
+        .expect("failed to update entry in lookup table");
     }
 
-    let core = make_core(random_identifier(), Box::new(MockErrorLookupTable));
-    let req = IdSearchReq {
-        nonce: Nonce::random(),
-        origin: core.id(),
-        target: random_identifier(),
-        level: 3,
-        direction: Direction::Left,
+    let req = IdSearchReq::builder(core.id())
+        .target(target)
+        .direction(Direction::Right)
+        .level(3)
+        .min_level(2)
+        .build()
+        .unwrap();
+    let res = core.search_by_id(req).unwrap();
+    assert_eq!(res.result, Identifier::from_bytes(&[30u8]).unwrap());
+    assert_eq!(res.termination_level, 2);
+
+    let res = core
+        .search_by_id(IdSearchReq {
+            min_level: 0,
+            ..req
+        })
+        .unwrap();
+    assert_eq!(res.result, target);
+    assert_eq!(res.termination_level, 0);
+}
+
+/// Verifies that a fallback caused by an empty lookup table and one caused by neighbors that are
+/// all out of range of the target are logged with distinct messages.
+#[test]
+fn test_search_by_id_fallback_logs_empty_table_apart_from_out_of_range() {
+    let target = random_identifier();
+    let fallback_messages = |lt: ArrayLookupTable| {
+        let core = make_core(random_identifier(), Box::new(lt));
+        let req = IdSearchReq::try_new(core.id(), target, 0, Direction::Left).unwrap();
+        let (res, events) = capture_events(|| core.search_by_id(req).unwrap());
+        assert_eq!(res.result, core.id());
+        events
+            .into_iter()
+            .filter_map(|event| event.fields.get("message").cloned())
+            .filter(|message| message.starts_with("search fallback"))
+            .collect::<Vec<_>>()
     };
-    let result = core.search_by_id(req);
 
+    let empty = fallback_messages(ArrayLookupTable::new());
+    assert_eq!(empty.len(), 1);
+    assert!(empty[0].contains("table empty at direction"), "{empty:?}");
+
+    let out_of_range = ArrayLookupTable::new();
+    out_of_range
+        .update_entry(
+            Identity::new(
+                random_identifier_less_than(&target),
+                random_membership_vector(),
+                random_address(),
+            ),
+            0,
+            Direction::Left,
+        )
+        .unwrap();
+    let out_of_range = fallback_messages(out_of_range);
+    assert_eq!(out_of_range.len(), 1);
     assert!(
-        result.is_err(),
-        "expected an error but got a success result"
+        out_of_range[0].contains("no in-range candidate"),
+        "{out_of_range:?}"
     );
+}
 
-    let error_msg = result.unwrap_err().to_string();
-    assert!(
-        error_msg.contains("error while searching by id in level"),
-        "error message '{error_msg}' doesn't contain expected text"
+/// Verifies that `search_and_learn` installs a discovered neighbor lying closer than the current
+/// entry, which the search then returns, and leaves the table alone for a farther one.
+#[test]
+fn test_search_and_learn_inserts_closer_neighbor() {
+    let origin_id = Identifier::from_bytes(&[200u8]).unwrap();
+    let lt = ArrayLookupTable::new();
+    let far = Identity::new(
+        Identifier::from_bytes(&[150u8]).unwrap(),
+        random_membership_vector(),
+        random_address(),
     );
-    assert!(
-        error_msg.contains("simulated lookup table error"),
-        "error message '{error_msg}' doesn't contain expected text"
+    lt.update_entry(far, 0, Direction::Left).unwrap();
+    let core = make_core(origin_id, Box::new(lt.clone()));
+
+    let farther = Identity::new(
+        Identifier::from_bytes(&[120u8]).unwrap(),
+        random_membership_vector(),
+        random_address(),
+    );
+    let req = IdSearchReq::try_new(origin_id, farther.id(), 0, Direction::Left).unwrap();
+    let res = core.search_and_learn(&req, &[farther]).unwrap();
+    assert_eq!(res.result, far.id());
+    assert_eq!(lt.get_entry(0, Direction::Left).unwrap(), Some(far));
+
+    let closer = Identity::new(
+        Identifier::from_bytes(&[180u8]).unwrap(),
+        random_membership_vector(),
+        random_address(),
     );
+    let req = IdSearchReq::try_new(origin_id, closer.id(), 0, Direction::Left).unwrap();
+    // without learning, no neighbor lies at or above the target, so the search falls back.
+    assert_eq!(core.search_by_id(req).unwrap().result, origin_id);
+
+    let res = core
+        .search_and_learn(&req, &[closer, core.identity()])
+        .unwrap();
+    assert_eq!(res.result, closer.id());
+    assert_eq!(res.matched_identity, Some(closer));
+    assert_eq!(lt.get_entry(0, Direction::Left).unwrap(), Some(closer));
+}
+
+/// Verifies that `search_by_range` lists a neighbor held at levels 0 and 2 once, at level 0, and
+/// only lists neighbors within the range, sorted by identifier.
+#[test]
+fn test_search_by_range_deduplicates_across_levels() {
+    let origin_id = Identifier::from_bytes(&[100u8]).unwrap();
+    let identity = |byte: u8| {
+        Identity::new(
+            Identifier::from_bytes(&[byte]).unwrap(),
+            random_membership_vector(),
+            random_address(),
+        )
+    };
+    let (repeated, other, outside) = (identity(150), identity(80), identity(50));
+    let lt = ArrayLookupTable::new();
+    lt.update_entry(repeated, 0, Direction::Right).unwrap();
+    lt.update_entry(repeated, 2, Direction::Right).unwrap();
+    lt.update_entry(other, 0, Direction::Left).unwrap();
+    lt.update_entry(outside, 1, Direction::Left).unwrap();
+    let core = make_core(origin_id, Box::new(lt));
+
+    let found = core
+        .search_by_range(
+            Identifier::from_bytes(&[60u8]).unwrap(),
+            Identifier::from_bytes(&[200u8]).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(found, vec![(0, other), (0, repeated)]);
+
+    assert!(core
+        .search_by_range(
+            Identifier::from_bytes(&[200u8]).unwrap(),
+            Identifier::from_bytes(&[60u8]).unwrap(),
+        )
+        .is_err());
 }
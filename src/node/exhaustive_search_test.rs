@@ -0,0 +1,62 @@
+//! Exhaustive correctness check of the full search path over a small identifier space.
+//!
+//! This lives in-crate rather than under `tests/` because `BaseNode`, the mock network, and the
+//! overlay harness are crate-private and test-only; an integration test cannot reach them.
+//! It depends on a correctly wired overlay from `build_overlay_with` and on the multi-hop
+//! `BaseNode::search_by_id`; a failure here means one of the two is broken.
+
+use crate::core::model::direction::Direction;
+use crate::core::testutil::fixtures::join_with_timeout;
+use crate::core::{IdSearchReq, Identifier};
+use crate::network::mock::hub::NetworkHub;
+use crate::network::Network;
+use crate::node::overlay::build_overlay_with;
+use crate::node::NodeView;
+
+/// Number of nodes in the overlay; node `i` has identifier `i`.
+const SPACE_SIZE: u8 = 16;
+
+/// Builds a node for every identifier in `0..16` and asserts that searching from every node for
+/// every identifier terminates at the node owning that identifier.
+#[test]
+fn test_exhaustive_search_small_space() {
+    let ids: Vec<Identifier> = (0..SPACE_SIZE)
+        .map(|i| Identifier::from_bytes(&[i]).unwrap())
+        .collect();
+    let hub = NetworkHub::new();
+    let nodes = build_overlay_with(ids.clone(), move |id| {
+        NetworkHub::new_mock_network(hub.clone(), id)
+            .expect("failed to create mock network")
+            .clone_box()
+    })
+    .expect("failed to build overlay");
+
+    let handle = std::thread::spawn(move || {
+        for origin in nodes.iter() {
+            for target in ids.iter() {
+                // a search for the origin itself terminates locally in either direction.
+                let direction = if *target < origin.id() {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                };
+                let req = IdSearchReq::builder(origin.id())
+                    .target(*target)
+                    .direction(direction)
+                    .build()
+                    .unwrap();
+                let res = origin.search_by_id(req).expect("failed to search by id");
+                assert_eq!(
+                    res.result,
+                    *target,
+                    "search from {} for {} ended at the wrong node",
+                    origin.id(),
+                    target
+                );
+            }
+        }
+    });
+
+    join_with_timeout(handle, std::time::Duration::from_secs(30))
+        .expect("exhaustive search did not complete within timeout (likely deadlocked)");
+}
@@ -0,0 +1,389 @@
+use super::base_node::BaseNode;
+use crate::core::model::direction::{Direction, DIRECTIONS};
+use crate::core::model::identity::Identity;
+use crate::core::testutil::fixtures::{
+    new_core, new_node, random_address, random_identifier, random_membership_vector,
+    random_sorted_identifiers, span_fixture,
+};
+use crate::core::{ArrayLookupTable, Identifier, LOOKUP_TABLE_LEVELS};
+use crate::network::mock::hub::NetworkHub;
+use crate::network::{Event, EventProcessorCore, MessageProcessor, Network};
+use crate::node::overlay::{balanced_skip_graph, build_overlay_with};
+use crate::node::{Node, NodeView};
+use anyhow::anyhow;
+use rand::seq::SliceRandom;
+
+/// Returns the identifier of the closest node to `nodes[i]` in `direction` sharing at least
+/// `level` bits of membership-vector prefix with it, if any. `nodes` must be sorted by identifier.
+fn expected_neighbor(
+    nodes: &[BaseNode],
+    i: usize,
+    level: usize,
+    direction: Direction,
+) -> Option<Identifier> {
+    let shares_prefix =
+        |j: &usize| nodes[*j].mem_vec().common_prefix_bit(nodes[i].mem_vec()) >= level;
+    let found = match direction {
+        Direction::Left => (0..i).rev().find(shares_prefix),
+        Direction::Right => (i + 1..nodes.len()).find(shares_prefix),
+    };
+    found.map(|j| nodes[j].id())
+}
+
+/// Verifies that a node is not joined until it joins, is joined afterwards, refuses to join
+/// twice, and is no longer joined, with an empty lookup table, once it leaves.
+#[test]
+fn test_is_joined_lifecycle() {
+    let (nodes, hub) = balanced_skip_graph(7).expect("failed to build balanced overlay");
+    let node = new_node(&hub, random_identifier());
+    assert!(!node.is_joined());
+
+    node.join(nodes[0].id()).expect("failed to join");
+    assert!(node.is_joined());
+    assert!(
+        node.lookup_table()
+            .get_entry(0, Direction::Left)
+            .unwrap()
+            .is_some()
+            || node
+                .lookup_table()
+                .get_entry(0, Direction::Right)
+                .unwrap()
+                .is_some(),
+        "joined node should have a level-0 neighbor"
+    );
+
+    assert!(
+        node.join(nodes[0].id()).is_err(),
+        "joining twice should fail"
+    );
+    assert!(node.is_joined());
+
+    node.leave().expect("failed to leave");
+    assert!(!node.is_joined());
+    for level in 0..LOOKUP_TABLE_LEVELS {
+        for direction in DIRECTIONS {
+            assert_eq!(
+                node.lookup_table().get_entry(level, direction).unwrap(),
+                None
+            );
+        }
+    }
+    assert!(node.leave().is_err(), "leaving twice should fail");
+}
+
+/// Verifies that a join failing part way leaves the node unjoined with an empty lookup table, and
+/// that the neighbor that already linked to it drops it again.
+#[test]
+fn test_failed_join_undoes_links() {
+    /// Forwards every event to the wrapped node, except link requests, which it rejects.
+    struct RejectLinks {
+        node: BaseNode,
+    }
+
+    impl EventProcessorCore for RejectLinks {
+        fn process_incoming_event(
+            &self,
+            origin_id: Identifier,
+            event: Event,
+        ) -> anyhow::Result<()> {
+            match event {
+                Event::LinkRequest(_) => Err(anyhow!("link rejected")),
+                event => self.node.process_incoming_event(origin_id, event),
+            }
+        }
+    }
+
+    let hub = NetworkHub::new();
+    let ids = random_sorted_identifiers(3);
+    let (left_id, id, right_id) = (ids[0], ids[1], ids[2]);
+    let left = new_node(&hub, left_id);
+    let right_net = NetworkHub::new_mock_network(hub.clone(), right_id).unwrap();
+    let right = BaseNode::new_unregistered(
+        span_fixture(),
+        new_core(
+            right_id,
+            random_membership_vector(),
+            Box::new(ArrayLookupTable::new()),
+        ),
+        right_net.clone_box(),
+    )
+    .unwrap();
+    right_net
+        .register_processor(MessageProcessor::new(Box::new(RejectLinks {
+            node: right.clone(),
+        })))
+        .unwrap();
+    left.lookup_table()
+        .update_entry(right.identity(), 0, Direction::Right)
+        .unwrap();
+    right
+        .lookup_table()
+        .update_entry(left.identity(), 0, Direction::Left)
+        .unwrap();
+
+    // the node links to `left` first, then fails to link to `right`.
+    let node = new_node(&hub, id);
+    node.join(left_id)
+        .expect_err("joining should fail once a neighbor rejects the link");
+
+    assert!(!node.is_joined());
+    assert!(node.lookup_table().left_neighbors().unwrap().is_empty());
+    assert!(node.lookup_table().right_neighbors().unwrap().is_empty());
+    assert!(
+        left.lookup_table()
+            .find_entry_by_id(&id)
+            .unwrap()
+            .is_empty(),
+        "the linked neighbor should drop the node again"
+    );
+}
+
+/// Verifies that an introducer answers a bootstrap with the new node's closest existing node on
+/// the introducer's side: its predecessor if the introducer is smaller, its successor otherwise.
+#[test]
+fn test_bootstrap_returns_closest_node() {
+    let (nodes, hub) = balanced_skip_graph(15).expect("failed to build balanced overlay");
+    let new_id = random_identifier();
+    let node = new_node(&hub, new_id);
+    let predecessor = nodes.iter().map(|n| n.id()).filter(|id| *id < new_id).max();
+    let successor = nodes.iter().map(|n| n.id()).filter(|id| *id > new_id).min();
+
+    for introducer in nodes.iter() {
+        let closest = node
+            .bootstrap(introducer.id())
+            .expect("failed to bootstrap");
+        let expected = if introducer.id() < new_id {
+            predecessor
+        } else {
+            successor
+        };
+        assert_eq!(
+            Some(closest),
+            expected,
+            "via introducer {:?}",
+            introducer.id()
+        );
+    }
+    assert!(!node.is_joined(), "bootstrapping alone does not join");
+}
+
+/// Verifies that bootstrapping does not hang when every node's deliveries are serialized, as the
+/// introducer no longer waits for the relayed search inside its handler, and that concurrent
+/// bootstraps through the same introducer are each answered with their own closest node.
+#[test]
+fn test_bootstrap_over_ordered_networks() {
+    let hub = NetworkHub::new();
+    let ordered = |id| {
+        NetworkHub::new_ordered_mock_network(hub.clone(), id)
+            .expect("failed to create ordered mock network")
+            .clone_box()
+    };
+    let nodes = build_overlay_with(random_sorted_identifiers(15), ordered)
+        .expect("failed to build overlay");
+    let introducer = nodes[nodes.len() / 2].id();
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let id = random_identifier();
+            let node = BaseNode::new(
+                span_fixture(),
+                new_core(
+                    id,
+                    random_membership_vector(),
+                    Box::new(ArrayLookupTable::new()),
+                ),
+                ordered(id),
+            )
+            .unwrap();
+            std::thread::spawn(move || (id, node.bootstrap(introducer)))
+        })
+        .collect();
+
+    for handle in handles {
+        let (id, closest) = handle.join().unwrap();
+        let closest = closest.expect("failed to bootstrap");
+        let expected = if introducer < id {
+            nodes.iter().map(|n| n.id()).filter(|n| *n < id).max()
+        } else {
+            nodes.iter().map(|n| n.id()).filter(|n| *n > id).min()
+        };
+        assert_eq!(Some(closest), expected, "bootstrapping {:?}", id);
+    }
+}
+
+/// Verifies that an introducer whose next hop towards the new node is gone answers the bootstrap
+/// with an error instead of leaving the new node waiting.
+#[test]
+fn test_bootstrap_reports_unreachable_next_hop() {
+    let hub = NetworkHub::new();
+    let ids = random_sorted_identifiers(3);
+    let (introducer_id, dead_id, new_id) = (ids[0], ids[1], ids[2]);
+    let introducer = new_node(&hub, introducer_id);
+    introducer
+        .lookup_table()
+        .update_entry(
+            Identity::new(dead_id, random_membership_vector(), random_address()),
+            0,
+            Direction::Right,
+        )
+        .unwrap();
+    let node = new_node(&hub, new_id);
+
+    let err = node
+        .bootstrap(introducer_id)
+        .expect_err("bootstrap through an unreachable next hop should fail");
+    assert!(
+        format!("{:#}", err).contains(&format!("introducer {} failed to bootstrap", introducer_id)),
+        "unexpected error: {:#}",
+        err
+    );
+}
+
+/// Verifies that nodes joining one by one, in random order and through random introducers, end
+/// up with the same lookup tables as a directly wired skip graph: at every level, each node is
+/// linked to its closest neighbor on either side sharing at least that many membership-vector
+/// prefix bits.
+#[test]
+fn test_join_links_every_level() {
+    let hub = NetworkHub::new();
+    let mut nodes: Vec<BaseNode> = random_sorted_identifiers(24)
+        .into_iter()
+        .map(|id| new_node(&hub, id))
+        .collect();
+    let mut order: Vec<usize> = (0..nodes.len()).collect();
+    order.shuffle(&mut rand::rng());
+
+    nodes[order[0]]
+        .join(nodes[order[0]].id())
+        .expect("first node failed to start the overlay");
+    for (joined, &i) in order.iter().enumerate().skip(1) {
+        let introducer = nodes[order[rand::random_range(0..joined)]].id();
+        nodes[i].join(introducer).expect("failed to join");
+    }
+
+    nodes.sort_by_key(|node| node.id());
+    for (i, node) in nodes.iter().enumerate() {
+        assert!(node.is_joined());
+        let lt = node.lookup_table();
+        for level in 0..LOOKUP_TABLE_LEVELS {
+            for direction in DIRECTIONS {
+                let actual = lt.get_entry(level, direction).unwrap().map(|e| e.id());
+                assert_eq!(
+                    actual,
+                    expected_neighbor(&nodes, i, level, direction),
+                    "wrong {direction} neighbor of node {i} at level {level}"
+                );
+            }
+        }
+    }
+}
+
+/// Verifies that a node that lost its right neighbor at level 1 recovers the same neighbor by
+/// repairing the level through the network, that a level without any matching node is left
+/// empty, and that level 0 and out-of-range levels are refused.
+#[test]
+fn test_repair_level_restores_missing_neighbor() {
+    let (nodes, _hub) = balanced_skip_graph(31).expect("failed to build balanced overlay");
+    let node = nodes
+        .iter()
+        .find(|n| {
+            n.lookup_table()
+                .get_entry(1, Direction::Right)
+                .unwrap()
+                .is_some()
+        })
+        .expect("some node should have a right neighbor at level 1");
+    let lt = node.lookup_table();
+    let expected = lt.get_entry(1, Direction::Right).unwrap();
+
+    lt.remove_entry(1, Direction::Right).unwrap();
+    node.repair_level(1, Direction::Right)
+        .expect("failed to repair level");
+    assert_eq!(lt.get_entry(1, Direction::Right).unwrap(), expected);
+
+    // no node shares every membership-vector bit with another, so the top level stays empty.
+    node.repair_level(LOOKUP_TABLE_LEVELS - 1, Direction::Right)
+        .expect("failed to repair level");
+    assert_eq!(
+        lt.get_entry(LOOKUP_TABLE_LEVELS - 1, Direction::Right)
+            .unwrap(),
+        None
+    );
+
+    assert!(node.repair_level(0, Direction::Right).is_err());
+    assert!(node
+        .repair_level(LOOKUP_TABLE_LEVELS, Direction::Right)
+        .is_err());
+}
+
+/// Verifies that a node joins via the next introducer when the first one is unreachable, and
+/// that joining fails with every introducer's failure once none of them is reachable.
+#[test]
+fn test_join_via_skips_unreachable_introducer() {
+    let (nodes, hub) = balanced_skip_graph(7).expect("failed to build balanced overlay");
+    let gone = new_node(&hub, random_identifier());
+    hub.remove_mock_network(gone.id())
+        .expect("failed to remove network");
+
+    let node = new_node(&hub, random_identifier());
+    node.join_via(&[gone.id(), nodes[3].id()])
+        .expect("failed to join via the second introducer");
+    assert!(node.is_joined());
+    assert!(node.join_via(&[nodes[3].id()]).is_err());
+
+    let stranded = new_node(&hub, random_identifier());
+    let err = stranded
+        .join_via(&[gone.id(), gone.id()])
+        .expect_err("joining via unreachable introducers should fail");
+    let failure = format!("failed to join via {}:", gone.id());
+    assert_eq!(err.to_string().matches(&failure).count(), 2);
+    assert!(!stranded.is_joined());
+    assert!(stranded.join_via(&[]).is_err());
+}
+
+/// Verifies that a node leaving a wired overlay over mock networks is dropped from the lookup
+/// table of every node that held it, while the other entries of those tables stay in place.
+#[test]
+fn test_leave_notifies_neighbors() {
+    let (nodes, _hub) = balanced_skip_graph(15).expect("failed to build balanced overlay");
+    let leaver = &nodes[7];
+    let populated = |node: &BaseNode| {
+        let lt = node.lookup_table();
+        lt.left_neighbors().unwrap().len() + lt.right_neighbors().unwrap().len()
+    };
+    // the number of entries each node should keep once the leaver is gone.
+    let expected: Vec<usize> = nodes
+        .iter()
+        .map(|node| {
+            let held = node.lookup_table().find_entry_by_id(&leaver.id()).unwrap();
+            populated(node) - held.len()
+        })
+        .collect();
+    assert!(
+        nodes
+            .iter()
+            .zip(&expected)
+            .any(|(node, expected)| populated(node) > *expected),
+        "leaver should be someone's neighbor"
+    );
+
+    leaver.leave().expect("failed to leave");
+
+    for (node, expected) in nodes.iter().zip(expected) {
+        let lt = node.lookup_table();
+        assert!(
+            lt.find_entry_by_id(&leaver.id()).unwrap().is_empty(),
+            "node {:?} still holds the leaver",
+            node.id()
+        );
+        if node.id() != leaver.id() {
+            assert_eq!(
+                populated(node),
+                expected,
+                "only the leaver's entries should go"
+            );
+        }
+    }
+    assert_eq!(populated(leaver), 0);
+}
@@ -1,8 +1,67 @@
-mod base_node;
+pub(crate) mod base_node;
 pub(crate) mod core;
 #[cfg(test)]
 mod core_test;
 #[cfg(test)]
+mod exhaustive_search_test;
+#[cfg(test)]
+mod join_test;
+#[cfg(any(test, feature = "bench"))]
+#[cfg_attr(not(test), allow(dead_code))] // only partly used by the benchmarks
+pub(crate) mod overlay;
+pub(crate) mod retry;
+#[cfg(test)]
 mod search_by_id_test;
 #[cfg(test)]
 mod skip_graph_integration_test;
+
+use crate::core::{IdSearchReq, IdSearchRes, Identifier, MembershipVector, SearchOutcome};
+use anyhow::anyhow;
+
+/// NodeView is the read-only part of a skip-graph node's interface: its identity and the searches
+/// it originates. Code that only reads from a node takes a `&dyn NodeView`, and so cannot change
+/// the node's membership in the overlay.
+// TODO: Remove #[allow(dead_code)] once NodeView is used in production code.
+#[allow(dead_code)]
+pub(crate) trait NodeView {
+    /// Returns the identifier of the node.
+    fn id(&self) -> Identifier;
+
+    /// Returns the membership vector of the node.
+    fn mem_vec(&self) -> MembershipVector;
+
+    /// Searches the overlay for the given identifier, blocking until the search terminates.
+    fn search_by_id(&self, req: IdSearchReq) -> anyhow::Result<IdSearchRes>;
+
+    /// Searches the overlay like `search_by_id`, and returns the identity of the node the search
+    /// settled on together with the number of network hops the search took to reach it.
+    fn search(&self, req: IdSearchReq) -> anyhow::Result<SearchOutcome> {
+        let res = self.search_by_id(req)?;
+        let identity = res
+            .matched_identity
+            .ok_or_else(|| anyhow!("search by id result {} carries no identity", res.result))?;
+        Ok(SearchOutcome {
+            identity,
+            hops: res.hops,
+        })
+    }
+}
+
+/// Node is the application-facing interface of a skip-graph node: the read-only `NodeView`, and
+/// its membership in the overlay.
+// TODO: Remove #[allow(dead_code)] once Node is used in production code.
+#[allow(dead_code)]
+pub(crate) trait Node: NodeView {
+    /// Joins the overlay through `introducer`, an already joined node, linking this node into the
+    /// lookup tables of its neighbors at every level. A node introduced by itself starts a new
+    /// overlay on its own. Returns an error if the node is already joined.
+    fn join(&self, introducer: Identifier) -> anyhow::Result<()>;
+
+    /// Leaves the overlay: notifies every neighbor in the node's lookup table with a `Leave`
+    /// event, so they remove the node from their tables, then clears the node's lookup table.
+    /// Returns an error if the node is not joined.
+    fn leave(&self) -> anyhow::Result<()>;
+
+    /// Returns true if the node has joined the overlay and not left it since.
+    fn is_joined(&self) -> bool;
+}
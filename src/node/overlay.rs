@@ -0,0 +1,384 @@
+use super::base_node::BaseNode;
+use crate::core::model::direction::Direction;
+use crate::core::model::identifier::{MAX, ZERO};
+use crate::core::testutil::fixtures::{random_address, random_membership_vector, span_fixture};
+use crate::core::{ArrayLookupTable, IdSearchReq, Identifier, LookupTable, LOOKUP_TABLE_LEVELS};
+use crate::network::mock::hub::NetworkHub;
+use crate::network::Network;
+use crate::node::core::BaseCore;
+use crate::node::NodeView;
+
+/// Builds a fully wired skip graph over the given identifiers, obtaining each
+/// node's transport from `net_factory`. This decouples the harness from the
+/// transport: any `Network` implementation can be plugged in, as long as the
+/// returned instances can reach one another by identifier.
+///
+/// The identifiers are sorted before construction and each node gets a random
+/// membership vector. Lookup tables are populated inline by running the
+/// insert/join algorithm (Aspnes & Shah, Algorithm 2) over the whole node set:
+/// level 0 as a doubly-linked list, higher levels linking each node to its
+/// closest membership-vector prefix-match on either side. This sidesteps
+/// `BaseNode::join` so tests can assert against a correctly-wired graph; the
+/// nodes are marked as joined once wired.
+///
+/// Every node registers itself as the event processor of its network; a
+/// registration failure is irrecoverable and aborts construction.
+pub(crate) fn build_overlay_with<N: Fn(Identifier) -> Box<dyn Network>>(
+    mut ids: Vec<Identifier>,
+    net_factory: N,
+) -> anyhow::Result<Vec<BaseNode>> {
+    if ids.is_empty() {
+        return Err(anyhow::anyhow!("cannot create skip graph with 0 nodes"));
+    }
+    ids.sort();
+
+    let mut nodes = Vec::with_capacity(ids.len());
+    for id in ids {
+        let lt: Box<dyn LookupTable> = Box::new(ArrayLookupTable::new());
+        let core = Box::new(BaseCore::new(
+            span_fixture(),
+            id,
+            random_membership_vector(),
+            random_address(),
+            lt,
+        ));
+        nodes.push(BaseNode::new(span_fixture(), core, net_factory(id))?);
+    }
+
+    wire_lookup_tables(&nodes)?;
+    for node in nodes.iter() {
+        node.mark_joined();
+    }
+    Ok(nodes)
+}
+
+/// Builds a fully wired skip graph of `n` nodes over a fresh `NetworkHub`, with identifiers
+/// evenly spaced across `ZERO..MAX` (see `evenly_spaced_identifiers`) instead of random ones,
+/// so the identifier layout is the same on every run. Membership vectors are still random.
+pub(crate) fn balanced_skip_graph(n: usize) -> anyhow::Result<(Vec<BaseNode>, NetworkHub)> {
+    let hub = NetworkHub::new();
+    let factory_hub = hub.clone();
+    let nodes = build_overlay_with(evenly_spaced_identifiers(n), move |id| {
+        NetworkHub::new_mock_network(factory_hub.clone(), id)
+            .expect("failed to create mock network")
+            .clone_box()
+    })?;
+    Ok((nodes, hub))
+}
+
+/// Picks `n` sorted identifiers strictly between `ZERO` and `MAX` by recursive bisection: the
+/// midpoint of the range is taken, and the remaining identifiers are split as evenly as possible
+/// between the two halves. For `n = 2^k - 1` the identifiers are exactly evenly spaced; otherwise
+/// neighboring gaps differ by at most a factor of two.
+pub(crate) fn evenly_spaced_identifiers(n: usize) -> Vec<Identifier> {
+    fn bisect(lo: Identifier, hi: Identifier, n: usize, out: &mut Vec<Identifier>) {
+        if n == 0 {
+            return;
+        }
+        let mid = lo.midpoint(&hi);
+        let left = (n - 1) / 2;
+        bisect(lo, mid, left, out);
+        out.push(mid);
+        bisect(mid, hi, n - 1 - left, out);
+    }
+
+    let mut ids = Vec::with_capacity(n);
+    bisect(ZERO, MAX, n, &mut ids);
+    ids
+}
+
+/// Returns true if both overlays consist of the same nodes, compared by identity regardless of
+/// order, and each node's lookup table is `equal` to that of its counterpart.
+pub(crate) fn overlays_equal(a: &[BaseNode], b: &[BaseNode]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let sorted = |nodes: &[BaseNode]| {
+        let mut sorted: Vec<BaseNode> = nodes.to_vec();
+        sorted.sort_by_key(|node| node.id());
+        sorted
+    };
+    sorted(a)
+        .iter()
+        .zip(sorted(b).iter())
+        .all(|(x, y)| x.identity() == y.identity() && x.lookup_table().equal(&*y.lookup_table()))
+}
+
+/// Searches from `origin` for `target`, in the direction of the target, over the whole lookup
+/// table, and returns the identifier the search settled on together with the number of network
+/// hops the search took to reach it.
+pub(crate) fn network_search_with_stats(
+    origin: &BaseNode,
+    target: Identifier,
+) -> anyhow::Result<(Identifier, usize)> {
+    let direction = if target > origin.id() {
+        Direction::Right
+    } else {
+        Direction::Left
+    };
+    let req = IdSearchReq::builder(origin.id())
+        .target(target)
+        .direction(direction)
+        .build()?;
+    let outcome = origin.search(req)?;
+    Ok((outcome.identity.id(), outcome.hops))
+}
+
+/// Runs `samples` searches over the overlay, each from a random node for the identifier of a
+/// random node, and returns the number of network hops each search took, in the order they ran.
+/// Panics if a search fails or settles on a node other than its target, as the overlay is then
+/// not correctly wired.
+pub(crate) fn measure_search_depths(nodes: &[BaseNode], samples: usize) -> Vec<usize> {
+    (0..samples)
+        .map(|_| {
+            let origin = &nodes[rand::random_range(0..nodes.len())];
+            let target = nodes[rand::random_range(0..nodes.len())].id();
+            let (result, hops) = network_search_with_stats(origin, target).expect("search failed");
+            assert_eq!(result, target, "search missed its target");
+            hops
+        })
+        .collect()
+}
+
+/// Populates the lookup tables of `nodes` (sorted by identifier) so that, at
+/// every level, each node is linked to its closest predecessor and successor
+/// sharing at least `level` bits of membership-vector prefix.
+fn wire_lookup_tables(nodes: &[BaseNode]) -> anyhow::Result<()> {
+    let lts: Vec<Box<dyn LookupTable>> = nodes.iter().map(|n| n.lookup_table()).collect();
+    let identity = |node: &BaseNode| node.identity();
+
+    // Connects the nodes in a doubly-linked list at level zero, the first node does not have
+    // a previous node and the last node does not have a next node.
+    for (n_pair, lt_pair) in nodes.windows(2).zip(lts.windows(2)) {
+        lt_pair[0].update_entry(identity(&n_pair[1]), 0, Direction::Right)?;
+        lt_pair[1].update_entry(identity(&n_pair[0]), 0, Direction::Left)?;
+    }
+
+    for i in 1..nodes.len() {
+        let mut loop_start = i - 1; // exclude i from considering for its own left neighbor
+        for level in 1..LOOKUP_TABLE_LEVELS {
+            let mut neighbor_idx: Option<usize> = None;
+
+            // moves leftward to find a neighbor at the given level
+            for j in (0..=loop_start).rev() {
+                // Invariant: loop_start < i, so j < i throughout — no self-link possible.
+                if nodes[i].mem_vec().common_prefix_bit(nodes[j].mem_vec()) >= level {
+                    lts[i].update_entry(identity(&nodes[j]), level, Direction::Left)?;
+                    lts[j].update_entry(identity(&nodes[i]), level, Direction::Right)?;
+                    neighbor_idx = Some(j);
+                    break;
+                }
+            }
+            match neighbor_idx {
+                // if a neighbor was found, we continue to search at the next level from the same node
+                Some(j) => loop_start = j,
+                // if no neighbor was found, we stop searching at any other level, as we cannot find at least 'level'-bit common prefix,
+                // hence we cannot find > 'level'-bit common prefix for any other level.
+                None => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::model::search::Nonce;
+    use crate::core::testutil::fixtures::random_sorted_identifiers;
+    use crate::core::{max_useful_level, IdSearchRes, NodeState};
+    use crate::network::mock::hub::NetworkHub;
+    use crate::network::Event;
+
+    /// Verifies that every node built through a mock-network factory is
+    /// registered as the processor of its network: routing an event to each
+    /// node through the hub reaches a processor instead of failing with a
+    /// missing-processor error.
+    #[test]
+    fn test_build_overlay_with_mock_network_factory() {
+        let hub = NetworkHub::new();
+        let ids = random_sorted_identifiers(16);
+        let factory_hub = hub.clone();
+        let nodes = build_overlay_with(ids.clone(), move |id| {
+            NetworkHub::new_mock_network(factory_hub.clone(), id)
+                .expect("failed to create mock network")
+                .clone_box()
+        })
+        .expect("failed to build overlay");
+
+        assert_eq!(nodes.len(), ids.len());
+        for (node, id) in nodes.iter().zip(ids.iter()) {
+            assert_eq!(node.id(), *id);
+
+            // a response nobody is waiting for is accepted and dropped by a registered node.
+            let event = Event::SearchByIdResponse(IdSearchRes {
+                nonce: Nonce::random(),
+                correlation: None,
+                trace_id: rand::random(),
+                hops: 0,
+                target: *id,
+                origin: ids[0],
+                termination_level: 0,
+                result: *id,
+                matched_identity: None,
+                fallback_direction: None,
+            });
+            hub.route_event(ids[0], *id, event)
+                .expect("node should have a registered processor");
+        }
+    }
+
+    /// Approximates an identifier by its most significant 64 bits.
+    fn high_u64(id: &Identifier) -> u64 {
+        u64::from_be_bytes(id.as_bytes()[..8].try_into().unwrap())
+    }
+
+    /// Verifies that the identifiers of a balanced skip graph are sorted, distinct, and evenly
+    /// spaced: exactly for `2^k - 1` nodes, and within a factor of two of the ideal gap otherwise.
+    #[test]
+    fn test_balanced_skip_graph_evenly_spaced() {
+        for n in [1, 7, 15, 16, 100] {
+            let (nodes, _hub) = balanced_skip_graph(n).expect("failed to build balanced overlay");
+            assert_eq!(nodes.len(), n);
+            let ids: Vec<Identifier> = nodes.iter().map(|node| node.id()).collect();
+            assert_eq!(
+                ids,
+                evenly_spaced_identifiers(n),
+                "node order differs from layout"
+            );
+            assert!(
+                ids.windows(2).all(|w| w[0] < w[1]),
+                "identifiers not strictly sorted"
+            );
+
+            // gaps between consecutive identifiers, including the ends of the range.
+            let mut points = vec![0u64];
+            points.extend(ids.iter().map(high_u64));
+            points.push(u64::MAX);
+            let gaps: Vec<u64> = points.windows(2).map(|w| w[1] - w[0]).collect();
+            let ideal = u64::MAX / (n as u64 + 1);
+            for gap in gaps.iter() {
+                if (n + 1).is_power_of_two() {
+                    assert!(
+                        gap.abs_diff(ideal) <= 1,
+                        "gap {gap} differs from ideal {ideal}"
+                    );
+                } else {
+                    assert!(
+                        *gap >= ideal / 2 && *gap <= ideal * 2,
+                        "gap {gap} is not within a factor of two of ideal {ideal}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Exports a node and its lookup table.
+    fn export_node(node: &BaseNode) -> NodeState {
+        NodeState::export_state(&node.identity(), node.lookup_table().as_ref()).unwrap()
+    }
+
+    /// Rebuilds a node exported by `export_node` on the given hub.
+    fn import_node(hub: &NetworkHub, exported: &NodeState) -> BaseNode {
+        let (identity, lt) = exported.import_state().unwrap();
+        let core = Box::new(BaseCore::new(
+            span_fixture(),
+            identity.id(),
+            identity.mem_vec(),
+            identity.address(),
+            Box::new(lt),
+        ));
+        let net = NetworkHub::new_mock_network(hub.clone(), identity.id())
+            .expect("failed to create mock network");
+        BaseNode::new(span_fixture(), core, net.clone_box()).expect("failed to create BaseNode")
+    }
+
+    /// Verifies that an overlay exported node by node and reimported, in reverse order, onto a
+    /// fresh hub is equal to the original, and that changing a single entry breaks the equality.
+    #[test]
+    fn test_overlays_equal_after_export_and_reimport() {
+        let (nodes, _hub) = balanced_skip_graph(16).expect("failed to build balanced overlay");
+        let exported: Vec<_> = nodes.iter().map(export_node).collect();
+
+        let restored_hub = NetworkHub::new();
+        let restored: Vec<BaseNode> = exported
+            .iter()
+            .rev()
+            .map(|e| import_node(&restored_hub, e))
+            .collect();
+        assert!(overlays_equal(&nodes, &restored));
+        assert!(!overlays_equal(&nodes, &restored[1..]));
+
+        restored[0]
+            .lookup_table()
+            .remove_entry(0, Direction::Left)
+            .unwrap();
+        assert!(!overlays_equal(&nodes, &restored));
+    }
+
+    /// Verifies that building an overlay over no identifiers fails.
+    #[test]
+    fn test_build_overlay_with_empty_ids() {
+        let hub = NetworkHub::new();
+        let result = build_overlay_with(Vec::new(), move |id| {
+            NetworkHub::new_mock_network(hub.clone(), id)
+                .expect("failed to create mock network")
+                .clone_box()
+        });
+        assert!(result.is_err(), "building an empty overlay should fail");
+    }
+
+    /// Verifies that searches over a 64-node overlay take, on average, fewer than 2 * log2(64)
+    /// network hops.
+    #[test]
+    fn test_measure_search_depths_logarithmic() {
+        let n: usize = 64;
+        let (nodes, _hub) = balanced_skip_graph(n).expect("failed to build balanced overlay");
+
+        let depths = measure_search_depths(&nodes, 200);
+        assert_eq!(depths.len(), 200);
+        let mean = depths.iter().sum::<usize>() as f64 / depths.len() as f64;
+        let bound = 2.0 * n.ilog2() as f64;
+        assert!(
+            mean < bound,
+            "searches took {mean} hops on average, not below {bound}"
+        );
+    }
+
+    /// Verifies that a search between any two nodes of a 32-node overlay settles on its target
+    /// within twice as many network hops as the overlay has populated levels, as a search takes
+    /// about two hops per level, and that a search for the origin itself takes none.
+    #[test]
+    fn test_network_search_with_stats_bounded_by_levels() {
+        let (nodes, _hub) = balanced_skip_graph(32).expect("failed to build balanced overlay");
+        let mem_vecs: Vec<_> = nodes.iter().map(|node| node.mem_vec()).collect();
+        let levels = mem_vecs
+            .iter()
+            .enumerate()
+            .map(|(i, me)| {
+                let others: Vec<_> = [&mem_vecs[..i], &mem_vecs[i + 1..]].concat();
+                max_useful_level(me, &others)
+            })
+            .max()
+            .unwrap()
+            + 1;
+
+        for origin in &nodes {
+            for target in &nodes {
+                let (result, hops) =
+                    network_search_with_stats(origin, target.id()).expect("search failed");
+                assert_eq!(result, target.id());
+                assert!(
+                    hops <= 2 * levels,
+                    "search from {:?} to {:?} took {hops} hops, beyond twice its {levels} levels",
+                    origin.id(),
+                    target.id()
+                );
+                if origin.id() == target.id() {
+                    assert_eq!(hops, 0);
+                }
+            }
+        }
+    }
+}
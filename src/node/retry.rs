@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+/// SearchError enumerates search failures that callers may need to tell apart from other errors,
+/// e.g., to retry them. It is returned wrapped in `anyhow::Error`, and recovered by downcasting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SearchError {
+    /// The search request could not be sent to the next hop.
+    NetworkSend,
+    /// No response arrived within the time allowed for the attempt.
+    Timeout,
+}
+
+impl SearchError {
+    /// Returns true if the failure may not recur, so that the search is worth retrying.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            SearchError::NetworkSend | SearchError::Timeout => true,
+        }
+    }
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::NetworkSend => write!(f, "failed to send search request"),
+            SearchError::Timeout => write!(f, "search attempt timed out"),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+/// RetryPolicy bounds how a search failing with a transient `SearchError` is retried: up to
+/// `max_attempts` attempts in total, each allowed `attempt_timeout` to get a response, with the
+/// delay before each retry doubling from `base_delay` up to `max_delay`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub attempt_timeout: Duration,
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry following the given attempt, counting attempts from 1.
+    pub fn delay_after(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies the delay doubles from the base delay with every attempt and is capped at the
+    /// maximum delay, including when doubling overflows.
+    #[test]
+    fn test_retry_policy_delay_after() {
+        let policy = RetryPolicy {
+            max_attempts: 100,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            attempt_timeout: Duration::from_secs(1),
+        };
+        assert_eq!(policy.delay_after(1), Duration::from_millis(10));
+        assert_eq!(policy.delay_after(2), Duration::from_millis(20));
+        assert_eq!(policy.delay_after(3), Duration::from_millis(40));
+        assert_eq!(policy.delay_after(4), Duration::from_millis(50));
+        assert_eq!(policy.delay_after(64), Duration::from_millis(50));
+    }
+}
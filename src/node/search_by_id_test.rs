@@ -1,15 +1,27 @@
 use super::base_node::BaseNode;
 use crate::core::model::direction::Direction;
+use crate::core::model::identifier::MAX;
 use crate::core::model::identity::Identity;
-use crate::core::model::search::Nonce;
+use crate::core::testutil::capture::{capture_spans, CapturedSpan};
 use crate::core::testutil::fixtures::{
     random_address, random_identifier, random_identifier_greater_than,
-    random_lookup_table_with_extremes, random_membership_vector, span_fixture,
+    random_lookup_table_with_extremes, random_membership_vector, random_sorted_identifiers,
+    span_fixture,
 };
-use crate::core::{IdSearchReq, Identifier, LookupTable, LOOKUP_TABLE_LEVELS};
-use crate::network::{Event, EventProcessorCore, NetworkMock};
+use crate::core::{
+    ArrayLookupTable, IdSearchReq, IdSearchRes, Identifier, IrrevocableContext, LookupTable,
+    LOOKUP_TABLE_LEVELS,
+};
+use crate::network::mock::hub::NetworkHub;
+use crate::network::{Event, EventProcessorCore, MessageProcessor, Network, NetworkMock};
 use crate::node::core::BaseCore;
+use crate::node::overlay::{balanced_skip_graph, build_overlay_with, evenly_spaced_identifiers};
+use crate::node::retry::{RetryPolicy, SearchError};
+use crate::node::NodeView;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use unimock::*;
 
 /// Verifies the node, acting as an `EventProcessor`, relays an
@@ -30,13 +42,7 @@ fn test_search_by_id_networking_integration_relay() {
     .expect("failed to update entry in lookup table");
 
     let node_id = random_identifier();
-    let search_request = IdSearchReq {
-        nonce: Nonce::random(),
-        origin: node_id,
-        target,
-        level: 0,
-        direction: Direction::Left,
-    };
+    let search_request = IdSearchReq::try_new(node_id, target, 0, Direction::Left).unwrap();
     let request_event = Event::SearchByIdRequest(search_request);
 
     let (expected_lvl, expected_identity) = lt
@@ -73,6 +79,7 @@ fn test_search_by_id_networking_integration_relay() {
         span_fixture(),
         node_id,
         random_membership_vector(),
+        random_address(),
         Box::new(lt.clone()),
     ));
     let node =
@@ -93,13 +100,7 @@ fn test_search_by_id_networking_integration_target_is_this_node() {
     let origin_id = random_identifier();
     let node_id = random_identifier();
 
-    let search_request = IdSearchReq {
-        nonce: Nonce::random(),
-        origin: origin_id,
-        target: node_id,
-        level: 0,
-        direction: Direction::Left,
-    };
+    let search_request = IdSearchReq::try_new(origin_id, node_id, 0, Direction::Left).unwrap();
     let request_event = Event::SearchByIdRequest(search_request);
 
     let mock_net = Unimock::new((
@@ -134,6 +135,7 @@ fn test_search_by_id_networking_integration_target_is_this_node() {
         span_fixture(),
         node_id,
         random_membership_vector(),
+        random_address(),
         Box::new(lt.clone()),
     ));
     let node =
@@ -143,3 +145,515 @@ fn test_search_by_id_networking_integration_target_is_this_node() {
     node.process_incoming_event(outer_origin_id, request_event)
         .expect("failed to process request event");
 }
+
+/// Returns a node whose lookup table reaches the identifier extremes, over a network that accepts
+/// a single relayed search but never answers it, and a context that is cancelled shortly after, so
+/// that a search relayed by the node is cancelled while pending.
+fn node_with_unanswered_relay() -> (BaseNode, IrrevocableContext) {
+    let lt = random_lookup_table_with_extremes(LOOKUP_TABLE_LEVELS);
+
+    // the relay is accepted by the network, but no response ever comes back.
+    let mock_net = Unimock::new((
+        NetworkMock::register_processor
+            .each_call(matching!(_))
+            .answers(&|_, _| Ok(())),
+        NetworkMock::send_event
+            .each_call(matching!(_))
+            .answers(&|_, _, _| Ok(()))
+            .once(),
+        NetworkMock::clone_box
+            .each_call(matching!())
+            .answers(&|mock| Box::new(mock.clone())),
+    ));
+
+    let core = Box::new(BaseCore::new(
+        span_fixture(),
+        random_identifier(),
+        random_membership_vector(),
+        random_address(),
+        Box::new(lt),
+    ));
+    let node =
+        BaseNode::new(span_fixture(), core, Box::new(mock_net)).expect("failed to create BaseNode");
+
+    let ctx = IrrevocableContext::new(&span_fixture(), "cancelled_search_test");
+    let cancel_ctx = ctx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        cancel_ctx.cancel();
+    });
+    (node, ctx)
+}
+
+/// Asserts that `search` on `node` returns the cancellation error rather than a result, and
+/// leaves no pending waiter behind.
+async fn assert_cancelled_mid_search<T: std::fmt::Debug>(
+    node: &BaseNode,
+    search: impl std::future::Future<Output = anyhow::Result<T>>,
+) {
+    let result = tokio::time::timeout(Duration::from_secs(5), search)
+        .await
+        .expect("search did not return after cancellation");
+    let err = result.expect_err("cancelled search should fail");
+    assert_eq!(err.to_string(), "context cancelled");
+    assert_eq!(node.pending_search_count(), 0, "waiter was not removed");
+}
+
+/// Verifies that cancelling the context of an in-flight `search_ctx` aborts the search with a
+/// cancellation error and drops its pending waiter, while the next hop never responds.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_search_ctx_cancelled_mid_search() {
+    let (node, ctx) = node_with_unanswered_relay();
+
+    // the MAX right neighbor of the extremes table is an exact match, so the search is relayed.
+    assert_cancelled_mid_search(&node, node.search_ctx(&ctx, MAX, Direction::Right)).await;
+}
+
+/// Verifies the same for `search_by_id_ctx`, with a request searching only part of the lookup
+/// table.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_search_by_id_ctx_cancelled_mid_search() {
+    let (node, ctx) = node_with_unanswered_relay();
+
+    // the level-0 MAX right neighbor of the extremes table is an exact match, so the search is
+    // relayed.
+    let req = IdSearchReq::try_new(node.id(), MAX, 2, Direction::Right).unwrap();
+    assert_cancelled_mid_search(&node, node.search_by_id_ctx(&ctx, &req)).await;
+}
+
+/// Verifies that a pending `search_by_id_ctx` does not leave its span entered on the polling
+/// thread once a poll returns, so that unrelated work on that thread is not attributed to it.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_search_by_id_ctx_span_not_entered_between_polls() {
+    let (node, ctx) = node_with_unanswered_relay();
+    let req = IdSearchReq::try_new(node.id(), MAX, 2, Direction::Right).unwrap();
+
+    let (current, spans) = capture_spans(|| {
+        let mut search = Box::pin(node.search_by_id_ctx(&ctx, &req));
+        let waker = futures::task::noop_waker();
+        let polled = search
+            .as_mut()
+            .poll(&mut std::task::Context::from_waker(&waker));
+        assert!(polled.is_pending(), "search should wait for the relay");
+        tracing::Span::current().metadata().map(|m| m.name())
+    });
+    assert!(spans.iter().any(|span| span.name == "search_by_id_ctx"));
+    assert_eq!(current, None, "search span is still entered");
+}
+
+/// Verifies that the node terminating a search echoes the request's correlation in its response,
+/// and that the originator only accepts a response carrying the correlation of its own request.
+#[test]
+fn test_search_by_id_response_correlation() {
+    // terminating node: the response echoes the request's correlation.
+    let node_id = random_identifier();
+    let origin_id = random_identifier();
+    let terminal_net = Unimock::new((
+        NetworkMock::register_processor
+            .each_call(matching!(_))
+            .answers(&|_, _| Ok(())),
+        NetworkMock::send_event
+            .each_call(matching!(_))
+            .answers(&|_, _, event: Event| match event {
+                Event::SearchByIdResponse(res) => {
+                    assert_eq!(res.correlation, Some(42));
+                    Ok(())
+                }
+                _ => panic!("expected IdSearchResponse payload, got: {:?}", event),
+            })
+            .once(),
+        NetworkMock::clone_box
+            .each_call(matching!())
+            .answers(&|mock| Box::new(mock.clone())),
+    ));
+    let core = Box::new(BaseCore::new(
+        span_fixture(),
+        node_id,
+        random_membership_vector(),
+        random_address(),
+        Box::new(random_lookup_table_with_extremes(LOOKUP_TABLE_LEVELS)),
+    ));
+    let terminal = BaseNode::new(span_fixture(), core, Box::new(terminal_net))
+        .expect("failed to create BaseNode");
+    let req = IdSearchReq::builder(origin_id)
+        .target(node_id)
+        .direction(Direction::Left)
+        .level(0)
+        .correlation(42)
+        .build()
+        .unwrap();
+    terminal
+        .process_incoming_event(origin_id, Event::SearchByIdRequest(req))
+        .expect("failed to process request event");
+
+    // originating node: the relayed request is captured, then answered.
+    let (relayed_tx, relayed_rx) = std::sync::mpsc::channel::<IdSearchReq>();
+    let relayed_tx = Arc::new(parking_lot::Mutex::new(relayed_tx));
+    let origin_net = Unimock::new((
+        NetworkMock::register_processor
+            .each_call(matching!(_))
+            .answers(&|_, _| Ok(())),
+        NetworkMock::send_event
+            .each_call(matching!(_))
+            .answers_arc(Arc::new(move |_, _, event: Event| match event {
+                Event::SearchByIdRequest(req) => {
+                    relayed_tx.lock().send(req).unwrap();
+                    Ok(())
+                }
+                _ => panic!("expected IdSearchRequest payload, got: {:?}", event),
+            }))
+            .once(),
+        NetworkMock::clone_box
+            .each_call(matching!())
+            .answers(&|mock| Box::new(mock.clone())),
+    ));
+    let core = Box::new(BaseCore::new(
+        span_fixture(),
+        origin_id,
+        random_membership_vector(),
+        random_address(),
+        Box::new(random_lookup_table_with_extremes(LOOKUP_TABLE_LEVELS)),
+    ));
+    let origin = BaseNode::new(span_fixture(), core, Box::new(origin_net))
+        .expect("failed to create BaseNode");
+
+    let searcher = origin.clone();
+    let (result_tx, result_rx) = std::sync::mpsc::channel::<IdSearchRes>();
+    let search_handle = std::thread::spawn(move || {
+        // the MAX right neighbor of the extremes table is an exact match, so the search is relayed.
+        let req = IdSearchReq::builder(searcher.id())
+            .target(MAX)
+            .direction(Direction::Right)
+            .build()
+            .unwrap();
+        let res = searcher.search_by_id(req).expect("failed to search by id");
+        result_tx.send(res).unwrap();
+    });
+
+    let relayed = relayed_rx
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .expect("search was not relayed");
+    let correlation = relayed
+        .correlation
+        .expect("originator should set a correlation");
+    let response = |correlation: u64| {
+        Event::SearchByIdResponse(IdSearchRes {
+            nonce: relayed.nonce,
+            correlation: Some(correlation),
+            trace_id: relayed.trace_id,
+            hops: 0,
+            target: relayed.target,
+            origin: relayed.origin,
+            termination_level: 0,
+            result: MAX,
+            matched_identity: None,
+            fallback_direction: None,
+        })
+    };
+
+    assert!(
+        origin
+            .process_incoming_event(MAX, response(correlation.wrapping_add(1)))
+            .is_err(),
+        "a response with a foreign correlation should be rejected"
+    );
+    assert_eq!(origin.pending_search_count(), 1);
+
+    origin
+        .process_incoming_event(MAX, response(correlation))
+        .expect("failed to process response event");
+    let res = result_rx
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .expect("search did not complete after the response");
+    assert_eq!(res.correlation, Some(correlation));
+    assert_eq!(res.result, MAX);
+
+    // the searcher holds a clone of the mock network, which must be dropped before the original.
+    search_handle.join().expect("search thread panicked");
+}
+
+/// Verifies that a singleton node, having no neighbors, answers a search locally with its own
+/// identifier and reports the search direction as the fallback direction, without any network
+/// traffic.
+#[test]
+fn test_search_by_id_singleton_sets_fallback_direction() {
+    let mock_net = Unimock::new((
+        NetworkMock::register_processor
+            .each_call(matching!(_))
+            .answers(&|_, _| Ok(())),
+        NetworkMock::clone_box
+            .each_call(matching!())
+            .answers(&|mock| Box::new(mock.clone())),
+    ));
+    let node_id = random_identifier();
+    let core = Box::new(BaseCore::new(
+        span_fixture(),
+        node_id,
+        random_membership_vector(),
+        random_address(),
+        Box::new(ArrayLookupTable::new()),
+    ));
+    let node =
+        BaseNode::new(span_fixture(), core, Box::new(mock_net)).expect("failed to create BaseNode");
+
+    for direction in [Direction::Left, Direction::Right] {
+        let req = IdSearchReq::builder(node_id)
+            .target(random_identifier())
+            .direction(direction)
+            .build()
+            .unwrap();
+        let res = node.search_by_id(req).expect("search failed");
+        assert_eq!(res.result, node_id);
+        assert_eq!(res.termination_level, 0);
+        assert_eq!(res.fallback_direction, Some(direction));
+    }
+}
+
+/// Verifies that a search carries one trace id from its origin across every node it traverses:
+/// the originator's span and the spans of the nodes processing the relayed request all record it.
+#[test]
+fn test_search_by_id_trace_id_spans_nodes() {
+    let (nodes, _hub) = balanced_skip_graph(15).expect("failed to build balanced overlay");
+    let origin = &nodes[0];
+    let req = IdSearchReq::builder(origin.id())
+        .target(nodes[nodes.len() - 1].id())
+        .direction(Direction::Right)
+        .build()
+        .unwrap();
+
+    let (res, spans) = capture_spans(|| origin.search_by_id(req));
+    assert_eq!(res.expect("search failed").trace_id, req.trace_id);
+
+    let trace_id = req.trace_id.to_string();
+    let has_trace_id = |span: &&CapturedSpan| span.fields.get("trace_id") == Some(&trace_id);
+    assert!(
+        spans
+            .iter()
+            .filter(has_trace_id)
+            .any(|span| span.name == "search_by_id"),
+        "originator span should record the trace id"
+    );
+    let origin_node = format!("{:?}", origin.id());
+    let relaying_nodes: Vec<&String> = spans
+        .iter()
+        .filter(has_trace_id)
+        .filter(|span| span.name == "search_by_id_request")
+        .filter_map(|span| span.fields.get("node"))
+        .collect();
+    assert!(
+        !relaying_nodes.is_empty(),
+        "the search should have been relayed"
+    );
+    assert!(relaying_nodes.iter().all(|node| **node != origin_node));
+}
+
+/// How `FaultyNetwork` mistreats the search requests it is asked to send.
+#[derive(Copy, Clone, Debug)]
+enum Fault {
+    /// Sending fails.
+    Fail,
+    /// Sending appears to succeed, but the request is never delivered.
+    Drop,
+}
+
+/// FaultyNetwork wraps a network, applying its fault to the first `faults` search requests sent
+/// through it, and passing every other event through untouched.
+struct FaultyNetwork {
+    inner: Box<dyn Network>,
+    fault: Fault,
+    faults: Arc<AtomicUsize>,
+}
+
+impl Network for FaultyNetwork {
+    fn send_event(&self, target_id: Identifier, event: Event) -> anyhow::Result<()> {
+        let faulty = matches!(event, Event::SearchByIdRequest(_))
+            && self
+                .faults
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+        match (faulty, self.fault) {
+            (false, _) => self.inner.send_event(target_id, event),
+            (true, Fault::Fail) => Err(anyhow::anyhow!("injected send failure")),
+            (true, Fault::Drop) => Ok(()),
+        }
+    }
+
+    fn register_processor(&self, processor: MessageProcessor) -> anyhow::Result<()> {
+        self.inner.register_processor(processor)
+    }
+
+    fn clone_box(&self) -> Box<dyn Network> {
+        Box::new(FaultyNetwork {
+            inner: self.inner.clone_box(),
+            fault: self.fault,
+            faults: self.faults.clone(),
+        })
+    }
+}
+
+/// Verifies that a search whose first attempt is lost, either failing to send or never being
+/// answered, is retried and succeeds on the second attempt, leaving no pending waiter behind; and
+/// that a search is given up once every attempt is lost.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_search_with_retry_recovers_from_lost_attempt() {
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(40),
+        attempt_timeout: Duration::from_millis(200),
+    };
+
+    for fault in [Fault::Fail, Fault::Drop] {
+        for (faults, succeeds) in [(1, true), (policy.max_attempts as usize, false)] {
+            let hub = NetworkHub::new();
+            let ids = random_sorted_identifiers(2);
+            let (origin_id, target_id) = (ids[0], ids[1]);
+
+            // the target answers every search with itself, as it has no neighbors.
+            let target_net = NetworkHub::new_mock_network(hub.clone(), target_id).unwrap();
+            let target = Identity::new(target_id, random_membership_vector(), random_address());
+            let target_core = Box::new(BaseCore::new(
+                span_fixture(),
+                target_id,
+                target.mem_vec(),
+                target.address(),
+                Box::new(ArrayLookupTable::new()),
+            ));
+            let _target_node = BaseNode::new(span_fixture(), target_core, target_net.clone_box())
+                .expect("failed to create target node");
+
+            let lt = ArrayLookupTable::new();
+            lt.update_entry(target, 0, Direction::Right).unwrap();
+            let origin_core = Box::new(BaseCore::new(
+                span_fixture(),
+                origin_id,
+                random_membership_vector(),
+                random_address(),
+                Box::new(lt),
+            ));
+            let origin_net = FaultyNetwork {
+                inner: NetworkHub::new_mock_network(hub.clone(), origin_id)
+                    .unwrap()
+                    .clone_box(),
+                fault,
+                faults: Arc::new(AtomicUsize::new(faults)),
+            };
+            let origin = BaseNode::new(span_fixture(), origin_core, Box::new(origin_net))
+                .expect("failed to create origin node");
+
+            let ctx = IrrevocableContext::new(&span_fixture(), "search_with_retry_test");
+            let result = tokio::time::timeout(
+                Duration::from_secs(5),
+                origin.search_with_retry(&ctx, target_id, Direction::Right, policy),
+            )
+            .await
+            .expect("search_with_retry did not return");
+
+            if succeeds {
+                let res = result.unwrap_or_else(|e| panic!("search failed with {fault:?}: {e}"));
+                assert_eq!(res.result, target_id);
+            } else {
+                let err = result.expect_err("search should give up once every attempt is lost");
+                assert!(err.downcast_ref::<SearchError>().is_some());
+            }
+            assert_eq!(origin.pending_search_count(), 0, "waiter was not removed");
+        }
+    }
+}
+
+/// Verifies that over a deterministic hub a search runs only once the hub is driven to
+/// quiescence, as a chain of relays from the origin to the node holding the target followed by
+/// the response back to the origin, and that repeating the search replays the exact same events.
+#[test]
+fn test_search_by_id_deterministic_hub_event_sequence() {
+    let hub = NetworkHub::builder().deterministic().build();
+    let factory_hub = hub.clone();
+    let nodes = build_overlay_with(evenly_spaced_identifiers(15), move |id| {
+        NetworkHub::new_mock_network(factory_hub.clone(), id)
+            .expect("failed to create mock network")
+            .clone_box()
+    })
+    .expect("failed to build overlay");
+    let origin = nodes[0].id();
+    let target = nodes[nodes.len() - 1].id();
+
+    let search = || {
+        let req = IdSearchReq::builder(origin)
+            .target(target)
+            .direction(Direction::Right)
+            .build()
+            .unwrap();
+        hub.route_event(origin, origin, Event::SearchByIdRequest(req))
+            .expect("failed to route search request");
+        hub.run_until_quiescent()
+            .expect("failed to run to quiescence")
+            .into_iter()
+            .map(|e| (e.origin, e.target, e.kind))
+            .collect::<Vec<_>>()
+    };
+
+    let events = search();
+    assert!(events.len() >= 2, "expected a relay and a response");
+    assert_eq!(events[0], (origin, origin, "SearchByIdRequest"));
+    let (requests, response) = events.split_at(events.len() - 1);
+    for pair in requests.windows(2) {
+        assert_eq!(pair[1].0, pair[0].1, "relay does not continue the chain");
+        assert_eq!(pair[1].2, "SearchByIdRequest");
+    }
+    assert_eq!(requests.last().unwrap().1, target);
+    assert_eq!(response[0], (target, origin, "SearchByIdResponse"));
+
+    assert_eq!(search(), events);
+    assert!(hub.run_until_quiescent().unwrap().is_empty());
+    assert!(NetworkHub::new().run_until_quiescent().is_err());
+}
+
+/// Verifies that `search_many` resolves several targets concurrently and returns each result at
+/// the position of its request.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_search_many_preserves_request_order() {
+    let (nodes, _hub) = balanced_skip_graph(15).expect("failed to build balanced overlay");
+    let origin = &nodes[0];
+    let targets: Vec<Identifier> = [3, 14, 1, 9, 6].iter().map(|&i| nodes[i].id()).collect();
+    let reqs: Vec<IdSearchReq> = targets
+        .iter()
+        .map(|&target| {
+            IdSearchReq::try_new(
+                origin.id(),
+                target,
+                LOOKUP_TABLE_LEVELS - 1,
+                Direction::Right,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let results = tokio::time::timeout(Duration::from_secs(5), origin.search_many(reqs.clone()))
+        .await
+        .expect("search_many did not complete");
+
+    assert_eq!(results.len(), reqs.len());
+    for ((req, target), res) in reqs.iter().zip(&targets).zip(results) {
+        let res = res.expect("search failed");
+        assert_eq!(res.nonce, req.nonce);
+        assert_eq!(res.result, *target);
+    }
+}
+
+/// Searches for `target` through a read-only view of a node, which offers no way to join or leave
+/// the overlay.
+fn resolve_through_view(view: &dyn NodeView, target: Identifier) -> anyhow::Result<Identifier> {
+    let req = IdSearchReq::try_new(view.id(), target, LOOKUP_TABLE_LEVELS - 1, Direction::Right)?;
+    Ok(view.search(req)?.identity.id())
+}
+
+/// Verifies that a search issued through a `&dyn NodeView` of a joined node resolves a target
+/// like a search through the node itself.
+#[test]
+fn test_search_through_node_view() {
+    let (nodes, _hub) = balanced_skip_graph(15).expect("failed to build balanced overlay");
+    let target = nodes[9].id();
+
+    let found = resolve_through_view(&nodes[0], target).expect("search through view failed");
+    assert_eq!(found, target);
+}
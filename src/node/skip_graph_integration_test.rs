@@ -1,18 +1,14 @@
 use super::base_node::BaseNode;
 use crate::core::model::direction::Direction;
-use crate::core::model::identity::Identity;
-use crate::core::model::search::Nonce;
 use crate::core::testutil::fixtures::{
-    join_all_with_timeout, join_with_timeout, random_membership_vector, random_sorted_identifiers,
-    span_fixture,
-};
-use crate::core::{
-    Address, ArrayLookupTable, IdSearchReq, Identifier, LookupTable, MembershipVector,
-    LOOKUP_TABLE_LEVELS,
+    join_all_with_timeout, join_with_timeout, random_sorted_identifiers, span_fixture,
+    spawn_in_span,
 };
+use crate::core::{IdSearchReq, Identifier, LookupTable, MembershipVector, LOOKUP_TABLE_LEVELS};
 use crate::network::mock::hub::NetworkHub;
 use crate::network::Network;
-use crate::node::core::BaseCore;
+use crate::node::overlay::build_overlay_with;
+use crate::node::NodeView;
 
 struct LocalSkipGraph {
     nodes: Vec<BaseNode>,
@@ -24,84 +20,18 @@ struct LocalSkipGraph {
 impl LocalSkipGraph {
     /// Builds a fully wired `n`-node skip graph for testing, sharing a single
     /// `NetworkHub`. Each node gets a unique sorted identifier and a random
-    /// membership vector. Lookup tables are populated inline by running
-    /// Algorithm 2 (insert/join, see `skip-graphs-paper.pdf`) — level 0 as a
-    /// doubly-linked list, higher levels linking each node to its closest
-    /// membership-vector prefix-match on either side. Sidesteps the placeholder
-    /// `BaseNode::join` so tests can assert against a correctly-wired graph.
+    /// membership vector; see `build_overlay_with` for how lookup tables are
+    /// populated.
     fn new(n: usize) -> anyhow::Result<Self> {
-        if n == 0 {
-            return Err(anyhow::anyhow!("cannot create skip graph with 0 nodes"));
-        }
-
         let hub = NetworkHub::new();
         let identifiers = random_sorted_identifiers(n);
-        let mut nodes = Vec::with_capacity(n);
-        let mut lts: Vec<Box<dyn LookupTable>> = Vec::with_capacity(n);
-
-        for &id in &identifiers {
-            let mem_vec = random_membership_vector();
-            let lt: Box<dyn LookupTable> = Box::new(ArrayLookupTable::new());
-            let network = NetworkHub::new_mock_network(hub.clone(), id)?;
-            let core = Box::new(BaseCore::new(span_fixture(), id, mem_vec, lt.clone()));
-            let node = BaseNode::new(span_fixture(), core, network.clone_box())?;
-            nodes.push(node);
-            lts.push(lt);
-        }
-
-        // Connects the nodes in a doubly-linked list at level zero, the first node does not have
-        // a previous node and the last node does not have a next node.
-        for (n_pair, lt_pair) in nodes.windows(2).zip(lts.windows(2)) {
-            let this_id = Identity::new(
-                n_pair[1].id(),
-                n_pair[1].mem_vec(),
-                Address::new("localhost", "0"),
-            );
-            let prev_id = Identity::new(
-                n_pair[0].id(),
-                n_pair[0].mem_vec(),
-                Address::new("localhost", "0"),
-            );
-
-            lt_pair[0].update_entry(this_id, 0, Direction::Right)?;
-            lt_pair[1].update_entry(prev_id, 0, Direction::Left)?;
-        }
-
-        for i in 1..n {
-            let mut loop_start = i - 1; // exclude i from considering for its own left neighbor
-            for level in 1..LOOKUP_TABLE_LEVELS {
-                let mut neighbor_idx: Option<usize> = None;
-
-                // moves leftward to find a neighbor at the given level
-                for j in (0..=loop_start).rev() {
-                    // Invariant: loop_start < i, so j < i throughout — no self-link possible.
-                    if nodes[i].mem_vec().common_prefix_bit(nodes[j].mem_vec()) >= level {
-                        let id_j = Identity::new(
-                            nodes[j].id(),
-                            nodes[j].mem_vec(),
-                            Address::new("localhost", "0"),
-                        );
-                        let id_i = Identity::new(
-                            nodes[i].id(),
-                            nodes[i].mem_vec(),
-                            Address::new("localhost", "0"),
-                        );
-                        lts[i].update_entry(id_j, level, Direction::Left)?;
-                        lts[j].update_entry(id_i, level, Direction::Right)?;
-                        neighbor_idx = Some(j);
-                        break;
-                    }
-                }
-                match neighbor_idx {
-                    // if a neighbor was found, we continue to search at the next level from the same node
-                    Some(j) => loop_start = j,
-                    // if no neighbor was found, we stop searching at any other level, as we cannot find at least 'level'-bit common prefix,
-                    // hence we cannot find > 'level'-bit common prefix for any other level.
-                    None => break,
-                }
-            }
-        }
+        let nodes = build_overlay_with(identifiers.clone(), move |id| {
+            NetworkHub::new_mock_network(hub.clone(), id)
+                .expect("failed to create mock network")
+                .clone_box()
+        })?;
 
+        let lts = nodes.iter().map(|n| n.lookup_table()).collect();
         let mvs = nodes.iter().map(|n| n.mem_vec()).collect();
         Ok(LocalSkipGraph {
             nodes,
@@ -172,14 +102,13 @@ fn test_skip_graph_search_by_id() {
     let origin_node = sg.nodes[0].clone();
     let target_id = sg.identifiers[7];
 
-    let handle = std::thread::spawn(move || {
-        let id_search_req = IdSearchReq {
-            nonce: Nonce::random(),
-            target: target_id,
-            origin: origin_node.id(),
-            level: LOOKUP_TABLE_LEVELS - 1,
-            direction: Direction::Right,
-        };
+    let span = tracing::trace_span!(parent: &span_fixture(), "search_thread", target = ?target_id);
+    let handle = spawn_in_span(&span, move || {
+        let id_search_req = IdSearchReq::builder(origin_node.id())
+            .target(target_id)
+            .direction(Direction::Right)
+            .build()
+            .unwrap();
         let result = origin_node
             .search_by_id(id_search_req)
             .expect("failed to search by id");
@@ -199,14 +128,14 @@ fn test_skip_graph_search_by_id_concurrent() {
     for i in 0..8 {
         let origin_node = origin_node.clone();
         let target_id = sg.identifiers[i];
-        let handle = std::thread::spawn(move || {
-            let id_search_req = IdSearchReq {
-                nonce: Nonce::random(),
-                target: target_id,
-                origin: origin_node.id(),
-                level: LOOKUP_TABLE_LEVELS - 1,
-                direction: Direction::Right,
-            };
+        let span =
+            tracing::trace_span!(parent: &span_fixture(), "search_thread", target = ?target_id);
+        let handle = spawn_in_span(&span, move || {
+            let id_search_req = IdSearchReq::builder(origin_node.id())
+                .target(target_id)
+                .direction(Direction::Right)
+                .build()
+                .unwrap();
             let result = origin_node
                 .search_by_id(id_search_req)
                 .expect("failed to search by id");
@@ -221,3 +150,50 @@ fn test_skip_graph_search_by_id_concurrent() {
     )
     .expect("search_by_id did not complete within timeout (likely deadlocked)");
 }
+
+/// Verifies that searches between random pairs of nodes of a 256-node skip graph find their
+/// target within a number of network hops logarithmic in the size of the graph, both per search
+/// and on average, and that a search for the origin itself takes none.
+#[test]
+fn test_skip_graph_search_hop_count() {
+    let n: usize = 256;
+    let log_n = n.ilog2() as usize;
+    let sg = LocalSkipGraph::new(n).expect("failed to initialize a local skip graph");
+
+    let mut total_hops = 0;
+    let searches = 200;
+    for _ in 0..searches {
+        let origin = &sg.nodes[rand::random_range(0..n)];
+        let target = sg.identifiers[rand::random_range(0..n)];
+        let direction = if target > origin.id() {
+            Direction::Right
+        } else {
+            Direction::Left
+        };
+        let req = IdSearchReq::builder(origin.id())
+            .target(target)
+            .direction(direction)
+            .build()
+            .unwrap();
+        let outcome = origin.search(req).expect("failed to search");
+        assert_eq!(outcome.identity.id(), target);
+        assert!(
+            outcome.hops <= 4 * log_n,
+            "search from {:?} to {:?} took {} hops",
+            origin.id(),
+            target,
+            outcome.hops
+        );
+        if target == origin.id() {
+            assert_eq!(outcome.hops, 0);
+        }
+        total_hops += outcome.hops;
+    }
+    // a skip graph search takes about log2(n) hops on average; the bounds leave room for the
+    // randomness of the membership vectors.
+    let mean = total_hops as f64 / searches as f64;
+    assert!(
+        mean <= 1.5 * log_n as f64,
+        "searches took {mean} hops on average, for log2(n) = {log_n}"
+    );
+}